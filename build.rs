@@ -40,6 +40,20 @@ impl fmt::Display for PythonVersion {
 
 const PY3_MIN_MINOR: u8 = 5;
 
+/// Minimum python 3 minor version of the stable ABI build (`abi3` features),
+/// the lowest enabled `abi3-py3x` feature wins
+fn abi3_min_minor() -> Option<u8> {
+    if env::var_os("CARGO_FEATURE_ABI3").is_none() {
+        return None;
+    }
+    for minor in PY3_MIN_MINOR..8 {
+        if env::var_os(format!("CARGO_FEATURE_ABI3_PY3{}", minor)).is_some() {
+            return Some(minor);
+        }
+    }
+    Some(PY3_MIN_MINOR)
+}
+
 const CFG_KEY: &'static str = "py_sys_config";
 
 // windows' python writes out lines with the windows crlf sequence;
@@ -361,7 +375,14 @@ fn configure_from_path(expected_version: &PythonVersion) -> Result<(String, Stri
     let exec_prefix: &str = &lines[4];
 
     let is_extension_module = env::var_os("CARGO_FEATURE_EXTENSION_MODULE").is_some();
-    if !is_extension_module || cfg!(target_os = "windows") {
+    let abi3 = abi3_min_minor();
+    if abi3.is_some() && interpreter_version.major == 3 && cfg!(target_os = "windows") {
+        // the stable ABI is exported by python3.dll
+        println!("cargo:rustc-link-lib=pythonXY:python3");
+        if libpath == "None" {
+            println!("cargo:rustc-link-search=native={}\\libs", exec_prefix);
+        }
+    } else if !is_extension_module || cfg!(target_os = "windows") {
         println!(
             "{}",
             get_rustc_link_lib(&interpreter_version, ld_version, enable_shared == "1").unwrap()
@@ -380,7 +401,7 @@ fn configure_from_path(expected_version: &PythonVersion) -> Result<(String, Stri
         minor: some_minor,
     } = interpreter_version
     {
-        if env::var_os("CARGO_FEATURE_PEP_384").is_some() {
+        if abi3.is_some() || env::var_os("CARGO_FEATURE_PEP_384").is_some() {
             println!("cargo:rustc-cfg=Py_LIMITED_API");
         }
         if let Some(minor) = some_minor {
@@ -390,6 +411,17 @@ fn configure_from_path(expected_version: &PythonVersion) -> Result<(String, Stri
                     PY3_MIN_MINOR, minor
                 ));
             }
+            // the stable ABI build uses the API of the minimum version only
+            let minor = match abi3 {
+                Some(abi3_minor) if abi3_minor > minor => {
+                    return Err(format!(
+                        "abi3 minimum version is 3.{}, current version is 3.{}",
+                        abi3_minor, minor
+                    ))
+                }
+                Some(abi3_minor) => abi3_minor,
+                None => minor,
+            };
             for i in 5..(minor + 1) {
                 println!("cargo:rustc-cfg=Py_3_{}", i);
                 flags += format!("CFG_Py_3_{},", i).as_ref();
@@ -3,11 +3,15 @@ use syn;
 
 #[derive(Debug, PartialEq)]
 pub enum Argument {
+    /// `"/"`, the preceding arguments are positional only
+    PosOnlySeparator,
     VarArgsSeparator,
     VarArgs(String),
     KeywordArgs(String),
     Arg(String, Option<String>),
     Kwarg(String, String),
+    /// Python name of the argument, `arg(name = "...")`
+    Name(String, String),
 }
 
 pub fn parse_arguments(items: &[syn::NestedMetaItem]) -> Vec<Argument> {
@@ -15,6 +19,7 @@ pub fn parse_arguments(items: &[syn::NestedMetaItem]) -> Vec<Argument> {
     let mut has_kw = false;
     let mut has_varargs = false;
     let mut has_kwargs = false;
+    let mut has_pos_only = false;
 
     let args_str = quote! {
         #(#items),*
@@ -37,7 +42,7 @@ pub fn parse_arguments(items: &[syn::NestedMetaItem]) -> Vec<Argument> {
             }
             &syn::NestedMetaItem::MetaItem(syn::MetaItem::NameValue(ref ident, ref lit)) => {
                 let name = ident.as_ref().to_owned();
-                match lit {
+                let default = match lit {
                     &syn::Lit::Str(ref s, _) => {
                         if s == "*" {  // #[args(args="*")]
                             if has_kwargs {
@@ -51,6 +56,7 @@ pub fn parse_arguments(items: &[syn::NestedMetaItem]) -> Vec<Argument> {
                             }
                             has_varargs = true;
                             arguments.push(Argument::VarArgs(name));
+                            continue
                         }
                         else if s == "**" {  // #[args(kwargs="**")]
                             if has_kwargs {
@@ -60,48 +66,53 @@ pub fn parse_arguments(items: &[syn::NestedMetaItem]) -> Vec<Argument> {
                             }
                             has_kwargs = true;
                             arguments.push(Argument::KeywordArgs(name));
-                        } else {
-                            if has_varargs {
-                                arguments.push(Argument::Kwarg(name, s.clone()))
-                            } else {
-                                if has_kwargs {
-                                    println!("syntax error, keyword arguments is defined: {:?}",
-                                             args_str);
-                                    return Vec::new()
-                                }
-                                has_kw = true;
-                                arguments.push(Argument::Arg(name, Some(s.clone())))
-                            }
+                            continue
                         }
+                        s.clone()
                     }
-                    &syn::Lit::Int(ref s, _) => {
-                        if has_varargs {
-                            arguments.push(Argument::Kwarg(name, format!("{}", s)));
-                        } else {
-                            if has_kwargs {
-                                println!("syntax error, keyword arguments is defined: {:?}",
-                                         args_str);
-                                return Vec::new()
-                            }
-                            has_kw = true;
-                            arguments.push(Argument::Arg(name, Some(format!("{}", s))));
-                        }
+                    &syn::Lit::Int(ref s, _) => format!("{}", s),
+                    &syn::Lit::Float(ref s, _) => s.clone(),
+                    &syn::Lit::Bool(ref b) => format!("{}", b),
+                    _ => {
+                        println!("Only string literal is supported, got: {:?}", lit);
+                        return Vec::new()
                     }
-                    &syn::Lit::Bool(ref b) => {
-                        if has_varargs {
-                            arguments.push(Argument::Kwarg(name, format!("{}", b)));
-                        } else {
-                            if has_kwargs {
-                                println!("syntax error, keyword arguments is defined: {:?}",
-                                         args_str);
-                                return Vec::new()
+                };
+                if !add_default(&mut arguments, name, default,
+                                has_varargs, has_kwargs, &mut has_kw, &args_str) {
+                    return Vec::new()
+                }
+            }
+            &syn::NestedMetaItem::MetaItem(syn::MetaItem::List(ref ident, ref meta)) => {
+                // #[args(arg(name = "py_name", default = "expr"))]
+                let name = ident.as_ref().to_owned();
+                let mut default = None;
+                for item in meta.iter() {
+                    match item {
+                        &syn::NestedMetaItem::MetaItem(syn::MetaItem::Word(ref key))
+                            if key.as_ref() == "default" =>
+                            default = Some("Default::default()".to_owned()),
+                        &syn::NestedMetaItem::MetaItem(
+                            syn::MetaItem::NameValue(ref key, syn::Lit::Str(ref s, _))) => {
+                            match key.as_ref() {
+                                "name" => arguments.push(Argument::Name(name.clone(), s.clone())),
+                                "default" => default = Some(s.clone()),
+                                _ => {
+                                    println!("Unknown argument option {:?}, args: {:?}",
+                                             key, args_str);
+                                    return Vec::new()
+                                }
                             }
-                            has_kw = true;
-                            arguments.push(Argument::Arg(name, Some(format!("{}", b))));
+                        }
+                        _ => {
+                            println!("Unknown argument option {:?}, args: {:?}", item, args_str);
+                            return Vec::new()
                         }
                     }
-                    _ => {
-                        println!("Only string literal is supported, got: {:?}", lit);
+                }
+                if let Some(default) = default {
+                    if !add_default(&mut arguments, name, default,
+                                    has_varargs, has_kwargs, &mut has_kw, &args_str) {
                         return Vec::new()
                     }
                 }
@@ -125,6 +136,16 @@ pub fn parse_arguments(items: &[syn::NestedMetaItem]) -> Vec<Argument> {
                             }
                             has_varargs = true;
                             arguments.push(Argument::VarArgsSeparator);
+                        } else if s == "/" {
+                            // #[args(a, b, "/")]
+                            if has_varargs || has_kwargs || has_pos_only {
+                                println!(
+                                    "syntax error, / is allowed once before * and **: {:?}",
+                                    args_str);
+                                return Vec::new()
+                            }
+                            has_pos_only = true;
+                            arguments.push(Argument::PosOnlySeparator);
                         } else {
                             println!("Unknown string literal, got: {:?} args: {:?}",
                                      s, args_str);
@@ -138,16 +159,29 @@ pub fn parse_arguments(items: &[syn::NestedMetaItem]) -> Vec<Argument> {
                     }
                 }
             }
-            _ => {
-                println!("Unknown argument, got: {:?} args: {:?}", item, args_str);
-                return Vec::new()
-            }
         }
     }
 
     arguments
 }
 
+/// Adds argument with default value, arguments after `*` are keyword only
+fn add_default(arguments: &mut Vec<Argument>, name: String, default: String,
+               has_varargs: bool, has_kwargs: bool, has_kw: &mut bool, args_str: &str) -> bool
+{
+    if has_varargs {
+        arguments.push(Argument::Kwarg(name, default));
+    } else {
+        if has_kwargs {
+            println!("syntax error, keyword arguments is defined: {:?}", args_str);
+            return false
+        }
+        *has_kw = true;
+        arguments.push(Argument::Arg(name, Some(default)));
+    }
+    true
+}
+
 
 #[cfg(test)]
 mod test {
@@ -201,4 +235,20 @@ mod test {
                              Argument::Kwarg("test3".to_owned(), "None".to_owned()),
                              Argument::KeywordArgs("kwargs".to_owned())]);
     }
+
+    #[test]
+    fn test_pos_only_and_options() {
+        let args = parse_arguments(
+            &items("#[args(a, \"/\", type_(name = \"type\", default), \"*\", \
+                    c(default = \"u8::max_value()\"), d = 1.5)]"));
+        assert!(args == vec![Argument::Arg("a".to_owned(), None),
+                             Argument::PosOnlySeparator,
+                             Argument::Name("type_".to_owned(), "type".to_owned()),
+                             Argument::Arg("type_".to_owned(),
+                                           Some("Default::default()".to_owned())),
+                             Argument::VarArgsSeparator,
+                             Argument::Kwarg("c".to_owned(), "u8::max_value()".to_owned()),
+                             Argument::Kwarg("d".to_owned(), "1.5".to_owned())]);
+        assert!(parse_arguments(&items("#[args(\"*\", a, \"/\")]")).is_empty());
+    }
 }
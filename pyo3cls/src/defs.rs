@@ -285,20 +285,22 @@ pub const SEQ: Proto = Proto {
             name: "__len__",
             pyres: false,
             proto: "pyo3::class::sequence::PySequenceLenProtocol"},
-        MethodProto::Unary{
+        MethodProto::Binary{
             name: "__getitem__",
+            arg: "Index",
             pyres: true,
             proto: "pyo3::class::sequence::PySequenceGetItemProtocol"},
-        MethodProto::Binary{
+        MethodProto::Ternary{
             name: "__setitem__",
-            arg: "Value",
+            arg1: "Index",
+            arg2: "Value",
             pyres: false,
-            proto: "pyo3::class::sequence::PyMappingSetItemProtocol"},
+            proto: "pyo3::class::sequence::PySequenceSetItemProtocol"},
         MethodProto::Binary{
             name: "__delitem__",
-            arg: "Key",
+            arg: "Index",
             pyres: false,
-            proto: "pyo3::class::mapping::PyMappingDelItemProtocol"},
+            proto: "pyo3::class::sequence::PySequenceDelItemProtocol"},
         MethodProto::Binary{
             name: "__contains__",
             arg: "Item",
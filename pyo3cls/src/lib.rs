@@ -22,6 +22,7 @@ mod defs;
 mod func;
 mod method;
 mod module;
+mod stub;
 mod utils;
 
 
@@ -63,6 +64,43 @@ pub fn mod3init(attr: TokenStream, input: TokenStream) -> TokenStream {
     TokenStream::from_str(s.as_str()).unwrap()
 }
 
+#[proc_macro_attribute]
+pub fn function(attr: TokenStream, input: TokenStream) -> TokenStream {
+    // Construct a string representation of the type definition
+    let source = input.to_string();
+
+    // Parse the string representation into a syntax tree
+    let mut ast = syn::parse_item(&source).unwrap();
+
+    // Build the output
+    let expanded = module::build_py_function(&mut ast, utils::attr_with_parentheses(attr));
+
+    // Return the generated impl as a TokenStream
+    let mut tokens = Tokens::new();
+    ast.to_tokens(&mut tokens);
+    let s = String::from(tokens.as_str()) + expanded.as_str();
+
+    TokenStream::from_str(s.as_str()).unwrap()
+}
+
+#[proc_macro_attribute]
+pub fn submodule(_: TokenStream, input: TokenStream) -> TokenStream {
+    // Construct a string representation of the type definition
+    let source = input.to_string();
+
+    // Parse the string representation into a syntax tree
+    let mut ast = syn::parse_item(&source).unwrap();
+
+    // Expand `#[pyfn]` items of the function body
+    module::build_submodule(&mut ast);
+
+    // Return the function as a TokenStream
+    let mut tokens = Tokens::new();
+    ast.to_tokens(&mut tokens);
+
+    TokenStream::from_str(tokens.as_str()).unwrap()
+}
+
 #[proc_macro_attribute]
 pub fn proto(_: TokenStream, input: TokenStream) -> TokenStream {
     // Construct a string representation of the type definition
@@ -4,6 +4,8 @@ use syn;
 use quote::{Tokens, Ident};
 
 use args::{Argument, parse_arguments};
+use py_method::variadic_params;
+use stub;
 use utils::for_err_msg;
 
 
@@ -21,12 +23,14 @@ pub struct FnArg<'a> {
 pub enum FnType {
     Getter(Option<String>),
     Setter(Option<String>),
+    Deleter(Option<String>),
     Fn,
     FnNew,
     FnInit,
     FnCall,
     FnClass,
     FnStatic,
+    ClassAttribute,
 }
 
 pub struct FnSpec<'a> {
@@ -34,6 +38,8 @@ pub struct FnSpec<'a> {
     pub attrs: Vec<Argument>,
     pub args: Vec<FnArg<'a>>,
     pub output: syn::Ty,
+    /// stub annotations from `#[stub(...)]`, by argument name or `returns`
+    pub stub: Vec<(String, String)>,
 }
 
 impl<'a> FnSpec<'a> {
@@ -42,7 +48,7 @@ impl<'a> FnSpec<'a> {
     pub fn parse(name: &'a syn::Ident,
                  sig: &'a syn::MethodSig,
                  meth_attrs: &'a mut Vec<syn::Attribute>) -> FnSpec<'a> {
-        let (fn_type, fn_attrs) = parse_attributes(meth_attrs);
+        let (fn_type, fn_attrs, stub) = parse_attributes(meth_attrs);
 
         let mut has_self = false;
         let mut arguments = Vec::new();
@@ -106,6 +112,7 @@ impl<'a> FnSpec<'a> {
             attrs: fn_attrs,
             args: arguments,
             output: ty,
+            stub: stub,
         }
     }
 
@@ -152,21 +159,33 @@ impl<'a> FnSpec<'a> {
         false
     }
 
+    /// `METH_FASTCALL` wrapper can be generated, the arguments do not get
+    /// collected into `*args` tuple or `**kwargs` dict
+    pub fn fastcall(&self) -> bool {
+        !self.args.iter().all(|arg| arg.py) && !self.accept_args() && !self.accept_kwargs()
+    }
+
     pub fn default_value(&self, name: &syn::Ident) -> Option<Tokens> {
+        self.default_expr(name).map(|val| {
+            let i = Ident::from(val);
+            quote!(#i)
+        })
+    }
+
+    /// Rust expression of the default value
+    fn default_expr(&self, name: &syn::Ident) -> Option<&str> {
         for s in self.attrs.iter() {
             match *s {
                 Argument::Arg(ref ident, ref opt) => {
                     if ident.as_str() == name.as_ref() {
                         if let &Some(ref val) = opt {
-                            let i = Ident::from(val.as_str());
-                            return Some(quote!(#i))
+                            return Some(val.as_str())
                         }
                     }
                 },
                 Argument::Kwarg(ref ident, ref opt) => {
                     if ident.as_str() == name.as_ref() {
-                        let i = Ident::from(opt.as_str());
-                        return Some(quote!(#i))
+                        return Some(opt.as_str())
                     }
                 },
                 _ => (),
@@ -175,6 +194,125 @@ impl<'a> FnSpec<'a> {
         None
     }
 
+    /// Signature line for `__text_signature__`, i.e. `name($self, a, b=1, *args, c=2)`.
+    /// `first` is the bound argument, `$self` or `$cls`.
+    pub fn text_signature(&self, name: &str, first: Option<&str>) -> String {
+        format!("{}({})", name, self.params(first, false).join(", "))
+    }
+
+    /// Parameters of the stub, i.e. `self, a: int, b: int = 1, *args: Any`
+    pub fn stub_params(&self, first: Option<&str>) -> String {
+        self.params(first, true).join(", ")
+    }
+
+    /// Annotation of the return value, `PyResult<T>` is annotated as `T`
+    pub fn stub_returns(&self) -> String {
+        self.stub_override("returns").unwrap_or_else(|| stub::annotation(&self.output))
+    }
+
+    fn stub_override(&self, name: &str) -> Option<String> {
+        self.stub.iter().find(|&&(ref key, _)| key == name).map(|&(_, ref value)| value.clone())
+    }
+
+    fn params(&self, first: Option<&str>, annotate: bool) -> Vec<String> {
+        let mut params: Vec<String> = first.into_iter().map(|s| s.to_owned()).collect();
+        let mut kw_only = false;
+        let mut pos_only = false;
+
+        for arg in self.args.iter() {
+            if arg.py {
+                continue
+            }
+            if self.is_pos_only(arg.name) {
+                pos_only = true;
+            } else if pos_only {
+                params.push("/".to_owned());
+                pos_only = false;
+            }
+            let name = self.py_name(arg.name);
+            let annotation = |default: &str| if annotate {
+                let ann = self.stub_override(arg.name.as_ref())
+                    .unwrap_or_else(|| default.to_owned());
+                format!(": {}", ann)
+            } else {
+                String::new()
+            };
+            // typed variadics are annotated with the type of the item
+            if self.is_args(arg.name) {
+                let item = match variadic_params(arg.ty, &["Vec"]) {
+                    Some(ref params) if params.len() == 1 => stub::annotation(params[0]),
+                    _ => "Any".to_owned(),
+                };
+                params.push(format!("*{}{}", arg.name, annotation(&item)));
+                kw_only = true;
+                continue
+            }
+            if self.is_kwargs(arg.name) {
+                let item = match variadic_params(arg.ty, &["HashMap", "BTreeMap"]) {
+                    Some(ref params) if params.len() >= 2 => stub::annotation(params[1]),
+                    _ => "Any".to_owned(),
+                };
+                params.push(format!("**{}{}", arg.name, annotation(&item)));
+                continue
+            }
+            if self.is_kw_only(arg.name) && !kw_only {
+                params.push("*".to_owned());
+                kw_only = true;
+            }
+            let ann = annotation(&stub::annotation(arg.ty));
+            let eq = if annotate { " = " } else { "=" };
+            match self.default_expr(arg.name) {
+                Some(expr) => params.push(
+                    format!("{}{}{}{}", name, ann, eq, python_default(expr))),
+                None if arg.optional.is_some() => params.push(
+                    format!("{}{}{}None", name, ann, eq)),
+                None => params.push(format!("{}{}", name, ann)),
+            }
+        }
+        if pos_only {
+            params.push("/".to_owned());
+        }
+        params
+    }
+
+    /// Python name of the argument, `arg(name = "...")` renames it
+    pub fn py_name(&self, name: &syn::Ident) -> String {
+        for s in self.attrs.iter() {
+            match *s {
+                Argument::Name(ref ident, ref py_name) if ident.as_str() == name.as_ref() =>
+                    return py_name.clone(),
+                _ => (),
+            }
+        }
+        name.as_ref().to_owned()
+    }
+
+    /// Arguments up to the last one listed before `"/"` are positional only
+    pub fn is_pos_only(&self, name: &syn::Ident) -> bool {
+        let mut listed = Vec::new();
+        let mut separator = false;
+        for s in self.attrs.iter() {
+            match *s {
+                Argument::PosOnlySeparator => {
+                    separator = true;
+                    break
+                }
+                Argument::Arg(ref ident, _) | Argument::Name(ref ident, _) =>
+                    listed.push(ident.as_str()),
+                _ => (),
+            }
+        }
+        if !separator {
+            return false
+        }
+        let last = self.args.iter().rposition(|arg| listed.contains(&arg.name.as_ref()));
+        let idx = self.args.iter().position(|arg| arg.name == name);
+        match (idx, last) {
+            (Some(idx), Some(last)) => idx <= last,
+            _ => false,
+        }
+    }
+
     pub fn is_kw_only(&self, name: &syn::Ident) -> bool {
         for s in self.attrs.iter() {
             match *s {
@@ -190,6 +328,26 @@ impl<'a> FnSpec<'a> {
     }
 }
 
+/// Converts rust default expression to python literal,
+/// expressions without python equivalent are shown as `...`
+fn python_default(expr: &str) -> String {
+    let expr = expr.trim();
+    match expr {
+        "true" => "True".to_owned(),
+        "false" => "False".to_owned(),
+        "None" => "None".to_owned(),
+        _ => {
+            let is_number = expr.parse::<i64>().is_ok() || expr.parse::<f64>().is_ok();
+            let is_str = expr.len() >= 2 && expr.starts_with('"') && expr.ends_with('"');
+            if is_number || is_str {
+                expr.to_owned()
+            } else {
+                "...".to_owned()
+            }
+        }
+    }
+}
+
 pub fn is_ref<'a>(name: &'a syn::Ident, ty: &'a syn::Ty) -> bool {
     match ty {
         &syn::Ty::Rptr(_, _) => {
@@ -277,9 +435,11 @@ pub fn check_arg_ty_and_optional<'a>(name: &'a syn::Ident, ty: &'a syn::Ty)
     }
 }
 
-fn parse_attributes(attrs: &mut Vec<syn::Attribute>) -> (FnType, Vec<Argument>) {
+fn parse_attributes(attrs: &mut Vec<syn::Attribute>)
+                    -> (FnType, Vec<Argument>, Vec<(String, String)>) {
     let mut new_attrs = Vec::new();
     let mut spec = Vec::new();
+    let mut stub = Vec::new();
     let mut res: Option<FnType> = None;
 
     for attr in attrs.iter() {
@@ -301,18 +461,21 @@ fn parse_attributes(attrs: &mut Vec<syn::Attribute>) -> (FnType, Vec<Argument>)
                     "staticmethod" => {
                         res = Some(FnType::FnStatic)
                     },
-                    "setter" | "getter" => {
+                    "classattr" => {
+                        res = Some(FnType::ClassAttribute)
+                    },
+                    "setter" | "getter" | "deleter" => {
                         if attr.style == syn::AttrStyle::Inner {
                             panic!("Inner style attribute is not
-                                    supported for setter and getter");
+                                    supported for setter, getter and deleter");
                         }
                         if res != None {
-                            panic!("setter/getter attribute can not be used mutiple times");
+                            panic!("setter/getter/deleter attribute can not be used mutiple times");
                         }
-                        if name.as_ref() == "setter" {
-                            res = Some(FnType::Setter(None))
-                        } else {
-                            res = Some(FnType::Getter(None))
+                        match name.as_ref() {
+                            "setter" => res = Some(FnType::Setter(None)),
+                            "deleter" => res = Some(FnType::Deleter(None)),
+                            _ => res = Some(FnType::Getter(None)),
                         }
                     },
                     _ => {
@@ -331,47 +494,48 @@ fn parse_attributes(attrs: &mut Vec<syn::Attribute>) -> (FnType, Vec<Argument>)
                     "call" => {
                         res = Some(FnType::FnCall)
                     },
-                    "setter" | "getter" => {
+                    "setter" | "getter" | "deleter" => {
                         if attr.style == syn::AttrStyle::Inner {
                             panic!("Inner style attribute is not
-                                    supported for setter and getter");
+                                    supported for setter, getter and deleter");
                         }
                         if res != None {
-                            panic!("setter/getter attribute can not be used mutiple times");
+                            panic!("setter/getter/deleter attribute can not be used mutiple times");
                         }
                         if meta.len() != 1 {
-                            panic!("setter/getter requires one value");
+                            panic!("setter/getter/deleter requires one value");
                         }
-                        match *meta.first().unwrap() {
+                        let py_name = match *meta.first().unwrap() {
                             syn::NestedMetaItem::MetaItem(syn::MetaItem::Word(ref w)) => {
-                                if name.as_ref() == "setter" {
-                                    res = Some(FnType::Setter(Some(w.to_string())))
-                                } else {
-                                    res = Some(FnType::Getter(Some(w.to_string())))
-                                }
+                                Some(w.to_string())
                             },
                             syn::NestedMetaItem::Literal(ref lit) => {
                                 match *lit {
                                     syn::Lit::Str(ref s, syn::StrStyle::Cooked) => {
-                                        if name.as_ref() == "setter" {
-                                            res = Some(FnType::Setter(Some(s.clone())))
-                                        } else {
-                                            res = Some(FnType::Getter(Some(s.clone())))
-                                        }
+                                        Some(s.clone())
                                     },
                                     _ => {
-                                        panic!("setter/getter attribute requires str value");
+                                        panic!("setter/getter/deleter attribute requires str value");
                                     },
                                 }
                             }
                             _ => {
                                 println!("cannot parse {:?} attribute: {:?}", name, meta);
+                                None
                             },
+                        };
+                        match name.as_ref() {
+                            "setter" => res = Some(FnType::Setter(py_name)),
+                            "deleter" => res = Some(FnType::Deleter(py_name)),
+                            _ => res = Some(FnType::Getter(py_name)),
                         }
                     },
                     "args" => {
                         spec.extend(parse_arguments(meta.as_slice()))
                     }
+                    "stub" => {
+                        stub.extend(stub::parse_stub_attr(meta.as_slice()))
+                    }
                     _ => {
                         new_attrs.push(attr.clone())
                     }
@@ -386,7 +550,7 @@ fn parse_attributes(attrs: &mut Vec<syn::Attribute>) -> (FnType, Vec<Argument>)
     attrs.extend(new_attrs);
 
     match res {
-        Some(tp) => (tp, spec),
-        None => (FnType::Fn, spec),
+        Some(tp) => (tp, spec, stub),
+        None => (FnType::Fn, spec, stub),
     }
 }
@@ -6,37 +6,101 @@ use quote::Tokens;
 use args;
 use method;
 use py_method;
+use stub;
 use utils;
 
 
-pub fn build_py3_module_init(ast: &mut syn::Item, attr: String) -> Tokens {
-    let modname = &attr[1..attr.len()-1].to_string();
+/// Parameters of `#[modinit(name, multi_phase, state = "Type")]`
+struct ModInitAttr {
+    name: String,
+    multi_phase: bool,
+    state: Option<syn::Ty>,
+}
 
-    match ast.node {
-        syn::ItemKind::Fn(_, _, _, _, _, ref mut block) => {
-            let mut stmts = Vec::new();
-            for stmt in block.stmts.iter_mut() {
-                match stmt {
-                    &mut syn::Stmt::Item(ref mut item) => {
-                        if let Some(block) = wrap_fn(item) {
-                            for stmt in block.stmts.iter() {
-                                stmts.push(stmt.clone());
-                            }
-                            continue
-                        }
+fn parse_modinit_attr(attr: &str) -> ModInitAttr {
+    let mut result = ModInitAttr { name: String::new(), multi_phase: false, state: None };
+
+    let attr = syn::parse_outer_attr(&format!("#[modinit{}]", attr))
+        .expect("can not parse 'modinit' params");
+    if let syn::MetaItem::List(_, ref meta) = attr.value {
+        for item in meta.iter() {
+            match *item {
+                syn::NestedMetaItem::MetaItem(syn::MetaItem::Word(ref ident)) => {
+                    if result.name.is_empty() {
+                        result.name = ident.as_ref().to_owned();
+                    } else if ident.as_ref() == "multi_phase" {
+                        result.multi_phase = true;
+                    } else {
+                        panic!("unknown #[modinit] parameter: {}", ident);
                     }
-                    _ => (),
                 }
-                stmts.push(stmt.clone());
+                syn::NestedMetaItem::MetaItem(
+                    syn::MetaItem::NameValue(ref key, syn::Lit::Str(ref s, _)))
+                    if key.as_ref() == "state" =>
+                {
+                    let ty = syn::parse_type(s).expect("can not parse module state type");
+                    result.state = Some(ty);
+                    result.multi_phase = true;
+                }
+                _ => panic!("unsupported #[modinit] parameter: {:?}", item),
             }
-            block.stmts = stmts;
+        }
+    }
+    if result.name.is_empty() {
+        panic!("#[modinit] requires the module name");
+    }
+    result
+}
+
+pub fn build_py3_module_init(ast: &mut syn::Item, attr: String) -> Tokens {
+    let attr = parse_modinit_attr(&attr);
 
-            py3_init(&ast.ident, &modname, utils::get_doc(&ast.attrs, false))
+    match ast.node {
+        syn::ItemKind::Fn(_, _, _, _, _, ref mut block) => {
+            expand_pyfns(block);
+
+            let doc = utils::get_doc(&ast.attrs, false);
+            if attr.multi_phase {
+                // the unit state holds the heap types of per-interpreter classes
+                let state = attr.state.clone()
+                    .unwrap_or_else(|| syn::parse_type("()").expect("unit type"));
+                py3_multi_phase_init(&ast.ident, &attr.name, doc, &state)
+            } else {
+                py3_init(&ast.ident, &attr.name, doc)
+            }
         },
         _ => panic!("#[modinit] can only be used with fn block"),
     }
 }
 
+/// `#[submodule]` function, the `#[pyfn]` items are expanded like in `#[modinit]`
+pub fn build_submodule(ast: &mut syn::Item) {
+    match ast.node {
+        syn::ItemKind::Fn(_, _, _, _, _, ref mut block) => expand_pyfns(block),
+        _ => panic!("#[submodule] can only be used with fn block"),
+    }
+}
+
+/// Replaces the `#[pyfn]` items with the code adding the functions to the module
+fn expand_pyfns(block: &mut syn::Block) {
+    let mut stmts = Vec::new();
+    for stmt in block.stmts.iter_mut() {
+        match stmt {
+            &mut syn::Stmt::Item(ref mut item) => {
+                if let Some(block) = wrap_fn(item) {
+                    for stmt in block.stmts.iter() {
+                        stmts.push(stmt.clone());
+                    }
+                    continue
+                }
+            }
+            _ => (),
+        }
+        stmts.push(stmt.clone());
+    }
+    block.stmts = stmts;
+}
+
 pub fn py3_init(fnname: &syn::Ident, name: &String, doc: syn::Lit) -> Tokens {
     let m_name = syn::Ident::from(name.trim().as_ref());
     let cb_name = syn::Ident::from(format!("PyInit_{}", name.trim()).as_ref());
@@ -85,27 +149,85 @@ pub fn py3_init(fnname: &syn::Ident, name: &String, doc: syn::Lit) -> Tokens {
     }
 }
 
-pub fn build_py2_module_init(ast: &mut syn::Item, attr: String) -> Tokens {
-    let modname = &attr[1..attr.len()-1].to_string();
+/// Multi-phase initialization (PEP 489), the module is created by python from
+/// the returned definition and `Py_mod_exec` slot runs the initialization function
+pub fn py3_multi_phase_init(fnname: &syn::Ident, name: &String, doc: syn::Lit,
+                            state: &syn::Ty) -> Tokens {
+    let m_name = syn::Ident::from(name.trim().as_ref());
+    let cb_name = syn::Ident::from(format!("PyInit_{}", name.trim()).as_ref());
 
-    match ast.node {
-        syn::ItemKind::Fn(_, _, _, _, _, ref mut block) => {
-            let mut stmts = Vec::new();
-            for stmt in block.stmts.iter_mut() {
-                match stmt {
-                    &mut syn::Stmt::Item(ref mut item) => {
-                        if let Some(block) = wrap_fn(item) {
-                            for stmt in block.stmts.iter() {
-                                stmts.push(stmt.clone());
-                            }
-                            continue
-                        }
+    let def_state = quote! {
+        MODULE_DEF.m_size = pyo3::modulestate::state_size::<#state>();
+        MODULE_DEF.m_traverse = Some(pyo3::modulestate::traverse_state::<#state>);
+        MODULE_DEF.m_clear = Some(pyo3::modulestate::clear_state::<#state>);
+        MODULE_DEF.m_free = Some(pyo3::modulestate::free_state::<#state>);
+    };
+    let init_state = quote! {
+        pyo3::modulestate::init_state::<#state>(_module)?;
+    };
+
+    quote! {
+        #[no_mangle]
+        #[allow(non_snake_case, unused_imports)]
+        pub unsafe extern "C" fn #cb_name() -> *mut ::pyo3::ffi::PyObject {
+            extern crate pyo3;
+            use std;
+            use pyo3::{IntoPyPointer, ObjectProtocol};
+
+            unsafe extern "C" fn __exec(
+                module: *mut pyo3::ffi::PyObject) -> std::os::raw::c_int
+            {
+                let _pool = pyo3::GILPool::new();
+                let _py = pyo3::Python::assume_gil_acquired();
+                let _module = _py.from_borrowed_ptr::<pyo3::PyModule>(module);
+
+                let result = (|| -> pyo3::PyResult<()> {
+                    #init_state
+                    _module.add("__doc__", #doc)?;
+                    #fnname(_py, _module)
+                })();
+                match result {
+                    Ok(_) => 0,
+                    Err(e) => {
+                        e.restore(_py);
+                        -1
                     }
-                    _ => (),
                 }
-                stmts.push(stmt.clone());
             }
-            block.stmts = stmts;
+
+            // initialize pyo3
+            pyo3::prepare_pyo3_library();
+
+            static mut MODULE_DEF: pyo3::ffi::PyModuleDef = pyo3::ffi::PyModuleDef_INIT;
+            static mut SLOTS: [pyo3::ffi::PyModuleDef_Slot; 2] = [
+                pyo3::ffi::PyModuleDef_Slot { slot: 0, value: 0 as *mut _ },
+                pyo3::ffi::PyModuleDef_Slot { slot: 0, value: 0 as *mut _ },
+            ];
+            SLOTS[0].slot = pyo3::ffi::Py_mod_exec;
+            SLOTS[0].value = __exec as *mut std::os::raw::c_void;
+
+            MODULE_DEF.m_name = concat!(stringify!(#m_name), "\0").as_ptr() as *const _;
+            MODULE_DEF.m_slots = SLOTS.as_mut_ptr();
+            #def_state
+
+            #[cfg(py_sys_config = "WITH_THREAD")]
+            pyo3::ffi::PyEval_InitThreads();
+
+            pyo3::ffi::PyModuleDef_Init(&mut MODULE_DEF)
+        }
+    }
+}
+
+pub fn build_py2_module_init(ast: &mut syn::Item, attr: String) -> Tokens {
+    let attr = parse_modinit_attr(&attr);
+    if attr.multi_phase {
+        panic!("multi-phase module initialization is not supported on python 2");
+    }
+    let modname = &attr.name;
+
+    match ast.node {
+        syn::ItemKind::Fn(_, _, _, _, _, ref mut block) => {
+            expand_pyfns(block);
 
             py2_init(&ast.ident, &modname, utils::get_doc(&ast.attrs, false))
         },
@@ -158,6 +280,8 @@ fn wrap_fn(item: &mut syn::Item) -> Option<Box<syn::Block>> {
     let mut fnname = None;
     let mut modname = None;
     let mut fn_attrs = Vec::new();
+    let mut stub_attrs = Vec::new();
+    let mut hidden = false;
 
     for attr in item.attrs.iter() {
         match attr.value {
@@ -182,10 +306,22 @@ fn wrap_fn(item: &mut syn::Item) -> Option<Box<syn::Block>> {
                             modname = None
                         }
                         if meta.len() >= 3 {
-                            fn_attrs = args::parse_arguments(&meta[2..meta.len()]);
+                            let mut arguments = Vec::new();
+                            for item in meta[2..meta.len()].iter() {
+                                match *item {
+                                    syn::NestedMetaItem::MetaItem(syn::MetaItem::Word(ref ident))
+                                        if ident.as_ref() == "hidden" => hidden = true,
+                                    _ => arguments.push(item.clone()),
+                                }
+                            }
+                            fn_attrs = args::parse_arguments(&arguments);
                         }
                         continue;
                     }
+                    "stub" => {
+                        stub_attrs.extend(stub::parse_stub_attr(meta.as_slice()));
+                        continue;
+                    }
                     _ => (),
                 }
             }
@@ -205,59 +341,24 @@ fn wrap_fn(item: &mut syn::Item) -> Option<Box<syn::Block>> {
 
     match item.node {
         syn::ItemKind::Fn(ref decl, _, _, _, _, _) => {
-            let mut arguments = Vec::new();
-
-            for input in decl.inputs.iter() {
-                match input {
-                    &syn::FnArg::SelfRef(_, _) | &syn::FnArg::SelfValue(_) => (),
-                    &syn::FnArg::Captured(ref pat, ref ty) => {
-                        let (mode, ident) = match pat {
-                            &syn::Pat::Ident(ref mode, ref ident, _) =>
-                                (mode, ident),
-                            _ =>
-                                panic!("unsupported argument: {:?}", pat),
-                        };
-
-                        let py = match ty {
-                            &syn::Ty::Path(_, ref path) =>
-                                if let Some(segment) = path.segments.last() {
-                                    segment.ident.as_ref() == "Python"
-                                } else {
-                                    false
-                                },
-                            _ => false
-                        };
-
-                        let opt = method::check_arg_ty_and_optional(&name, ty);
-                        arguments.push(method::FnArg {name: ident,
-                                                      mode: mode,
-                                                      ty: ty,
-                                                      optional: opt,
-                                                      py: py,
-                                                      reference: method::is_ref(&name, ty)});
-                    }
-                    &syn::FnArg::Ignored(_) =>
-                        panic!("ignored argument: {:?}", name),
-                }
-            }
-
-            let ty = match decl.output {
-                syn::FunctionRetTy::Default => syn::Ty::Infer,
-                syn::FunctionRetTy::Ty(ref ty) => ty.clone()
-            };
-
-            let spec = method::FnSpec {
-                tp: method::FnType::Fn,
-                attrs: fn_attrs,
-                args: arguments,
-                output: ty,
-            };
+            let spec = function_spec(&name, decl, fn_attrs, stub_attrs);
 
             let m = modname.unwrap();
             let fnname = fnname.unwrap();
             let wrapper = impl_wrap(&name, &spec);
+            let meth = py_method::impl_method_type(&spec);
             let item2 = item.clone();
-            let doc = utils::get_doc(&item.attrs, true);
+            let stub = stub::fn_stub(fnname.as_ref(), "Function", &spec.stub_params(None),
+                                     &spec.stub_returns(), &utils::get_doc(&item.attrs, false));
+            let doc = utils::doc_with_signature(
+                utils::get_doc(&item.attrs, true),
+                &spec.text_signature(fnname.as_ref(), None));
+            // hidden functions are not listed in `__all__`
+            let add = if hidden {
+                quote! { #m.setattr(stringify!(#fnname), func)? }
+            } else {
+                quote! { #m.add(stringify!(#fnname), func)? }
+            };
 
             let tokens = quote! {
                 fn test() {
@@ -273,7 +374,7 @@ fn wrap_fn(item: &mut syn::Item) -> Option<Box<syn::Block>> {
 
                         let _def = pyo3::class::PyMethodDef {
                             ml_name: stringify!(#fnname),
-                            ml_meth: pyo3::class::PyMethodType::PyCFunctionWithKeywords(__wrap),
+                            ml_meth: #meth,
                             ml_flags: pyo3::ffi::METH_VARARGS | pyo3::ffi::METH_KEYWORDS,
                             ml_doc: #doc,
                         };
@@ -284,8 +385,11 @@ fn wrap_fn(item: &mut syn::Item) -> Option<Box<syn::Block>> {
                                     Box::into_raw(Box::new(_def.as_method_def())),
                                     std::ptr::null_mut()));
 
-                            #m.add(stringify!(#fnname), func)?
+                            #add
                         }
+
+                        static STUB: _pyo3::stubs::StubFn = #stub;
+                        _pyo3::stubs::register_function(#m, &STUB)?;
                     }
                 }
             }.to_string();
@@ -305,6 +409,147 @@ fn wrap_fn(item: &mut syn::Item) -> Option<Box<syn::Block>> {
 }
 
 
+/// `#[function]`, implements `PyFunctionInfo` for the type with the name of the function
+pub fn build_py_function(ast: &mut syn::Item, attr: String) -> Tokens {
+    let (py_name, fn_attrs) = parse_function_attr(&ast.ident, attr);
+    let mut stub_attrs = Vec::new();
+    ast.attrs.retain(|attr| match attr.value {
+        syn::MetaItem::List(ref name, ref meta) if name.as_ref() == "stub" => {
+            stub_attrs.extend(stub::parse_stub_attr(meta.as_slice()));
+            false
+        }
+        _ => true,
+    });
+
+    match ast.node {
+        syn::ItemKind::Fn(ref decl, _, _, _, ref generics, _) => {
+            if !generics.ty_params.is_empty() {
+                panic!("python function can not be generic: {:?}", ast.ident);
+            }
+            let name = &ast.ident;
+            let vis = &ast.vis;
+            let spec = function_spec(name, decl, fn_attrs, stub_attrs);
+            let wrapper = impl_wrap(name, &spec);
+            let meth = py_method::impl_method_type(&spec);
+            let stub = stub::fn_stub(&py_name, "Function", &spec.stub_params(None),
+                                     &spec.stub_returns(), &utils::get_doc(&ast.attrs, false));
+            let doc = utils::doc_with_signature(
+                utils::get_doc(&ast.attrs, true), &spec.text_signature(&py_name, None));
+
+            let dummy_const = syn::Ident::new(format!("_IMPL_PYO3_FUNCTION_{}", name));
+            quote! {
+                // the type shares the name of the function, `wrap_function!` uses it
+                #[doc(hidden)]
+                #[allow(non_camel_case_types)]
+                #vis struct #name {}
+
+                #[allow(non_upper_case_globals, unused_attributes,
+                        unused_qualifications, unused_variables, unused_imports)]
+                const #dummy_const: () = {
+                    use std;
+                    extern crate pyo3 as _pyo3;
+
+                    impl _pyo3::function::PyFunctionInfo for #name {
+                        fn function_def() -> &'static _pyo3::function::PyFunctionDef {
+                            #wrapper
+
+                            static DEF: _pyo3::function::PyFunctionDef =
+                                _pyo3::function::PyFunctionDef {
+                                    method: _pyo3::class::PyMethodDef {
+                                        ml_name: #py_name,
+                                        ml_meth: #meth,
+                                        ml_flags: _pyo3::ffi::METH_VARARGS | _pyo3::ffi::METH_KEYWORDS,
+                                        ml_doc: #doc,
+                                    },
+                                    stub: #stub,
+                                    ffi_def: std::sync::atomic::ATOMIC_USIZE_INIT,
+                                };
+                            &DEF
+                        }
+                    }
+                };
+            }
+        },
+        _ => panic!("#[function] can only be used with fn block"),
+    }
+}
+
+/// Parses `#[function(name = "py_name", arg = "default", args = "*", ...)]`,
+/// the arguments are specified like in `#[pyfn]`
+fn parse_function_attr(name: &syn::Ident, attr: String) -> (String, Vec<args::Argument>) {
+    let mut py_name = name.as_ref().to_owned();
+    let mut arguments = Vec::new();
+
+    let attr = syn::parse_outer_attr(&format!("#[function{}]", attr))
+        .expect("can not parse 'function' params");
+    if let syn::MetaItem::List(_, ref meta) = attr.value {
+        for item in meta.iter() {
+            match *item {
+                syn::NestedMetaItem::MetaItem(
+                    syn::MetaItem::NameValue(ref key, syn::Lit::Str(ref s, _)))
+                    if key.as_ref() == "name" => py_name = s.clone(),
+                _ => arguments.push(item.clone()),
+            }
+        }
+    }
+    (py_name, args::parse_arguments(&arguments))
+}
+
+/// Signature of the free function
+fn function_spec<'a>(name: &'a syn::Ident, decl: &'a syn::FnDecl,
+                     fn_attrs: Vec<args::Argument>, stub_attrs: Vec<(String, String)>)
+                     -> method::FnSpec<'a>
+{
+    let mut arguments = Vec::new();
+
+    for input in decl.inputs.iter() {
+        match input {
+            &syn::FnArg::SelfRef(_, _) | &syn::FnArg::SelfValue(_) => (),
+            &syn::FnArg::Captured(ref pat, ref ty) => {
+                let (mode, ident) = match pat {
+                    &syn::Pat::Ident(ref mode, ref ident, _) =>
+                        (mode, ident),
+                    _ =>
+                        panic!("unsupported argument: {:?}", pat),
+                };
+
+                let py = match ty {
+                    &syn::Ty::Path(_, ref path) =>
+                        if let Some(segment) = path.segments.last() {
+                            segment.ident.as_ref() == "Python"
+                        } else {
+                            false
+                        },
+                    _ => false
+                };
+
+                let opt = method::check_arg_ty_and_optional(&name, ty);
+                arguments.push(method::FnArg {name: ident,
+                                              mode: mode,
+                                              ty: ty,
+                                              optional: opt,
+                                              py: py,
+                                              reference: method::is_ref(&name, ty)});
+            }
+            &syn::FnArg::Ignored(_) =>
+                panic!("ignored argument: {:?}", name),
+        }
+    }
+
+    let ty = match decl.output {
+        syn::FunctionRetTy::Default => syn::Ty::Infer,
+        syn::FunctionRetTy::Ty(ref ty) => ty.clone()
+    };
+
+    method::FnSpec {
+        tp: method::FnType::Fn,
+        attrs: fn_attrs,
+        args: arguments,
+        output: ty,
+        stub: stub_attrs,
+    }
+}
+
 /// Generate static method wrapper (PyCFunction, PyCFunctionWithKeywords)
 pub fn impl_wrap(name: &syn::Ident, spec: &method::FnSpec) -> Tokens {
     let names: Vec<syn::Ident> = spec.args.iter().enumerate().map(
@@ -314,8 +559,10 @@ pub fn impl_wrap(name: &syn::Ident, spec: &method::FnSpec) -> Tokens {
         #name(#(#names),*)
     }};
 
-    let body = py_method::impl_arg_params(spec, cb);
+    let body = py_method::impl_arg_params(spec, cb.clone());
     let output = &spec.output;
+    let fast = py_method::impl_wrap_fast(
+        &quote! { concat!(stringify!(#name), "()") }, &Tokens::new(), spec, cb);
 
     quote! {
         #[allow(unused_variables, unused_imports)]
@@ -337,5 +584,7 @@ pub fn impl_wrap(name: &syn::Ident, spec: &method::FnSpec) -> Tokens {
             _pyo3::callback::cb_convert(
                 _pyo3::callback::PyObjectCallbackConverter, _py, _result)
         }
+
+        #fast
     }
 }
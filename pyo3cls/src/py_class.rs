@@ -7,8 +7,9 @@ use syn;
 use quote::{Tokens, ToTokens};
 
 use utils;
-use method::{FnType, FnSpec, FnArg};
-use py_method::{impl_wrap_getter, impl_wrap_setter, impl_py_getter_def, impl_py_setter_def};
+use method::FnType;
+use py_method::{impl_py_getter_def, impl_py_setter_def, impl_py_deleter_def};
+use stub;
 
 
 pub fn build_py_class(ast: &mut syn::DeriveInput, attr: String) -> Tokens {
@@ -16,16 +17,18 @@ pub fn build_py_class(ast: &mut syn::DeriveInput, attr: String) -> Tokens {
     let doc = utils::get_doc(&ast.attrs, true);
     let mut token: Option<syn::Ident> = None;
     let mut descriptors = Vec::new();
+    let mut field_names = Vec::new();
     match ast.body {
         syn::Body::Struct(syn::VariantData::Struct(ref mut fields)) => {
+            field_names.extend(fields.iter().map(|field| (field.ident.clone().unwrap(), field.ty.clone())));
             for field in fields.iter_mut() {
                 if is_python_token(field) {
                     token = field.ident.clone();
                     break
                 } else {
-                    let field_descs = parse_descriptors(field);
+                    let (field_descs, doc, ann) = parse_descriptors(field);
                     if !field_descs.is_empty() {
-                        descriptors.push((field.clone(), field_descs));
+                        descriptors.push((field.clone(), field_descs, doc, ann));
                     }
                 }
             }
@@ -34,7 +37,7 @@ pub fn build_py_class(ast: &mut syn::DeriveInput, attr: String) -> Tokens {
     }
 
     let dummy_const = syn::Ident::new(format!("_IMPL_PYO3_CLS_{}", ast.ident));
-    let tokens = impl_class(&ast.ident, &base, token, doc, params, flags, descriptors);
+    let tokens = impl_class(&ast.ident, &base, token, doc, params, flags, descriptors, field_names);
 
     quote! {
         #[allow(non_upper_case_globals, unused_attributes,
@@ -48,9 +51,14 @@ pub fn build_py_class(ast: &mut syn::DeriveInput, attr: String) -> Tokens {
     }
 }
 
-fn parse_descriptors(item: &mut syn::Field) -> Vec<FnType> {
+/// Parses `#[prop(get, set, del, name = "py_name", doc = "...")]` field attribute
+/// and the stub annotation `#[stub(ty = "...")]`
+fn parse_descriptors(item: &mut syn::Field) -> (Vec<FnType>, syn::Lit, String) {
     let mut descs = Vec::new();
     let mut new_attrs = Vec::new();
+    let mut py_name = None;
+    let mut doc = None;
+    let mut ann = None;
     for attr in item.attrs.iter() {
         match attr.value {
             syn::MetaItem::List(ref name, ref metas) => {
@@ -58,20 +66,39 @@ fn parse_descriptors(item: &mut syn::Field) -> Vec<FnType> {
                     "prop" => {
                         for meta in metas.iter() {
                             match *meta {
-                                syn::NestedMetaItem::MetaItem(ref metaitem) => {
-                                    match metaitem.name() {
+                                syn::NestedMetaItem::MetaItem(syn::MetaItem::Word(ref w)) => {
+                                    match w.as_ref() {
                                         "get" => {
                                             descs.push(FnType::Getter(None));
                                         }
                                         "set" => {
                                             descs.push(FnType::Setter(None));
                                         }
+                                        "del" => {
+                                            descs.push(FnType::Deleter(None));
+                                        }
                                         _ => {
-                                            panic!("Only getter and setter supported");
+                                            panic!("Only get, set and del are supported");
                                         }
                                     }
                                 }
-                                _ => ()
+                                syn::NestedMetaItem::MetaItem(
+                                    syn::MetaItem::NameValue(ref key, syn::Lit::Str(ref s, _))) => {
+                                    match key.as_ref() {
+                                        "name" => py_name = Some(s.clone()),
+                                        "doc" => doc = Some(s.clone()),
+                                        _ => panic!("Unsupported prop parameter: {}", key),
+                                    }
+                                }
+                                _ => panic!("Wrong prop format: {:?}", meta),
+                            }
+                        }
+                    }
+                    "stub" => {
+                        for (key, value) in stub::parse_stub_attr(metas.as_slice()) {
+                            match key.as_ref() {
+                                "ty" => ann = Some(value),
+                                _ => panic!("Unsupported stub parameter for field: {}", key),
                             }
                         }
                     }
@@ -87,19 +114,63 @@ fn parse_descriptors(item: &mut syn::Field) -> Vec<FnType> {
     }
     item.attrs.clear();
     item.attrs.extend(new_attrs);
-    descs
+
+    let py_name = py_name.unwrap_or_else(|| item.ident.as_ref().unwrap().to_string());
+    let descs = descs.into_iter().map(|desc| match desc {
+        FnType::Getter(_) => FnType::Getter(Some(py_name.clone())),
+        FnType::Setter(_) => FnType::Setter(Some(py_name.clone())),
+        FnType::Deleter(_) => FnType::Deleter(Some(py_name.clone())),
+        _ => unreachable!(),
+    }).collect();
+
+    // falls back to the field doc comment
+    let doc = match doc {
+        Some(doc) => syn::Lit::Str(format!("{}\0", doc), syn::StrStyle::Cooked),
+        None => utils::get_doc(&item.attrs, true),
+    };
+    let ann = ann.unwrap_or_else(|| stub::annotation(&item.ty));
+    (descs, doc, ann)
 }
 
 fn impl_class(cls: &syn::Ident, base: &syn::Ident,
               token: Option<syn::Ident>, doc: syn::Lit,
               params: HashMap<&'static str, syn::Ident>,
               flags: Vec<syn::Ident>,
-              descriptors: Vec<(syn::Field, Vec<FnType>)>) -> Tokens {
+              descriptors: Vec<(syn::Field, Vec<FnType>, syn::Lit, String)>,
+              field_names: Vec<(syn::Ident, syn::Ty)>) -> Tokens {
     let cls_name = match params.get("name") {
         Some(name) => quote! { #name }.as_str().to_string(),
         None => quote! { #cls }.as_str().to_string()
     };
 
+    // `repr` and `str` derive the slots from user's `Debug` and `Display` impls
+    let debug = if params.contains_key("repr") {
+        None
+    } else {
+        Some(quote! {
+            impl std::fmt::Debug for #cls {
+                fn fmt(&self, f : &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+                    use pyo3::ObjectProtocol;
+                    let s = try!(self.repr().map_err(|_| std::fmt::Error));
+                    f.write_str(&s.to_string_lossy())
+                }
+            }
+        })
+    };
+    let display = if params.contains_key("str") {
+        None
+    } else {
+        Some(quote! {
+            impl std::fmt::Display for #cls {
+                fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+                    use pyo3::ObjectProtocol;
+                    let s = try!(self.str().map_err(|_| std::fmt::Error));
+                    f.write_str(&s.to_string_lossy())
+                }
+            }
+        })
+    };
+
     let extra = if let Some(token) = token {
         Some(quote! {
             impl _pyo3::PyObjectWithToken for #cls {
@@ -151,20 +222,8 @@ fn impl_class(cls: &syn::Ident, base: &syn::Ident,
                     }
                 }
             }
-            impl std::fmt::Debug for #cls {
-                fn fmt(&self, f : &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
-                    use pyo3::ObjectProtocol;
-                    let s = try!(self.repr().map_err(|_| std::fmt::Error));
-                    f.write_str(&s.to_string_lossy())
-                }
-            }
-            impl std::fmt::Display for #cls {
-                fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
-                    use pyo3::ObjectProtocol;
-                    let s = try!(self.str().map_err(|_| std::fmt::Error));
-                    f.write_str(&s.to_string_lossy())
-                }
-            }
+            #debug
+            #display
         })
     } else {
         None
@@ -172,6 +231,10 @@ fn impl_class(cls: &syn::Ident, base: &syn::Ident,
 
     let extra = {
         if let Some(freelist) = params.get("freelist") {
+            // the free list is shared by all interpreters
+            if flags.contains(&syn::Ident::from("_pyo3::typeob::PY_TYPE_FLAG_PER_INTERPRETER")) {
+                panic!("#[class(freelist)] can not be used with per_interpreter");
+            }
             Some(quote! {
                 impl _pyo3::freelist::PyObjectWithFreeList for #cls {
                     #[inline]
@@ -182,7 +245,8 @@ fn impl_class(cls: &syn::Ident, base: &syn::Ident,
                                 FREELIST = Box::into_raw(Box::new(
                                     _pyo3::freelist::FreeList::with_capacity(#freelist)));
 
-                                <#cls as _pyo3::typeob::PyTypeObject>::init_type();
+                                // initialization errors are raised by the allocation
+                                let _ = <#cls as _pyo3::typeob::PyTypeObject>::init_type();
                             }
                             &mut *FREELIST
                         }
@@ -196,6 +260,64 @@ fn impl_class(cls: &syn::Ident, base: &syn::Ident,
         }
     };
 
+    let derives: Vec<Tokens> = ["repr", "str", "eq", "ord", "hash"].iter()
+        .filter_map(|key| params.get(key))
+        .map(|derive| quote! {
+            impl _pyo3::class::derived::#derive for #cls {}
+        })
+        .collect();
+    let extra = Some(quote! {
+        #(#derives)*
+        #extra
+    });
+
+    let extra = if params.contains_key("pickle") {
+        Some(quote! {
+            impl _pyo3::class::pickle::PyPickleProtocol for #cls {}
+
+            #extra
+        })
+    } else {
+        extra
+    };
+
+    // `__copy__` and `__deepcopy__` copy the struct field by field
+    let extra = if params.contains_key("clone") {
+        let fields: Vec<_> = field_names.iter().map(|&(ref name, _)| name).collect();
+        let fields = &fields;
+        let shallow: Vec<Tokens> = field_names.iter()
+            .map(|&(ref name, ref ty)| shallow_copy_expr(ty, quote! { &self.#name }))
+            .collect();
+        let deep: Vec<Tokens> = field_names.iter()
+            .map(|&(ref name, ref ty)| deep_copy_expr(ty, quote! { &self.#name }))
+            .collect();
+        Some(quote! {
+            impl _pyo3::class::copy::PyCloneProtocol for #cls {}
+
+            impl _pyo3::class::copy::PyShallowCopy for #cls {
+                fn shallow_copy(&self, py: _pyo3::Python) -> Self {
+                    #cls {
+                        #(#fields: #shallow,)*
+                    }
+                }
+            }
+
+            impl _pyo3::class::copy::PyDeepCopy for #cls {
+                fn deep_copy(&self, py: _pyo3::Python, memo: &_pyo3::PyObjectRef)
+                             -> _pyo3::PyResult<Self>
+                {
+                    Ok(#cls {
+                        #(#fields: #deep?,)*
+                    })
+                }
+            }
+
+            #extra
+        })
+    } else {
+        extra
+    };
+
     let extra = if !descriptors.is_empty() {
         let ty = syn::parse::ty(cls.as_ref()).expect("no name");
         let desc_impls = impl_descriptors(&ty, descriptors);
@@ -207,6 +329,11 @@ fn impl_class(cls: &syn::Ident, base: &syn::Ident,
         extra
     };
 
+    let module = match params.get("module") {
+        Some(module) => module.clone(),
+        None => syn::Ident::from("None"),
+    };
+
     // insert space for weak ref
     let mut has_weakref = false;
     let mut has_dict = false;
@@ -234,6 +361,7 @@ fn impl_class(cls: &syn::Ident, base: &syn::Ident,
             type BaseType = #base;
 
             const NAME: &'static str = #cls_name;
+            const MODULE: Option<&'static str> = #module;
             const DESCRIPTION: &'static str = #doc;
             const FLAGS: usize = #(#flags)|*;
 
@@ -252,29 +380,24 @@ fn impl_class(cls: &syn::Ident, base: &syn::Ident,
 
             #[inline]
             unsafe fn type_object() -> &'static mut _pyo3::ffi::PyTypeObject {
-                static mut TYPE_OBJECT: _pyo3::ffi::PyTypeObject = _pyo3::ffi::PyTypeObject_INIT;
-                &mut TYPE_OBJECT
+                _pyo3::typeob::class_type_object::<#cls>()
             }
-        }
 
-        impl _pyo3::typeob::PyTypeObject for #cls {
-            #[inline(always)]
-            fn init_type() {
-                static START: std::sync::Once = std::sync::ONCE_INIT;
-                START.call_once(|| {
-                    let ty = unsafe{<#cls as _pyo3::typeob::PyTypeInfo>::type_object()};
-
-                    if (ty.tp_flags & _pyo3::ffi::Py_TPFLAGS_READY) == 0 {
-                        let gil = _pyo3::Python::acquire_gil();
-                        let py = gil.python();
-
-                        // automatically initialize the class on-demand
-                        _pyo3::typeob::initialize_type::<#cls>(py, None)
-                            .map_err(|e| e.print(py))
-                            .expect(format!("An error occurred while initializing class {}",
-                                            <#cls as _pyo3::typeob::PyTypeInfo>::NAME).as_ref());
-                    }
-                });
+            #[inline]
+            fn is_instance(ptr: *mut _pyo3::ffi::PyObject) -> bool {
+                _pyo3::typeob::class_is_instance::<#cls>(ptr)
+            }
+
+            #[inline]
+            fn is_exact_instance(ptr: *mut _pyo3::ffi::PyObject) -> bool {
+                _pyo3::typeob::class_is_exact_instance::<#cls>(ptr)
+            }
+
+            #[inline]
+            unsafe fn type_storage() -> Option<&'static mut _pyo3::typeob::PyTypeStorage> {
+                static mut TYPE_OBJECT: _pyo3::typeob::PyTypeStorage =
+                    _pyo3::typeob::PyTypeStorage::INIT;
+                Some(&mut TYPE_OBJECT)
             }
         }
 
@@ -282,64 +405,89 @@ fn impl_class(cls: &syn::Ident, base: &syn::Ident,
     }
 }
 
-fn impl_descriptors(cls: &syn::Ty, descriptors: Vec<(syn::Field, Vec<FnType>)>) -> Tokens {
-    let methods: Vec<Tokens> = descriptors.iter().flat_map(|&(ref field, ref fns)| {
-        fns.iter().map(|desc| {
+fn impl_descriptors(cls: &syn::Ty, descriptors: Vec<(syn::Field, Vec<FnType>, syn::Lit, String)>) -> Tokens {
+    let py_methods: Vec<Tokens> = descriptors.iter().flat_map(|&(ref field, ref fns, ref doc, _)| {
+        fns.iter().map(move |desc| {
             let name = field.ident.clone().unwrap();
             let field_ty = &field.ty;
             match *desc {
-                FnType::Getter(_) => {
-                    quote! {
-                        impl #cls {
-                            fn #name(&self) -> _pyo3::PyResult<#field_ty> {
-                                Ok(self.#name)
-                            }
+                FnType::Getter(ref getter) => {
+                    // converts a reference to the field, `Py<T>` fields return the same object
+                    let wrapper = quote! {
+                        unsafe extern "C" fn __wrap(
+                            _slf: *mut _pyo3::ffi::PyObject, _: *mut _pyo3::c_void) -> *mut _pyo3::ffi::PyObject
+                        {
+                            let _pool = _pyo3::GILPool::new();
+                            let _py = _pyo3::Python::assume_gil_acquired();
+                            let _slf = _py.mut_from_borrowed_ptr::<#cls>(_slf);
+
+                            _pyo3::IntoPyPointer::into_ptr(
+                                _pyo3::ToPyObject::to_object(&_slf.#name, _py))
                         }
-                    }
+                    };
+                    impl_py_getter_def(&name, doc.clone(), getter, &wrapper)
                 }
-                FnType::Setter(_) => {
-                    let setter_name = syn::Ident::from(format!("set_{}", name));
-                    quote! {
-                        impl #cls {
-                            fn #setter_name(&mut self, value: #field_ty) -> _pyo3::PyResult<()> {
-                                self.#name = value;
-                                Ok(())
+                FnType::Setter(ref setter) => {
+                    let wrapper = quote! {
+                        unsafe extern "C" fn __wrap(
+                            _slf: *mut _pyo3::ffi::PyObject,
+                            _value: *mut _pyo3::ffi::PyObject, _: *mut _pyo3::c_void) -> _pyo3::c_int
+                        {
+                            let _pool = _pyo3::GILPool::new();
+                            let _py = _pyo3::Python::assume_gil_acquired();
+                            if _value.is_null() {
+                                _pyo3::PyErr::new::<_pyo3::exc::AttributeError, _>(
+                                    "can't delete attribute").restore(_py);
+                                return -1
+                            }
+                            let _slf = _py.mut_from_borrowed_ptr::<#cls>(_slf);
+                            let _value = _py.from_borrowed_ptr(_value);
+
+                            match <#field_ty as _pyo3::FromPyObject>::extract(_value) {
+                                Ok(_val) => {
+                                    _slf.#name = _val;
+                                    0
+                                }
+                                Err(e) => {
+                                    e.restore(_py);
+                                    -1
+                                }
                             }
                         }
-                    }
+                    };
+                    impl_py_setter_def(&name, doc.clone(), setter, &wrapper)
                 },
-                _ => unreachable!()
-            }
-        }).collect::<Vec<Tokens>>()
-    }).collect();
-
-    let py_methods: Vec<Tokens> = descriptors.iter().flat_map(|&(ref field, ref fns)| {
-        fns.iter().map(|desc| {
-            let name = field.ident.clone().unwrap();
-            // FIXME better doc?
-            let doc = syn::Lit::from(name.as_ref());
-            let field_ty = &field.ty;
-            match *desc {
-                FnType::Getter(ref getter) => {
-                    impl_py_getter_def(&name, doc, getter, &impl_wrap_getter(&Box::new(cls.clone()), &name))
-                }
-                FnType::Setter(ref setter) => {
-                    let mode = syn::BindingMode::ByValue(syn::Mutability::Immutable);
-                    let setter_name = syn::Ident::from(format!("set_{}", name));
-                    let spec = FnSpec {
-                        tp: FnType::Setter(None),
-                        attrs: Vec::new(),
-                        args: vec![FnArg {
-                            name: &name,
-                            mode: &mode,
-                            ty: field_ty,
-                            optional: None,
-                            py: true,
-                            reference: false
-                        }],
-                        output: syn::parse::ty("PyResult<()>").expect("error parse PyResult<()>")
+                FnType::Deleter(ref deleter) => {
+                    // `Option` fields are reset to `None`, other fields can not be deleted
+                    let delete = if is_option(field_ty) {
+                        quote! {
+                            let _slf = _py.mut_from_borrowed_ptr::<#cls>(_slf);
+                            _slf.#name = None;
+                            0
+                        }
+                    } else {
+                        quote! {
+                            _pyo3::PyErr::new::<_pyo3::exc::AttributeError, _>(
+                                "can't delete attribute").restore(_py);
+                            -1
+                        }
+                    };
+                    let wrapper = quote! {
+                        unsafe extern "C" fn __wrap(
+                            _slf: *mut _pyo3::ffi::PyObject,
+                            _value: *mut _pyo3::ffi::PyObject, _: *mut _pyo3::c_void) -> _pyo3::c_int
+                        {
+                            let _pool = _pyo3::GILPool::new();
+                            let _py = _pyo3::Python::assume_gil_acquired();
+                            if !_value.is_null() {
+                                _pyo3::PyErr::new::<_pyo3::exc::AttributeError, _>(
+                                    "can't set attribute").restore(_py);
+                                return -1
+                            }
+                            #delete
+                        }
                     };
-                    impl_py_setter_def(&name, doc, setter, &impl_wrap_setter(&Box::new(cls.clone()), &setter_name, &spec))
+                    impl_py_deleter_def(&name, doc.clone(), deleter, &wrapper)
                 },
                 _ => unreachable!()
             }
@@ -347,8 +495,6 @@ fn impl_descriptors(cls: &syn::Ty, descriptors: Vec<(syn::Field, Vec<FnType>)>)
     }).collect();
 
     let tokens = quote! {
-        #(#methods)*
-
         impl _pyo3::class::methods::PyPropMethodsProtocolImpl for #cls {
             fn py_methods() -> &'static [_pyo3::class::PyMethodDefType] {
                 static METHODS: &'static [_pyo3::class::PyMethodDefType] = &[
@@ -359,6 +505,19 @@ fn impl_descriptors(cls: &syn::Ty, descriptors: Vec<(syn::Field, Vec<FnType>)>)
         }
     };
 
+    let stubs = descriptors.iter().flat_map(|&(_, ref fns, ref doc, ref ann)| {
+        fns.iter().map(move |desc| match *desc {
+            FnType::Getter(Some(ref name)) =>
+                stub::fn_stub(name, "Getter", "self", ann, doc),
+            FnType::Setter(Some(ref name)) =>
+                stub::fn_stub(name, "Setter", &format!("self, value: {}", ann), "None", doc),
+            FnType::Deleter(Some(ref name)) =>
+                stub::fn_stub(name, "Deleter", "self", "None", doc),
+            _ => unreachable!()
+        })
+    }).collect();
+    let stubs = stub::impl_stubs(cls, "Class", stubs);
+
     let n = match cls {
         &syn::Ty::Path(_, ref p) => {
             p.segments.last().as_ref().unwrap().ident.as_ref()
@@ -375,10 +534,92 @@ fn impl_descriptors(cls: &syn::Ty, descriptors: Vec<(syn::Field, Vec<FnType>)>)
             extern crate pyo3 as _pyo3;
 
             #tokens
+
+            #stubs
         };
     }
 }
 
+/// Type arguments of `Option`, `Vec`, `HashMap` and `BTreeMap`,
+/// their items are copied one by one
+fn container_args(ty: &syn::Ty) -> Option<(&str, &[syn::Ty])> {
+    if let syn::Ty::Path(None, ref path) = *ty {
+        if let Some(segment) = path.segments.last() {
+            if let syn::PathParameters::AngleBracketed(ref data) = segment.parameters {
+                let name = segment.ident.as_ref();
+                match (name, data.types.len()) {
+                    ("Option", 1) | ("Vec", 1) | ("HashMap", 2) | ("BTreeMap", 2) =>
+                        return Some((name, &data.types)),
+                    _ => (),
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Expression for `PyShallowCopy` of `value: &ty`
+fn shallow_copy_expr(ty: &syn::Ty, value: Tokens) -> Tokens {
+    match container_args(ty) {
+        Some(("Option", args)) => {
+            let item = shallow_copy_expr(&args[0], quote! { v });
+            quote! { (#value).as_ref().map(|v| #item) }
+        }
+        Some(("Vec", args)) => {
+            let item = shallow_copy_expr(&args[0], quote! { v });
+            quote! { (#value).iter().map(|v| #item).collect::<#ty>() }
+        }
+        Some((_, args)) => {
+            let key = shallow_copy_expr(&args[0], quote! { k });
+            let item = shallow_copy_expr(&args[1], quote! { v });
+            quote! { (#value).iter().map(|(k, v)| (#key, #item)).collect::<#ty>() }
+        }
+        None => quote! { _pyo3::class::copy::PyShallowCopy::shallow_copy(#value, py) },
+    }
+}
+
+/// `PyResult` expression for `PyDeepCopy` of `value: &ty`, map keys are shallow copied
+fn deep_copy_expr(ty: &syn::Ty, value: Tokens) -> Tokens {
+    match container_args(ty) {
+        Some(("Option", args)) => {
+            let item = deep_copy_expr(&args[0], quote! { v });
+            quote! {
+                match *(#value) {
+                    Some(ref v) => #item.map(Some),
+                    None => Ok(None),
+                }
+            }
+        }
+        Some(("Vec", args)) => {
+            let item = deep_copy_expr(&args[0], quote! { v });
+            quote! { (#value).iter().map(|v| #item).collect::<_pyo3::PyResult<#ty>>() }
+        }
+        Some((_, args)) => {
+            let key = shallow_copy_expr(&args[0], quote! { k });
+            let item = deep_copy_expr(&args[1], quote! { v });
+            quote! {
+                (#value).iter().map(|(k, v)| {
+                    let k = #key;
+                    #item.map(move |v| (k, v))
+                }).collect::<_pyo3::PyResult<#ty>>()
+            }
+        }
+        None => quote! { _pyo3::class::copy::PyDeepCopy::deep_copy(#value, py, memo) },
+    }
+}
+
+fn is_option(ty: &syn::Ty) -> bool {
+    match *ty {
+        syn::Ty::Path(_, ref path) => {
+            if let Some(segment) = path.segments.last() {
+                return segment.ident.as_ref() == "Option"
+            }
+        }
+        _ => (),
+    }
+    return false
+}
+
 fn is_python_token(field: &syn::Field) -> bool {
     match field.ty {
         syn::Ty::Path(_, ref path) => {
@@ -461,6 +702,38 @@ fn parse_attribute(mut attr: String) -> (HashMap<&'static str, syn::Ident>,
                         flags.push(syn::Ident::from("_pyo3::typeob::PY_TYPE_FLAG_DICT"));
                         continue
                     }
+                    "per_interpreter" => {
+                        flags.push(syn::Ident::from("_pyo3::typeob::PY_TYPE_FLAG_PER_INTERPRETER"));
+                        continue
+                    }
+                    "pickle" => {
+                        params.insert("pickle", syn::Ident::from("true"));
+                        continue
+                    }
+                    "clone" => {
+                        params.insert("clone", syn::Ident::from("true"));
+                        continue
+                    }
+                    "repr" => {
+                        params.insert("repr", syn::Ident::from("PyDeriveRepr"));
+                        continue
+                    }
+                    "str" => {
+                        params.insert("str", syn::Ident::from("PyDeriveStr"));
+                        continue
+                    }
+                    "eq" => {
+                        params.insert("eq", syn::Ident::from("PyDeriveEq"));
+                        continue
+                    }
+                    "ord" => {
+                        params.insert("ord", syn::Ident::from("PyDeriveOrd"));
+                        continue
+                    }
+                    "hash" => {
+                        params.insert("hash", syn::Ident::from("PyDeriveHash"));
+                        continue
+                    }
                     _ => {
                         println!("Unsupported parameter: {:?}", key);
                     }
@@ -507,6 +780,20 @@ fn parse_attribute(mut attr: String) -> (HashMap<&'static str, syn::Ident>,
                         }
                     }
                 },
+                "module" => {
+                    if elem.len() != 3 {
+                        println!("Wrong 'module' format: {:?}", elem);
+                    } else {
+                        match elem[2] {
+                            syn::TokenTree::Token(
+                                syn::Token::Literal(
+                                    syn::Lit::Str(ref s, _))) => {
+                                params.insert("module", syn::Ident::from(format!("Some({:?})", s)));
+                            }
+                            _ => println!("Wrong 'module' format: {:?}", elem)
+                        }
+                    }
+                },
                 "base" => {
                     let mut m = String::new();
                     for el in elem[2..elem.len()].iter() {
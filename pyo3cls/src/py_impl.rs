@@ -4,6 +4,7 @@ use syn;
 use quote::Tokens;
 
 use py_method;
+use stub;
 
 
 pub fn build_py_methods(ast: &mut syn::Item) -> Tokens {
@@ -23,15 +24,36 @@ fn impl_methods(ty: &Box<syn::Ty>, impls: &mut Vec<syn::ImplItem>) -> Tokens {
 
     // get method names in impl block
     let mut methods = Vec::new();
+    let mut stubs = Vec::new();
+    // `#[overload]` methods grouped by python name
+    let mut overloads: Vec<(String, Vec<_>)> = Vec::new();
     for iimpl in impls.iter_mut() {
         match iimpl.node {
             syn::ImplItemKind::Method(ref mut sig, _) => {
-                methods.push(py_method::gen_py_method(
-                    ty, &iimpl.ident, sig, &mut iimpl.attrs));
+                match py_method::parse_overload(&mut iimpl.attrs) {
+                    Some(py_name) => {
+                        let variant = (&iimpl.ident, sig, &mut iimpl.attrs);
+                        match overloads.iter().position(|&(ref name, _)| *name == py_name) {
+                            Some(idx) => overloads[idx].1.push(variant),
+                            None => overloads.push((py_name, vec![variant])),
+                        }
+                    }
+                    None => {
+                        let (method, stub) = py_method::gen_py_method(
+                            ty, &iimpl.ident, sig, &mut iimpl.attrs);
+                        methods.push(method);
+                        stubs.push(stub);
+                    }
+                }
             },
             _ => (),
         }
     }
+    for (py_name, variants) in overloads {
+        let (method, overload_stubs) = py_method::gen_py_overload(ty, &py_name, variants);
+        methods.push(method);
+        stubs.extend(overload_stubs);
+    }
 
     let tokens = quote! {
         impl _pyo3::class::methods::PyMethodsProtocolImpl for #ty {
@@ -43,6 +65,7 @@ fn impl_methods(ty: &Box<syn::Ty>, impls: &mut Vec<syn::ImplItem>) -> Tokens {
             }
         }
     };
+    let stubs = stub::impl_stubs(ty, "Methods", stubs);
 
     let n = match ty.as_ref() {
         &syn::Ty::Path(_, ref p) => {
@@ -60,6 +83,8 @@ fn impl_methods(ty: &Box<syn::Ty>, impls: &mut Vec<syn::ImplItem>) -> Tokens {
             extern crate pyo3 as _pyo3;
 
             #tokens
+
+            #stubs
         };
     }
 }
@@ -4,18 +4,35 @@ use syn;
 use quote::{Tokens, ToTokens};
 use method::{FnArg, FnSpec, FnType};
 
+use stub;
 use utils;
 
 
+/// Generates the method definition and the stub of the method
 pub fn gen_py_method<'a>(cls: &Box<syn::Ty>, name: &syn::Ident,
-                         sig: &mut syn::MethodSig, meth_attrs: &mut Vec<syn::Attribute>) -> Tokens
+                         sig: &mut syn::MethodSig, meth_attrs: &mut Vec<syn::Attribute>)
+                         -> (Tokens, Tokens)
 {
     check_generic(name, sig);
 
     let doc = utils::get_doc(&meth_attrs, true);
     let spec = FnSpec::parse(name, sig, meth_attrs);
+    let stub = stub::method_stub(name, &spec, &doc);
 
-    match spec.tp {
+    // `__text_signature__`, `__new__` signature is used for the class doc string
+    let doc = match spec.tp {
+        FnType::Fn =>
+            utils::doc_with_signature(doc, &spec.text_signature(name.as_ref(), Some("$self"))),
+        FnType::FnClass =>
+            utils::doc_with_signature(doc, &spec.text_signature(name.as_ref(), Some("$cls"))),
+        FnType::FnStatic =>
+            utils::doc_with_signature(doc, &spec.text_signature(name.as_ref(), None)),
+        FnType::FnNew =>
+            utils::doc_with_signature(doc, &spec.text_signature("__new__", None)),
+        _ => doc,
+    };
+
+    let def = match spec.tp {
         FnType::Fn =>
             impl_py_method_def(name, doc, &spec, &impl_wrap(cls, name, &spec, true)),
         FnType::FnNew =>
@@ -25,14 +42,137 @@ pub fn gen_py_method<'a>(cls: &Box<syn::Ty>, name: &syn::Ident,
         FnType::FnCall =>
             impl_py_method_def_call(name, doc, &impl_wrap(cls, name, &spec, false)),
         FnType::FnClass =>
-            impl_py_method_def_class(name, doc, &impl_wrap_class(cls, name, &spec)),
+            impl_py_method_def_class(name, doc, &spec, &impl_wrap_class(cls, name, &spec)),
         FnType::FnStatic =>
-            impl_py_method_def_static(name, doc, &impl_wrap_static(cls, name, &spec)),
+            impl_py_method_def_static(name, doc, &spec, &impl_wrap_static(cls, name, &spec)),
+        FnType::ClassAttribute =>
+            impl_py_class_attribute(cls, name, &spec),
         FnType::Getter(ref getter) =>
             impl_py_getter_def(name, doc, getter, &impl_wrap_getter(cls, name)),
         FnType::Setter(ref setter) =>
             impl_py_setter_def(name, doc, setter, &impl_wrap_setter(cls, name, &spec)),
+        FnType::Deleter(ref deleter) =>
+            impl_py_deleter_def(name, doc, deleter, &impl_wrap_deleter(cls, name)),
+    };
+    (def, stub)
+}
+
+/// Parses and removes `#[overload(name = "py_name")]` attribute
+pub fn parse_overload(attrs: &mut Vec<syn::Attribute>) -> Option<String> {
+    let mut py_name = None;
+    attrs.retain(|attr| match attr.value {
+        syn::MetaItem::List(ref name, ref meta) if name.as_ref() == "overload" => {
+            for item in meta.iter() {
+                match *item {
+                    syn::NestedMetaItem::MetaItem(
+                        syn::MetaItem::NameValue(ref key, syn::Lit::Str(ref s, _)))
+                        if key.as_ref() == "name" => py_name = Some(s.clone()),
+                    _ => panic!("overload attribute requires `name = \"...\"`: {:?}", item),
+                }
+            }
+            false
+        }
+        _ => true,
+    });
+    py_name
+}
+
+/// Generates the method definition and the stubs of the overloaded method,
+/// the wrapper tries the signatures in order, the first one that accepts
+/// the arguments is called
+pub fn gen_py_overload(cls: &Box<syn::Ty>, py_name: &str,
+                       variants: Vec<(&syn::Ident, &mut syn::MethodSig, &mut Vec<syn::Attribute>)>)
+                       -> (Tokens, Vec<Tokens>)
+{
+    let mut doc = None;
+    let mut bodies = Vec::new();
+    let mut signatures = Vec::new();
+    let mut stubs = Vec::new();
+
+    for (name, sig, meth_attrs) in variants {
+        check_generic(name, sig);
+
+        let variant_doc = utils::get_doc(&meth_attrs, true);
+        let stub_doc = utils::get_doc(&meth_attrs, false);
+        let spec = FnSpec::parse(name, sig, meth_attrs);
+        if spec.tp != FnType::Fn {
+            panic!("#[overload] is supported for instance methods only: {:?}", name);
+        }
+        if let syn::Lit::Str(ref s, _) = variant_doc {
+            if doc.is_none() && s.len() > 1 {
+                doc = Some(variant_doc.clone());
+            }
+        }
+        stubs.push(stub::fn_stub(py_name, "Overload", &spec.stub_params(Some("self")),
+                                 &spec.stub_returns(), &stub_doc));
+        signatures.push(format!("{}({})", py_name, spec.stub_params(None)));
+
+        // arguments get extracted before the call, errors of the call are not ignored
+        let cb = impl_call(cls, name, &spec);
+        let body = if spec.args.iter().all(|arg| arg.py) {
+            // the signature does not accept any argument
+            quote! {
+                match _pyo3::argparse::parse_args(
+                    Some(_LOCATION), &[], _args, _kwargs, false, false, &mut [])
+                {
+                    Ok(_) => Ok(#cb),
+                    Err(e) => Err(e)
+                }
+            }
+        } else {
+            impl_arg_params(&spec, quote! { Ok(#cb) })
+        };
+        let output = &spec.output;
+        bodies.push(quote! {
+            {
+                let _result: _pyo3::PyResult<#output> = {
+                    #body
+                };
+                // only a signature mismatch falls through to the next signature
+                match _result {
+                    Ok(_result) => return _pyo3::callback::cb_convert(
+                        _pyo3::callback::PyObjectCallbackConverter, _py, _result),
+                    Err(ref e) if e.is_instance::<_pyo3::exc::TypeError>(_py) => (),
+                    Err(e) => {
+                        e.restore(_py);
+                        return std::ptr::null_mut()
+                    }
+                }
+            }
+        });
     }
+    let doc = doc.unwrap_or_else(|| syn::Lit::Str("\0".to_owned(), syn::StrStyle::Cooked));
+
+    let def = quote! {
+        _pyo3::class::PyMethodDefType::Method({
+            #[allow(unused_mut)]
+            unsafe extern "C" fn __wrap(
+                _slf: *mut _pyo3::ffi::PyObject,
+                _args: *mut _pyo3::ffi::PyObject,
+                _kwargs: *mut _pyo3::ffi::PyObject) -> *mut _pyo3::ffi::PyObject
+            {
+                const _LOCATION: &'static str = concat!(stringify!(#cls), ".", #py_name, "()");
+                let _pool = _pyo3::GILPool::new();
+                let _py = _pyo3::Python::assume_gil_acquired();
+                let _slf = _py.mut_from_borrowed_ptr::<#cls>(_slf);
+                let _args = _py.from_borrowed_ptr::<_pyo3::PyTuple>(_args);
+                let _kwargs = _pyo3::argparse::get_kwargs(_py, _kwargs);
+
+                #(#bodies)*
+
+                _pyo3::argparse::overload_error(_LOCATION, &[#(#signatures),*]).restore(_py);
+                std::ptr::null_mut()
+            }
+
+            _pyo3::class::PyMethodDef {
+                ml_name: #py_name,
+                ml_meth: _pyo3::class::PyMethodType::PyCFunctionWithKeywords(__wrap),
+                ml_flags: _pyo3::ffi::METH_VARARGS | _pyo3::ffi::METH_KEYWORDS,
+                ml_doc: #doc,
+            }
+        })
+    };
+    (def, stubs)
 }
 
 
@@ -67,7 +207,16 @@ pub fn impl_wrap(cls: &Box<syn::Ty>, name: &syn::Ident, spec: &FnSpec, noargs: b
             }
         }
     } else {
-        let body = impl_arg_params(&spec, cb);
+        let body = impl_arg_params(&spec, cb.clone());
+        // `__call__` is called through `tp_call` slot
+        let fast = if noargs {
+            impl_wrap_fast(
+                &quote! { concat!(stringify!(#cls), ".", stringify!(#name), "()") },
+                &quote! { let _slf = _py.mut_from_borrowed_ptr::<#cls>(_slf); },
+                spec, cb)
+        } else {
+            Tokens::new()
+        };
 
         quote! {
             unsafe extern "C" fn __wrap(
@@ -90,6 +239,52 @@ pub fn impl_wrap(cls: &Box<syn::Ty>, name: &syn::Ident, spec: &FnSpec, noargs: b
                 _pyo3::callback::cb_convert(
                     _pyo3::callback::PyObjectCallbackConverter, _py, _result)
             }
+
+            #fast
+        }
+    }
+}
+
+/// Generate `__wrap_fast`, the `METH_FASTCALL` variant of the wrapper,
+/// if the arguments of the function allow it. `prologue` converts `_slf`.
+pub fn impl_wrap_fast(location: &Tokens, prologue: &Tokens, spec: &FnSpec, cb: Tokens) -> Tokens {
+    if !spec.fastcall() {
+        return Tokens::new()
+    }
+    let body = impl_arg_params_fast(spec, cb);
+    let output = &spec.output;
+
+    quote! {
+        #[allow(unused_mut)]
+        unsafe extern "C" fn __wrap_fast(
+            _slf: *mut _pyo3::ffi::PyObject,
+            _args: *const *mut _pyo3::ffi::PyObject,
+            _nargs: _pyo3::ffi::Py_ssize_t,
+            _kwnames: *mut _pyo3::ffi::PyObject) -> *mut _pyo3::ffi::PyObject
+        {
+            const _LOCATION: &'static str = #location;
+            let _pool = _pyo3::GILPool::new();
+            let _py = _pyo3::Python::assume_gil_acquired();
+            #prologue
+
+            let _result: #output = {
+                #body
+            };
+            _pyo3::callback::cb_convert(
+                _pyo3::callback::PyObjectCallbackConverter, _py, _result)
+        }
+    }
+}
+
+/// `ml_meth` of the method definition, prefers `__wrap_fast` if it was generated
+pub fn impl_method_type(spec: &FnSpec) -> Tokens {
+    if spec.fastcall() {
+        quote! {
+            _pyo3::class::PyMethodType::PyCFunctionFastWithKeywords(__wrap_fast, __wrap)
+        }
+    } else {
+        quote! {
+            _pyo3::class::PyMethodType::PyCFunctionWithKeywords(__wrap)
         }
     }
 }
@@ -147,7 +342,7 @@ pub fn impl_wrap_new(cls: &Box<syn::Ty>, name: &syn::Ident, spec: &FnSpec) -> To
             const _LOCATION: &'static str = concat!(stringify!(#cls),".",stringify!(#name),"()");
             let _pool = _pyo3::GILPool::new();
             let _py = _pyo3::Python::assume_gil_acquired();
-            match _pyo3::typeob::PyRawObject::new(_py, #cls::type_object(), _cls) {
+            match _pyo3::typeob::PyRawObject::new_with_args::<#cls>(_py, _cls, _args, _kwargs) {
                 Ok(_obj) => {
                     let _args = _py.from_borrowed_ptr::<_pyo3::PyTuple>(_args);
                     let _kwargs = _pyo3::argparse::get_kwargs(_py, _kwargs);
@@ -214,8 +409,14 @@ pub fn impl_wrap_class(cls: &Box<syn::Ty>, name: &syn::Ident, spec: &FnSpec) ->
     let cb = quote! {{
         #cls::#name(&_cls, #(#names),*)
     }};
-    let body = impl_arg_params(spec, cb);
+    let body = impl_arg_params(spec, cb.clone());
     let output = &spec.output;
+    let fast = impl_wrap_fast(
+        &quote! { concat!(stringify!(#cls), ".", stringify!(#name), "()") },
+        &quote! {
+            let _cls = _pyo3::PyType::from_type_ptr(_py, _slf as *mut _pyo3::ffi::PyTypeObject);
+        },
+        spec, cb);
 
     quote! {
         #[allow(unused_mut)]
@@ -237,6 +438,7 @@ pub fn impl_wrap_class(cls: &Box<syn::Ty>, name: &syn::Ident, spec: &FnSpec) ->
             _pyo3::callback::cb_convert(
                 _pyo3::callback::PyObjectCallbackConverter, _py, _result)
         }
+        #fast
     }
 }
 
@@ -249,8 +451,11 @@ pub fn impl_wrap_static(cls: &Box<syn::Ty>, name: &syn::Ident, spec: &FnSpec) ->
         #cls::#name(#(#names),*)
     }};
 
-    let body = impl_arg_params(spec, cb);
+    let body = impl_arg_params(spec, cb.clone());
     let output = &spec.output;
+    let fast = impl_wrap_fast(
+        &quote! { concat!(stringify!(#cls), ".", stringify!(#name), "()") },
+        &Tokens::new(), spec, cb);
 
     quote! {
         #[allow(unused_mut)]
@@ -271,6 +476,7 @@ pub fn impl_wrap_static(cls: &Box<syn::Ty>, name: &syn::Ident, spec: &FnSpec) ->
             _pyo3::callback::cb_convert(
                 _pyo3::callback::PyObjectCallbackConverter, _py, _result)
         }
+        #fast
     }
 }
 
@@ -316,6 +522,11 @@ pub(crate) fn impl_wrap_setter(cls: &Box<syn::Ty>, name: &syn::Ident, spec: &FnS
             const _LOCATION: &'static str = concat!(stringify!(#cls),".",stringify!(#name),"()");
             let _pool = _pyo3::GILPool::new();
             let _py = _pyo3::Python::assume_gil_acquired();
+            if _value.is_null() {
+                _pyo3::PyErr::new::<_pyo3::exc::AttributeError, _>(
+                    "can't delete attribute").restore(_py);
+                return -1
+            }
             let _slf = _py.mut_from_borrowed_ptr::<#cls>(_slf);
             let _value = _py.from_borrowed_ptr(_value);
 
@@ -334,6 +545,34 @@ pub(crate) fn impl_wrap_setter(cls: &Box<syn::Ty>, name: &syn::Ident, spec: &FnS
     }
 }
 
+/// Generate deleter wrapper, called through the `set` slot with NULL value
+pub(crate) fn impl_wrap_deleter(cls: &Box<syn::Ty>, name: &syn::Ident) -> Tokens {
+    quote! {
+        unsafe extern "C" fn __wrap(
+            _slf: *mut _pyo3::ffi::PyObject,
+            _value: *mut _pyo3::ffi::PyObject, _: *mut _pyo3::c_void) -> _pyo3::c_int
+        {
+            const _LOCATION: &'static str = concat!(stringify!(#cls),".",stringify!(#name),"()");
+            let _pool = _pyo3::GILPool::new();
+            let _py = _pyo3::Python::assume_gil_acquired();
+            if !_value.is_null() {
+                _pyo3::PyErr::new::<_pyo3::exc::AttributeError, _>(
+                    "can't set attribute").restore(_py);
+                return -1
+            }
+            let _slf = _py.mut_from_borrowed_ptr::<#cls>(_slf);
+
+            match _slf.#name() {
+                Ok(_) => 0,
+                Err(e) => {
+                    e.restore(_py);
+                    -1
+                }
+            }
+        }
+    }
+}
+
 
 fn impl_call(_cls: &Box<syn::Ty>, fname: &syn::Ident, spec: &FnSpec) -> Tokens {
     let names: Vec<syn::Ident> = spec.args.iter().enumerate().map(
@@ -349,6 +588,26 @@ fn impl_call(_cls: &Box<syn::Ty>, fname: &syn::Ident, spec: &FnSpec) -> Tokens {
 }
 
 pub fn impl_arg_params(spec: &FnSpec, body: Tokens) -> Tokens {
+    let accept_args = syn::Ident::from(
+        if spec.accept_args() { "true" } else { "false" });
+    let accept_kwargs = syn::Ident::from(
+        if spec.accept_kwargs() { "true" } else { "false" });
+
+    impl_arg_params_with(spec, body, quote! {
+        _pyo3::argparse::parse_args(Some(_LOCATION), _PARAMS, &_args,
+            _kwargs, #accept_args, #accept_kwargs, &mut _output)
+    })
+}
+
+/// Parses the arguments of `METH_FASTCALL` wrapper, see `FnSpec::fastcall()`
+pub fn impl_arg_params_fast(spec: &FnSpec, body: Tokens) -> Tokens {
+    impl_arg_params_with(spec, body, quote! {
+        _pyo3::argparse::parse_fastcall_args(_py, Some(_LOCATION), _PARAMS,
+            _args, _nargs, _kwnames, &mut _output)
+    })
+}
+
+fn impl_arg_params_with(spec: &FnSpec, body: Tokens, parse: Tokens) -> Tokens {
     let args: Vec<FnArg> = spec.args.iter()
         .filter(|item| !item.py).map(|item| item.clone()).collect();
     if args.is_empty() {
@@ -362,12 +621,17 @@ pub fn impl_arg_params(spec: &FnSpec, body: Tokens) -> Tokens {
             continue
         }
         if ! (spec.is_args(&arg.name) || spec.is_kwargs(&arg.name)) {
-            let name = arg.name.as_ref();
+            let name = spec.py_name(&arg.name);
             let kwonly = if spec.is_kw_only(&arg.name) {
                 syn::Ident::from("true")
             } else {
                 syn::Ident::from("false")
             };
+            let posonly = if spec.is_pos_only(&arg.name) {
+                syn::Ident::from("true")
+            } else {
+                syn::Ident::from("false")
+            };
 
             let opt = if let Some(_) = arg.optional {
                 syn::Ident::from("true")
@@ -380,7 +644,8 @@ pub fn impl_arg_params(spec: &FnSpec, body: Tokens) -> Tokens {
             params.push(
                 quote! {
                     _pyo3::argparse::ParamDescription{
-                        name: #name, is_optional: #opt, kw_only: #kwonly}
+                        name: #name, is_optional: #opt, kw_only: #kwonly,
+                        pos_only: #posonly}
                 }
             );
         }
@@ -397,11 +662,6 @@ pub fn impl_arg_params(spec: &FnSpec, body: Tokens) -> Tokens {
         body = impl_arg_param(&arg, &spec, &body, len-idx-1);
     }
 
-    let accept_args = syn::Ident::from(
-        if spec.accept_args() { "true" } else { "false" });
-    let accept_kwargs = syn::Ident::from(
-        if spec.accept_kwargs() { "true" } else { "false" });
-
     // create array of arguments, and then parse
     quote! {
         const _PARAMS: &'static [_pyo3::argparse::ParamDescription<'static>] = &[
@@ -409,8 +669,7 @@ pub fn impl_arg_params(spec: &FnSpec, body: Tokens) -> Tokens {
         ];
 
         let mut _output = [#(#placeholders),*];
-        match _pyo3::argparse::parse_args(Some(_LOCATION), _PARAMS, &_args,
-            _kwargs, #accept_args, #accept_kwargs, &mut _output)
+        match #parse
         {
             Ok(_) => {
                 let mut _iter = _output.iter();
@@ -435,21 +694,63 @@ fn impl_arg_param(arg: &FnArg, spec: &FnSpec, body: &Tokens, idx: usize) -> Toke
     // parse_args already checked for).
 
     if spec.is_args(&name) {
-        quote! {
-            match <#ty as _pyo3::FromPyObject>::extract(_args.as_ref())
+        let py_name = name.as_ref();
+        let extract = match variadic_params(ty, &["Vec"]) {
+            // `&PyTuple`, the tuple of the extra positional arguments
+            None if is_native_ref(ty, "PyTuple") => quote! { Ok(_varargs) },
+            Some(ref params) if params.len() == 1 => {
+                let item = params[0];
+                quote! {
+                    _pyo3::argparse::extract_args::<#item>(_LOCATION, #py_name, _varargs)
+                }
+            }
+            _ => quote! {
+                _pyo3::argparse::extract_variadic::<#ty>(_LOCATION, #py_name, _varargs.as_ref())
+            },
+        };
+        quote! {{
+            let _varargs = _pyo3::argparse::extra_args(_py, _PARAMS, _args);
+            match #extract
             {
                 Ok(#arg_name) => {
                     #body
                 }
                 Err(e) => Err(e)
             }
-        }
+        }}
     }
     else if spec.is_kwargs(&name) {
-        quote! {{
-            let #arg_name = _kwargs;
-            #body
-        }}
+        let py_name = name.as_ref();
+        let extract = match variadic_params(ty, &["HashMap", "BTreeMap"]) {
+            // `Option<&PyDict>`, the dict of the extra keyword arguments
+            None if arg.optional.map(|ty| is_native_ref(ty, "PyDict")) == Some(true) =>
+                quote! { Ok(_varkwargs) },
+            Some(ref params) if params.len() >= 2 => {
+                let key = params[0];
+                let value = params[1];
+                quote! {
+                    _pyo3::argparse::extract_kwargs::<#key, #value, #ty>(
+                        _LOCATION, #py_name, _varkwargs)
+                }
+            }
+            _ => quote! {
+                _pyo3::argparse::extract_variadic::<#ty>(
+                    _LOCATION, #py_name,
+                    _varkwargs.unwrap_or_else(|| _pyo3::PyDict::new(_py)).as_ref())
+            },
+        };
+        quote! {
+            match _pyo3::argparse::extra_kwargs(_PARAMS, _kwargs) {
+                Ok(_varkwargs) => match #extract
+                {
+                    Ok(#arg_name) => {
+                        #body
+                    }
+                    Err(e) => Err(e)
+                },
+                Err(e) => Err(e)
+            }
+        }
     }
     else {
         if let Some(_) = arg.optional {
@@ -514,6 +815,30 @@ fn impl_arg_param(arg: &FnArg, spec: &FnSpec, body: &Tokens, idx: usize) -> Toke
     }
 }
 
+/// Type parameters of the variadic argument type, if the type is one of `names`
+pub(crate) fn variadic_params<'a>(ty: &'a syn::Ty, names: &[&str]) -> Option<Vec<&'a syn::Ty>> {
+    if let syn::Ty::Path(_, ref path) = *ty {
+        if let Some(segment) = path.segments.last() {
+            if names.contains(&segment.ident.as_ref()) {
+                if let syn::PathParameters::AngleBracketed(ref data) = segment.parameters {
+                    return Some(data.types.iter().collect())
+                }
+            }
+        }
+    }
+    None
+}
+
+/// `&PyTuple` or `&PyDict` reference
+fn is_native_ref(ty: &syn::Ty, name: &str) -> bool {
+    if let syn::Ty::Rptr(_, ref ty) = *ty {
+        if let syn::Ty::Path(_, ref path) = ty.ty {
+            return path.segments.last().map(|s| s.ident.as_ref() == name) == Some(true)
+        }
+    }
+    false
+}
+
 pub fn impl_py_method_def(name: &syn::Ident, doc: syn::Lit, spec: &FnSpec, wrapper: &Tokens)
                           -> Tokens
 {
@@ -531,13 +856,14 @@ pub fn impl_py_method_def(name: &syn::Ident, doc: syn::Lit, spec: &FnSpec, wrapp
             })
         }
     } else {
+        let meth = impl_method_type(spec);
         quote! {
             _pyo3::class::PyMethodDefType::Method({
                 #wrapper
 
                 _pyo3::class::PyMethodDef {
                     ml_name: stringify!(#name),
-                    ml_meth: _pyo3::class::PyMethodType::PyCFunctionWithKeywords(__wrap),
+                    ml_meth: #meth,
                     ml_flags: _pyo3::ffi::METH_VARARGS | _pyo3::ffi::METH_KEYWORDS,
                     ml_doc: #doc,
                 }
@@ -578,15 +904,17 @@ pub fn impl_py_method_def_init(name: &syn::Ident, doc: syn::Lit, wrapper: &Token
     }
 }
 
-pub fn impl_py_method_def_class(name: &syn::Ident, doc: syn::Lit, wrapper: &Tokens) -> Tokens
+pub fn impl_py_method_def_class(name: &syn::Ident, doc: syn::Lit, spec: &FnSpec, wrapper: &Tokens)
+                                -> Tokens
 {
+    let meth = impl_method_type(spec);
     quote! {
         _pyo3::class::PyMethodDefType::Class({
             #wrapper
 
             _pyo3::class::PyMethodDef {
                 ml_name: stringify!(#name),
-                ml_meth: _pyo3::class::PyMethodType::PyCFunctionWithKeywords(__wrap),
+                ml_meth: #meth,
                 ml_flags: _pyo3::ffi::METH_VARARGS | _pyo3::ffi::METH_KEYWORDS |
                 _pyo3::ffi::METH_CLASS,
                 ml_doc: #doc,
@@ -595,15 +923,17 @@ pub fn impl_py_method_def_class(name: &syn::Ident, doc: syn::Lit, wrapper: &Toke
     }
 }
 
-pub fn impl_py_method_def_static(name: &syn::Ident, doc: syn::Lit, wrapper: &Tokens) -> Tokens
+pub fn impl_py_method_def_static(name: &syn::Ident, doc: syn::Lit, spec: &FnSpec, wrapper: &Tokens)
+                                -> Tokens
 {
+    let meth = impl_method_type(spec);
     quote! {
         _pyo3::class::PyMethodDefType::Static({
             #wrapper
 
             _pyo3::class::PyMethodDef {
                 ml_name: stringify!(#name),
-                ml_meth: _pyo3::class::PyMethodType::PyCFunctionWithKeywords(__wrap),
+                ml_meth: #meth,
                 ml_flags: _pyo3::ffi::METH_VARARGS | _pyo3::ffi::METH_KEYWORDS | _pyo3::ffi::METH_STATIC,
                 ml_doc: #doc,
             }
@@ -611,6 +941,33 @@ pub fn impl_py_method_def_static(name: &syn::Ident, doc: syn::Lit, wrapper: &Tok
     }
 }
 
+pub fn impl_py_class_attribute(cls: &Box<syn::Ty>, name: &syn::Ident, spec: &FnSpec) -> Tokens
+{
+    let names: Vec<syn::Ident> = spec.args.iter().map(|arg| {
+        if arg.py {
+            syn::Ident::from("_py")
+        } else {
+            panic!("class attribute can not have arguments: {:?}", name)
+        }
+    }).collect();
+
+    let output = &spec.output;
+
+    quote! {
+        _pyo3::class::PyMethodDefType::ClassAttribute({
+            fn __wrap(_py: _pyo3::Python) -> _pyo3::PyResult<_pyo3::PyObject> {
+                let _result: #output = #cls::#name(#(#names),*);
+                _result.map(|value| _pyo3::IntoPyObject::into_object(value, _py))
+            }
+
+            _pyo3::class::PyClassAttributeDef {
+                name: stringify!(#name),
+                meth: __wrap,
+            }
+        })
+    }
+}
+
 pub fn impl_py_method_def_call(name: &syn::Ident, doc: syn::Lit, wrapper: &Tokens) -> Tokens
 {
     quote! {
@@ -630,16 +987,7 @@ pub fn impl_py_method_def_call(name: &syn::Ident, doc: syn::Lit, wrapper: &Token
 pub(crate) fn impl_py_setter_def(name: &syn::Ident, doc: syn::Lit, setter: &Option<String>, wrapper: &Tokens)
     -> Tokens
 {
-    let n = if let &Some(ref name) = setter {
-        name.to_string()
-    } else {
-        let n = String::from(name.as_ref());
-        if n.starts_with("set_") {
-            n[4..].to_string()
-        } else {
-            n
-        }
-    };
+    let n = py_property_name(name, setter, "set_");
 
     quote! {
         _pyo3::class::PyMethodDefType::Setter({
@@ -654,19 +1002,28 @@ pub(crate) fn impl_py_setter_def(name: &syn::Ident, doc: syn::Lit, setter: &Opti
     }
 }
 
+pub(crate) fn impl_py_deleter_def(name: &syn::Ident, doc: syn::Lit, deleter: &Option<String>, wrapper: &Tokens)
+    -> Tokens
+{
+    let n = py_property_name(name, deleter, "del_");
+
+    quote! {
+        _pyo3::class::PyMethodDefType::Deleter({
+            #wrapper
+
+            _pyo3::class::PyDeleterDef {
+                name: #n,
+                meth: __wrap,
+                doc: #doc,
+            }
+        })
+    }
+}
+
 pub(crate) fn impl_py_getter_def(name: &syn::Ident, doc: syn::Lit, getter: &Option<String>, wrapper: &Tokens)
     -> Tokens
 {
-    let n = if let &Some(ref name) = getter {
-        name.to_string()
-    } else {
-        let n = String::from(name.as_ref());
-        if n.starts_with("get_") {
-            n[4..].to_string()
-        } else {
-            n
-        }
-    };
+    let n = py_property_name(name, getter, "get_");
 
     quote! {
         _pyo3::class::PyMethodDefType::Getter({
@@ -680,3 +1037,17 @@ pub(crate) fn impl_py_getter_def(name: &syn::Ident, doc: syn::Lit, getter: &Opti
         })
     }
 }
+
+/// Python name of the property, the explicit name or the method name without the prefix
+pub(crate) fn py_property_name(name: &syn::Ident, py_name: &Option<String>, prefix: &str) -> String {
+    if let &Some(ref name) = py_name {
+        name.to_string()
+    } else {
+        let n = String::from(name.as_ref());
+        if n.starts_with(prefix) {
+            n[prefix.len()..].to_string()
+        } else {
+            n
+        }
+    }
+}
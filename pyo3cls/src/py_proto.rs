@@ -7,6 +7,7 @@ use defs;
 use py_method;
 use method::FnSpec;
 use func::impl_method_proto;
+use stub;
 
 
 pub fn build_py_proto(ast: &mut syn::Item) -> Tokens {
@@ -72,10 +73,19 @@ fn impl_proto_impl(ty: &Box<syn::Ty>,
 {
     let mut tokens = Tokens::new();
     let mut py_methods = Vec::new();
+    let mut stubs = Vec::new();
 
     for iimpl in impls.iter_mut() {
         match iimpl.node {
             syn::ImplItemKind::Method(ref mut sig, _) => {
+                // buffer and gc methods are not visible from python
+                let ident = iimpl.ident.as_ref();
+                if proto.name != "Buffer" && proto.name != "GC" &&
+                    (proto.methods.iter().any(|m| m.eq(ident)) ||
+                     proto.py_methods.iter().any(|m| m.name == ident))
+                {
+                    stubs.push(stub::proto_stub(&iimpl.ident, sig, &mut iimpl.attrs));
+                }
                 for m in proto.methods {
                     if m.eq(iimpl.ident.as_ref()) {
                         impl_method_proto(ty, sig, m).to_tokens(&mut tokens);
@@ -122,6 +132,12 @@ fn impl_proto_impl(ty: &Box<syn::Ty>,
     _ => "PROTO_METHODS"
     };
 
+    let stubs = if stubs.is_empty() {
+        Tokens::new()
+    } else {
+        stub::impl_stubs(ty, p, stubs)
+    };
+
     let dummy_const = syn::Ident::new(format!("_IMPL_PYO3_{}_{}", n, p));
     quote! {
         #[feature(specialization)]
@@ -133,6 +149,8 @@ fn impl_proto_impl(ty: &Box<syn::Ty>,
             #tokens
 
             #(#py_methods)*
+
+            #stubs
         };
     }
 }
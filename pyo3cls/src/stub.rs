@@ -0,0 +1,190 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Metadata for `.pyi` stub files, the stubs get rendered by `pyo3::stubs`
+
+use syn;
+use quote::Tokens;
+
+use method::{FnSpec, FnType};
+use py_method::py_property_name;
+use utils;
+
+
+/// Parses `#[stub(arg = "annotation", returns = "annotation")]` attribute
+pub fn parse_stub_attr(meta: &[syn::NestedMetaItem]) -> Vec<(String, String)> {
+    meta.iter().map(|item| match *item {
+        syn::NestedMetaItem::MetaItem(
+            syn::MetaItem::NameValue(ref key, syn::Lit::Str(ref s, _))) => {
+            (key.as_ref().to_owned(), s.clone())
+        }
+        _ => panic!("stub attribute requires `name = \"annotation\"` values: {:?}", item),
+    }).collect()
+}
+
+/// Python annotation for the rust type, unknown types are expected to be `#[class]` types
+pub fn annotation(ty: &syn::Ty) -> String {
+    match *ty {
+        syn::Ty::Infer => "None".to_owned(),
+        syn::Ty::Tup(ref tys) => {
+            if tys.is_empty() {
+                "None".to_owned()
+            } else {
+                let tys: Vec<String> = tys.iter().map(annotation).collect();
+                format!("Tuple[{}]", tys.join(", "))
+            }
+        }
+        syn::Ty::Rptr(_, ref ty) => annotation(&ty.ty),
+        syn::Ty::Paren(ref ty) => annotation(ty),
+        syn::Ty::Slice(ref ty) | syn::Ty::Array(ref ty, _) => format!("List[{}]", annotation(ty)),
+        syn::Ty::Path(_, ref path) => path_annotation(path),
+        _ => "Any".to_owned(),
+    }
+}
+
+fn path_annotation(path: &syn::Path) -> String {
+    // associated types, i.e. `Self::Result` of the protocol traits
+    if path.segments.len() > 1 && path.segments[0].ident.as_ref() == "Self" {
+        return "Any".to_owned()
+    }
+    let segment = match path.segments.last() {
+        Some(segment) => segment,
+        None => return "Any".to_owned(),
+    };
+    let params: Vec<String> = match segment.parameters {
+        syn::PathParameters::AngleBracketed(ref data) =>
+            data.types.iter().map(annotation).collect(),
+        _ => Vec::new(),
+    };
+    let param = |idx: usize| params.get(idx).cloned().unwrap_or_else(|| "Any".to_owned());
+
+    match segment.ident.as_ref() {
+        "bool" | "PyBool" => "bool".to_owned(),
+        "i8" | "i16" | "i32" | "i64" | "i128" | "isize" |
+        "u8" | "u16" | "u32" | "u64" | "u128" | "usize" | "PyLong" | "PyInt" => "int".to_owned(),
+        "f32" | "f64" | "PyFloat" => "float".to_owned(),
+        "PyComplex" => "complex".to_owned(),
+        "String" | "str" | "char" | "PyString" | "PyUnicode" => "str".to_owned(),
+        "PyBytes" => "bytes".to_owned(),
+        "PyByteArray" => "bytearray".to_owned(),
+        "PySlice" => "slice".to_owned(),
+        "PyType" => "type".to_owned(),
+        "Vec" | "VecDeque" | "LinkedList" => format!("List[{}]", param(0)),
+        "HashSet" | "BTreeSet" => format!("Set[{}]", param(0)),
+        "HashMap" | "BTreeMap" => format!("Dict[{}, {}]", param(0), param(1)),
+        "PyList" => "List[Any]".to_owned(),
+        "PySet" => "Set[Any]".to_owned(),
+        "PyFrozenSet" => "FrozenSet[Any]".to_owned(),
+        "PyDict" => "Dict[Any, Any]".to_owned(),
+        "PyTuple" => "Tuple[Any, ...]".to_owned(),
+        "Option" => format!("Optional[{}]", param(0)),
+        "PyResult" | "Result" | "Box" | "Rc" | "Arc" | "Cow" | "Py" => param(0),
+        "PyObject" | "PyObjectRef" | "Self" | "PyModule" | "PyIterator" => "Any".to_owned(),
+        name => name.to_owned(),
+    }
+}
+
+/// Stub of `#[methods]` item, the kind and the python name follow the method type
+pub fn method_stub(name: &syn::Ident, spec: &FnSpec, doc: &syn::Lit) -> Tokens {
+    let ret = spec.stub_returns();
+    match spec.tp {
+        FnType::Fn =>
+            fn_stub(name.as_ref(), "Method", &spec.stub_params(Some("self")), &ret, doc),
+        FnType::FnNew =>
+            fn_stub("__new__", "New", &spec.stub_params(Some("cls")), "", doc),
+        FnType::FnInit =>
+            fn_stub("__init__", "Method", &spec.stub_params(Some("self")), "None", doc),
+        FnType::FnCall =>
+            fn_stub("__call__", "Method", &spec.stub_params(Some("self")), &ret, doc),
+        FnType::FnClass =>
+            fn_stub(name.as_ref(), "ClassMethod", &spec.stub_params(Some("cls")), &ret, doc),
+        FnType::FnStatic =>
+            fn_stub(name.as_ref(), "StaticMethod", &spec.stub_params(None), &ret, doc),
+        FnType::ClassAttribute =>
+            fn_stub(name.as_ref(), "Attribute", "", &ret, doc),
+        FnType::Getter(ref getter) =>
+            fn_stub(&py_property_name(name, getter, "get_"), "Getter", "self", &ret, doc),
+        FnType::Setter(ref setter) =>
+            fn_stub(&py_property_name(name, setter, "set_"), "Setter",
+                    &spec.stub_params(Some("self")), "None", doc),
+        FnType::Deleter(ref deleter) =>
+            fn_stub(&py_property_name(name, deleter, "del_"), "Deleter", "self", "None", doc),
+    }
+}
+
+/// Stub of the protocol method, protocol methods take positional arguments only
+pub fn proto_stub(name: &syn::Ident, sig: &syn::MethodSig, attrs: &mut Vec<syn::Attribute>) -> Tokens {
+    let mut overrides = Vec::new();
+    attrs.retain(|attr| match attr.value {
+        syn::MetaItem::List(ref name, ref meta) if name.as_ref() == "stub" => {
+            overrides.extend(parse_stub_attr(meta.as_slice()));
+            false
+        }
+        _ => true,
+    });
+    let annotate = |name: &str, ty: &syn::Ty| {
+        overrides.iter().find(|&&(ref key, _)| key == name)
+            .map(|&(_, ref value)| value.clone())
+            .unwrap_or_else(|| annotation(ty))
+    };
+
+    let mut params = Vec::new();
+    for (idx, input) in sig.decl.inputs.iter().enumerate() {
+        match *input {
+            syn::FnArg::SelfRef(_, _) | syn::FnArg::SelfValue(_) =>
+                params.push("self".to_owned()),
+            syn::FnArg::Captured(ref pat, ref ty) => {
+                if let syn::Ty::Path(_, ref path) = *ty {
+                    if path.segments.last().map(|s| s.ident.as_ref() == "Python") == Some(true) {
+                        continue
+                    }
+                }
+                let arg = match *pat {
+                    syn::Pat::Ident(_, ref ident, _) => ident.as_ref().to_owned(),
+                    _ => format!("arg{}", idx),
+                };
+                let ann = annotate(&arg, ty);
+                params.push(format!("{}: {}", arg, ann));
+            }
+            syn::FnArg::Ignored(ref ty) => params.push(format!("arg{}: {}", idx, annotate("", ty))),
+        }
+    }
+    let returns = match sig.decl.output {
+        syn::FunctionRetTy::Default => annotate("returns", &syn::Ty::Infer),
+        syn::FunctionRetTy::Ty(ref ty) => annotate("returns", ty),
+    };
+    fn_stub(name.as_ref(), "Method", &params.join(", "), &returns, &utils::get_doc(attrs, false))
+}
+
+/// `_pyo3::stubs::StubFn` value
+pub fn fn_stub(name: &str, kind: &str, params: &str, returns: &str, doc: &syn::Lit) -> Tokens {
+    let kind = syn::Ident::from(kind);
+    let doc = match *doc {
+        syn::Lit::Str(ref s, _) if s.ends_with('\0') => s[..s.len()-1].to_owned(),
+        syn::Lit::Str(ref s, _) => s.clone(),
+        _ => String::new(),
+    };
+    quote! {
+        _pyo3::stubs::StubFn {
+            name: #name,
+            kind: _pyo3::stubs::StubKind::#kind,
+            params: #params,
+            returns: #returns,
+            doc: #doc,
+        }
+    }
+}
+
+/// Implements `PyStubsImpl` of the source (`Class`, `Methods` or the protocol) for the class
+pub fn impl_stubs(cls: &syn::Ty, source: &str, stubs: Vec<Tokens>) -> Tokens {
+    let source = syn::Ident::from(source);
+    quote! {
+        impl _pyo3::stubs::PyStubsImpl<_pyo3::stubs::source::#source> for #cls {
+            fn stubs() -> &'static [_pyo3::stubs::StubFn] {
+                static STUBS: &'static [_pyo3::stubs::StubFn] = &[
+                    #(#stubs),*
+                ];
+                STUBS
+            }
+        }
+    }
+}
@@ -58,3 +58,12 @@ pub fn get_doc(attrs: &Vec<syn::Attribute>, null_terminated: bool) -> syn::Lit {
         syn::Lit::Str(doc, syn::StrStyle::Cooked)
     }
 }
+
+/// Prepends the signature line to the doc string,
+/// python reads it as `__text_signature__`.
+pub fn doc_with_signature(doc: syn::Lit, signature: &str) -> syn::Lit {
+    match doc {
+        syn::Lit::Str(doc, style) => syn::Lit::Str(format!("{}\n--\n\n{}", signature, doc), style),
+        doc => doc,
+    }
+}
@@ -3,11 +3,15 @@
 // based on Daniel Grunwald's https://github.com/dgrunwald/rust-cpython
 
 //! Python argument parsing
-use conversion::PyTryFrom;
-use err::PyResult;
+use std::iter::FromIterator;
+
+use conversion::{FromPyObject, PyTryFrom};
+use err::{PyErr, PyResult};
 use ffi;
+use instance::{AsPyRef, PyObjectWithToken};
+use objectprotocol::ObjectProtocol;
 use objects::{exc, PyDict, PyObjectRef, PyString, PyTuple};
-use python::Python;
+use python::{IntoPyPointer, Python};
 
 #[derive(Debug)]
 /// Description of a python parameter; used for `parse_args()`.
@@ -16,8 +20,10 @@ pub struct ParamDescription<'a> {
     pub name: &'a str,
     /// Whether the parameter is optional.
     pub is_optional: bool,
-    /// Whether the parameter is optional.
+    /// Whether the parameter is keyword only.
     pub kw_only: bool,
+    /// Whether the parameter is positional only.
+    pub pos_only: bool,
 }
 
 /// Parse argument list
@@ -39,47 +45,145 @@ pub fn parse_args<'p>(
 ) -> PyResult<()> {
     let nargs = args.len();
     let nkeywords = kwargs.map_or(0, |d| d.len());
-    if !accept_args && (nargs + nkeywords > params.len()) {
-        return Err(exc::TypeError::new(format!(
-            "{}{} takes at most {} argument{} ({} given)",
-            fname.unwrap_or("function"),
-            if fname.is_some() { "()" } else { "" },
-            params.len(),
-            if params.len() == 1 { "s" } else { "" },
-            nargs + nkeywords
-        )));
+    let used_keywords = parse_params(
+        fname,
+        params,
+        nargs,
+        nkeywords,
+        accept_args,
+        output,
+        |i| args.get_item(i),
+        |name| kwargs.and_then(|d| d.get_item(name)),
+    )?;
+    if !accept_kwargs && used_keywords != nkeywords {
+        // check for extraneous keyword arguments
+        for item in kwargs.unwrap().items().iter() {
+            let item = <PyTuple as PyTryFrom>::try_from(item)?;
+            let key = <PyString as PyTryFrom>::try_from(item.get_item(0))?.to_string()?;
+            check_keyword(fname, params, &key)?;
+        }
+    }
+    Ok(())
+}
+
+/// Parse argument list of `METH_FASTCALL | METH_KEYWORDS` function
+///
+/// * fname:   Name of the current function
+/// * params:  Declared parameters of the function
+/// * args:    Positional arguments followed by the values of the keyword arguments
+/// * nargs:   Number of the positional arguments
+/// * kwnames: Tuple of the keywords or NULL
+/// * output:  Output array that receives the arguments.
+///            Must have same length as `params` and must be initialized to `None`.
+///
+/// `*args` and `**kwargs` parameters are not supported.
+pub unsafe fn parse_fastcall_args<'p>(
+    py: Python<'p>,
+    fname: Option<&str>,
+    params: &[ParamDescription],
+    args: *const *mut ffi::PyObject,
+    nargs: ffi::Py_ssize_t,
+    kwnames: *mut ffi::PyObject,
+    output: &mut [Option<&'p PyObjectRef>],
+) -> PyResult<()> {
+    let nargs = nargs as usize;
+    let arg = |i: usize| py.from_borrowed_ptr::<PyObjectRef>(*args.offset(i as isize));
+
+    let mut keywords = Vec::new();
+    if !kwnames.is_null() {
+        for key in py.from_borrowed_ptr::<PyTuple>(kwnames).iter() {
+            let key = <PyString as PyTryFrom>::try_from(key)?.to_string()?;
+            check_keyword(fname, params, &key)?;
+            keywords.push(key);
+        }
+    }
+    parse_params(
+        fname,
+        params,
+        nargs,
+        keywords.len(),
+        false,
+        output,
+        &arg,
+        |name| {
+            keywords
+                .iter()
+                .position(|key| key == name)
+                .map(|idx| arg(nargs + idx))
+        },
+    )?;
+    Ok(())
+}
+
+/// Assigns the positional and the keyword arguments to the parameters,
+/// returns the number of the used keyword arguments.
+fn parse_params<'p, A, K>(
+    fname: Option<&str>,
+    params: &[ParamDescription],
+    nargs: usize,
+    nkeywords: usize,
+    accept_args: bool,
+    output: &mut [Option<&'p PyObjectRef>],
+    arg: A,
+    kwarg: K,
+) -> PyResult<usize>
+where
+    A: Fn(usize) -> &'p PyObjectRef,
+    K: Fn(&str) -> Option<&'p PyObjectRef>,
+{
+    let fname = fname.unwrap_or("function");
+    if !accept_args {
+        let npositional = params.iter().filter(|p| !p.kw_only).count();
+        if nargs > npositional {
+            return Err(exc::TypeError::new(format!(
+                "{} takes at most {} positional argument{} ({} given)",
+                fname,
+                npositional,
+                plural(npositional),
+                nargs
+            )));
+        }
+        if nargs + nkeywords > params.len() {
+            return Err(exc::TypeError::new(format!(
+                "{} takes at most {} argument{} ({} given)",
+                fname,
+                params.len(),
+                plural(params.len()),
+                nargs + nkeywords
+            )));
+        }
     }
     let mut used_keywords = 0;
     // Iterate through the parameters and assign values to output:
     for (i, (p, out)) in params.iter().zip(output).enumerate() {
-        match kwargs.and_then(|d| d.get_item(p.name)) {
+        // positional only parameters can not be passed by name
+        let value = if p.pos_only { None } else { kwarg(p.name) };
+        match value {
             Some(kwarg) => {
                 *out = Some(kwarg);
                 used_keywords += 1;
                 if i < nargs {
                     return Err(exc::TypeError::new(format!(
-                        "Argument given by name ('{}') and position ({})",
+                        "argument for {} given by name ('{}') and position ({})",
+                        fname,
                         p.name,
                         i + 1
                     )));
                 }
             }
             None => {
-                if p.kw_only {
-                    if !p.is_optional {
-                        return Err(exc::TypeError::new(format!(
-                            "Required argument ('{}') is keyword only argument",
-                            p.name
-                        )));
-                    }
-                    *out = None;
-                } else if i < nargs {
-                    *out = Some(args.get_item(i));
+                if !p.kw_only && i < nargs {
+                    *out = Some(arg(i));
                 } else {
                     *out = None;
                     if !p.is_optional {
+                        if p.pos_only && kwarg(p.name).is_some() {
+                            return Err(pos_only_error(fname, p.name));
+                        }
                         return Err(exc::TypeError::new(format!(
-                            "Required argument ('{}') (pos {}) not found",
+                            "{} missing required {}argument '{}' (pos {})",
+                            fname,
+                            if p.kw_only { "keyword-only " } else { "" },
                             p.name,
                             i + 1
                         )));
@@ -88,20 +192,139 @@ pub fn parse_args<'p>(
             }
         }
     }
-    if !accept_kwargs && used_keywords != nkeywords {
-        // check for extraneous keyword arguments
-        for item in kwargs.unwrap().items().iter() {
-            let item = <PyTuple as PyTryFrom>::try_from(item)?;
-            let key = <PyString as PyTryFrom>::try_from(item.get_item(0))?.to_string()?;
-            if !params.iter().any(|p| p.name == key) {
-                return Err(exc::TypeError::new(format!(
-                    "'{}' is an invalid keyword argument for this function",
-                    key
-                )));
-            }
+    Ok(used_keywords)
+}
+
+fn check_keyword(fname: Option<&str>, params: &[ParamDescription], key: &str) -> PyResult<()> {
+    let fname = fname.unwrap_or("function");
+    match params.iter().find(|p| p.name == key) {
+        Some(p) if p.pos_only => Err(pos_only_error(fname, key)),
+        Some(_) => Ok(()),
+        None => Err(exc::TypeError::new(format!(
+            "'{}' is an invalid keyword argument for {}",
+            key, fname
+        ))),
+    }
+}
+
+fn pos_only_error(fname: &str, name: &str) -> PyErr {
+    exc::TypeError::new(format!(
+        "{} got some positional-only arguments passed as keyword arguments: '{}'",
+        fname, name
+    ))
+}
+
+fn plural(n: usize) -> &'static str {
+    if n == 1 {
+        ""
+    } else {
+        "s"
+    }
+}
+
+/// Error of the overloaded method, none of the signatures accepts the arguments
+pub fn overload_error(fname: &str, signatures: &[&str]) -> PyErr {
+    let mut msg = format!("{} arguments do not match any of the signatures:", fname);
+    for signature in signatures {
+        msg.push_str("\n    ");
+        msg.push_str(signature);
+    }
+    exc::TypeError::new(msg)
+}
+
+/// Positional arguments that are not assigned to the parameters, the value of `*args`
+pub fn extra_args<'p>(py: Python<'p>, params: &[ParamDescription], args: &'p PyTuple) -> &'p PyTuple {
+    let npositional = params.iter().filter(|p| !p.kw_only).count();
+    if npositional == 0 {
+        args
+    } else {
+        unsafe { py.from_owned_ptr(args.split_from(npositional as isize).into_ptr()) }
+    }
+}
+
+/// Keyword arguments that are not assigned to the parameters, the value of `**kwargs`
+pub fn extra_kwargs<'p>(
+    params: &[ParamDescription],
+    kwargs: Option<&'p PyDict>,
+) -> PyResult<Option<&'p PyDict>> {
+    let kwargs = match kwargs {
+        Some(kwargs) => kwargs,
+        None => return Ok(None),
+    };
+    // positional only parameters do not consume keywords
+    let mut named = params.iter().filter(|p| !p.pos_only);
+    if !named.any(|p| kwargs.get_item(p.name).is_some()) {
+        return Ok(Some(kwargs));
+    }
+    let extra = kwargs.copy()?;
+    for p in params.iter().filter(|p| !p.pos_only) {
+        if extra.contains(p.name)? {
+            extra.del_item(p.name)?;
         }
     }
-    Ok(())
+    Ok(if extra.is_empty() { None } else { Some(extra) })
+}
+
+/// Converts the items of `*args`, the error reports the position of the item
+pub fn extract_args<'p, T>(fname: &str, name: &str, args: &'p PyTuple) -> PyResult<Vec<T>>
+where
+    T: FromPyObject<'p>,
+{
+    args.iter()
+        .enumerate()
+        .map(|(idx, item)| {
+            item.extract().map_err(|e| {
+                arg_error(args.py(), e, format!("{} argument '{}' item {}", fname, name, idx))
+            })
+        })
+        .collect()
+}
+
+/// Converts `**kwargs` to a map, the error reports the keyword
+pub fn extract_kwargs<'p, K, V, M>(
+    fname: &str,
+    name: &str,
+    kwargs: Option<&'p PyDict>,
+) -> PyResult<M>
+where
+    K: FromPyObject<'p>,
+    V: FromPyObject<'p>,
+    M: FromIterator<(K, V)>,
+{
+    let kwargs = match kwargs {
+        Some(kwargs) => kwargs,
+        None => return Ok(M::from_iter(None)),
+    };
+    kwargs
+        .iter()
+        .map(|(key, value)| {
+            let context = || format!("{} argument '{}' key {}", fname, name, key.repr()
+                .map(|repr| repr.to_string_lossy().into_owned())
+                .unwrap_or_default());
+            let key = key.extract().map_err(|e| arg_error(kwargs.py(), e, context()))?;
+            let value = value.extract().map_err(|e| arg_error(kwargs.py(), e, context()))?;
+            Ok((key, value))
+        })
+        .collect()
+}
+
+/// Converts `*args` or `**kwargs` to any `FromPyObject` type
+pub fn extract_variadic<'p, T>(fname: &str, name: &str, obj: &'p PyObjectRef) -> PyResult<T>
+where
+    T: FromPyObject<'p>,
+{
+    obj.extract()
+        .map_err(|e| arg_error(obj.py(), e, format!("{} argument '{}'", fname, name)))
+}
+
+/// Prefixes the message of the conversion error, the type of the exception is kept
+fn arg_error(py: Python, err: PyErr, context: String) -> PyErr {
+    let ptype = err.ptype.clone_ref(py);
+    let msg = match err.instance(py).as_ref(py).str() {
+        Ok(msg) => msg.to_string_lossy().into_owned(),
+        Err(_) => String::new(),
+    };
+    PyErr::from_type(ptype, format!("{}: {}", context, msg))
 }
 
 #[inline]
@@ -151,7 +151,7 @@ pub trait PyObjectRichcmpProtocol<'p>: PyObjectProtocol<'p> {
 #[doc(hidden)]
 pub trait PyObjectProtocolImpl {
     fn methods() -> Vec<PyMethodDef>;
-    fn tp_as_object(type_object: &mut ffi::PyTypeObject);
+    fn tp_as_object(type_object: &mut ffi::PyTypeObjectLayout);
     fn nb_bool_fn() -> Option<ffi::inquiry>;
 }
 
@@ -159,7 +159,7 @@ impl<T> PyObjectProtocolImpl for T {
     default fn methods() -> Vec<PyMethodDef> {
         Vec::new()
     }
-    default fn tp_as_object(_type_object: &mut ffi::PyTypeObject) {}
+    default fn tp_as_object(_type_object: &mut ffi::PyTypeObjectLayout) {}
     default fn nb_bool_fn() -> Option<ffi::inquiry> {
         None
     }
@@ -184,7 +184,7 @@ where
         }
         methods
     }
-    fn tp_as_object(type_object: &mut ffi::PyTypeObject) {
+    fn tp_as_object(type_object: &mut ffi::PyTypeObjectLayout) {
         type_object.tp_str = Self::tp_str();
         type_object.tp_repr = Self::tp_repr();
         type_object.tp_hash = Self::tp_hash();
@@ -489,7 +489,7 @@ where
     }
 }
 
-fn extract_op(op: c_int) -> PyResult<CompareOp> {
+pub(crate) fn extract_op(op: c_int) -> PyResult<CompareOp> {
     match op {
         ffi::Py_LT => Ok(CompareOp::Lt),
         ffi::Py_LE => Ok(CompareOp::Le),
@@ -0,0 +1,211 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Copy support
+//! Trait and support implementation for `#[class(clone)]`
+//!
+//! `__copy__` copies the struct field by field with `PyShallowCopy`, Python object
+//! fields are shared with `clone_ref()`. `__deepcopy__` copies the struct
+//! field by field with `PyDeepCopy`, Python object fields are copied with
+//! `copy.deepcopy()`. Other fields are cloned. The items of `Option`, `Vec`,
+//! `HashMap` and `BTreeMap` fields are copied one by one, map keys are cloned.
+//! The copy is always an instance of the class itself, the state of a native
+//! base type (i.e. `dict` items) is not copied.
+
+use callback::{cb_convert, PyObjectCallbackConverter};
+use class::methods::{PyMethodDef, PyMethodType};
+use conversion::ToPyObject;
+use err::{PyErr, PyResult};
+use ffi;
+use instance::Py;
+use object::PyObject;
+use objectprotocol::ObjectProtocol;
+use objects::{exc, PyObjectRef};
+use python::{IntoPyPointer, Python, ToPyPointer};
+use pythonrun::GILPool;
+use typeob::{PyObjectAlloc, PyTypeInfo, PyTypeObject};
+
+/// Copy interface, implemented by `#[class(clone)]`
+pub trait PyCloneProtocol: PyTypeInfo + PyShallowCopy + PyDeepCopy {}
+
+/// Shallow copy of a value, used for `__copy__`.
+///
+/// The default implementation clones the value, Python objects
+/// are copied with `clone_ref()`, `#[class(clone)]` implements it
+/// for the class field by field.
+pub trait PyShallowCopy: Sized {
+    fn shallow_copy(&self, py: Python) -> Self;
+}
+
+impl<T> PyShallowCopy for T
+where
+    T: Clone,
+{
+    default fn shallow_copy(&self, _py: Python) -> Self {
+        self.clone()
+    }
+}
+
+impl PyShallowCopy for PyObject {
+    fn shallow_copy(&self, py: Python) -> Self {
+        self.clone_ref(py)
+    }
+}
+
+impl<T> PyShallowCopy for Py<T> {
+    fn shallow_copy(&self, py: Python) -> Self {
+        self.clone_ref(py)
+    }
+}
+
+/// Deep copy of a value, used for `__deepcopy__`.
+///
+/// The default implementation clones the value,
+/// `#[class(clone)]` implements it for the class field by field.
+pub trait PyDeepCopy: Sized {
+    fn deep_copy(&self, py: Python, memo: &PyObjectRef) -> PyResult<Self>;
+}
+
+impl<T> PyDeepCopy for T
+where
+    T: Clone,
+{
+    default fn deep_copy(&self, _py: Python, _memo: &PyObjectRef) -> PyResult<Self> {
+        Ok(self.clone())
+    }
+}
+
+impl PyDeepCopy for PyObject {
+    fn deep_copy(&self, py: Python, memo: &PyObjectRef) -> PyResult<Self> {
+        let copy = py.import("copy")?;
+        Ok(copy.call1("deepcopy", (self, memo))?.to_object(py))
+    }
+}
+
+impl<T> PyDeepCopy for Py<T>
+where
+    T: PyTypeInfo,
+{
+    fn deep_copy(&self, py: Python, memo: &PyObjectRef) -> PyResult<Self> {
+        let obj = self.to_object(py).deep_copy(py, memo)?;
+        if T::is_instance(obj.as_ptr()) {
+            Ok(unsafe { Py::from_owned_ptr(obj.into_ptr()) })
+        } else {
+            Err(PyErr::new::<exc::TypeError, _>(format!(
+                "deep copy of {} has a different type",
+                T::NAME
+            )))
+        }
+    }
+}
+
+#[doc(hidden)]
+pub trait PyCloneProtocolImpl {
+    fn methods() -> Vec<PyMethodDef>;
+}
+
+impl<T> PyCloneProtocolImpl for T {
+    #[inline]
+    default fn methods() -> Vec<PyMethodDef> {
+        Vec::new()
+    }
+}
+
+impl<T> PyCloneProtocolImpl for T
+where
+    T: PyCloneProtocol,
+{
+    fn methods() -> Vec<PyMethodDef> {
+        vec![
+            PyMethodDef {
+                ml_name: "__copy__",
+                ml_meth: PyMethodType::PyCFunction(copy::<T>),
+                ml_flags: ffi::METH_NOARGS,
+                ml_doc: "Returns a shallow copy of the object.\0",
+            },
+            PyMethodDef {
+                ml_name: "__deepcopy__",
+                ml_meth: PyMethodType::PyCFunction(deepcopy::<T>),
+                ml_flags: ffi::METH_O,
+                ml_doc: "Returns a deep copy of the object.\0",
+            },
+        ]
+    }
+}
+
+unsafe extern "C" fn copy<T>(
+    slf: *mut ffi::PyObject,
+    _args: *mut ffi::PyObject,
+) -> *mut ffi::PyObject
+where
+    T: PyCloneProtocol,
+{
+    let _pool = GILPool::new();
+    let py = Python::assume_gil_acquired();
+    let slf = py.from_borrowed_ptr::<T>(slf);
+    let result = check_base::<T>().and_then(|_| new_instance(py, slf.shallow_copy(py)));
+    cb_convert(PyObjectCallbackConverter, py, result)
+}
+
+unsafe extern "C" fn deepcopy<T>(
+    slf: *mut ffi::PyObject,
+    memo: *mut ffi::PyObject,
+) -> *mut ffi::PyObject
+where
+    T: PyCloneProtocol,
+{
+    let _pool = GILPool::new();
+    let py = Python::assume_gil_acquired();
+    let memo = py.from_borrowed_ptr::<PyObjectRef>(memo);
+    let result = check_base::<T>().and_then(|_| deepcopy_memo::<T>(py, slf, memo));
+    cb_convert(PyObjectCallbackConverter, py, result)
+}
+
+/// The copy is registered in `memo[id(self)]` before the fields get copied,
+/// so fields referencing the object itself get the copy instead of recursing.
+/// The copy holds a shallow copy of the value until the deep copy is done.
+unsafe fn deepcopy_memo<T>(
+    py: Python,
+    slf: *mut ffi::PyObject,
+    memo: &PyObjectRef,
+) -> PyResult<PyObject>
+where
+    T: PyCloneProtocol,
+{
+    let value = py.from_borrowed_ptr::<T>(slf);
+    let copy = new_instance(py, value.shallow_copy(py))?;
+    memo.set_item(slf as usize, &copy)?;
+
+    let deep_value = value.deep_copy(py, memo)?;
+    *py.mut_from_borrowed_ptr::<T>(copy.as_ptr()) = deep_value;
+    Ok(copy)
+}
+
+/// Only the rust value of `T` gets copied,
+/// so the base type has to be a native type.
+fn check_base<T>() -> PyResult<()>
+where
+    T: PyTypeInfo,
+{
+    unsafe {
+        if <T::BaseType as PyTypeInfo>::native_base_type()
+            == <T::BaseType as PyTypeInfo>::type_object() as *mut _
+        {
+            Ok(())
+        } else {
+            Err(PyErr::new::<exc::TypeError, _>(format!(
+                "{}: copy of classes derived from #[class] types is not supported",
+                T::NAME
+            )))
+        }
+    }
+}
+
+/// Allocates new instance of `T` for the copied value.
+fn new_instance<T>(py: Python, value: T) -> PyResult<PyObject>
+where
+    T: PyTypeInfo + PyObjectAlloc<T>,
+{
+    let ob = <T as PyTypeObject>::create(py)?;
+    ob.init(|_| value)?;
+    Ok(unsafe { PyObject::from_owned_ptr(py, ob.into_ptr()) })
+}
@@ -0,0 +1,231 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Derived object protocol
+//! Slots generated by `#[class(repr, str, eq, ord, hash)]` from the Rust trait implementations
+//!
+//! Derived slots do not replace slots implemented with `PyObjectProtocol`.
+
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::os::raw::c_int;
+use std::ptr;
+
+use callback::{cb_convert, HashConverter};
+use class::basic::extract_op;
+use conversion::IntoPyObject;
+use ffi;
+use objects::PyString;
+use python::{IntoPyPointer, Python};
+use typeob::PyTypeInfo;
+use CompareOp;
+
+/// `__repr__` from `Debug`, implemented by `#[class(repr)]`
+pub trait PyDeriveRepr: PyTypeInfo + fmt::Debug {}
+
+/// `__str__` from `Display`, implemented by `#[class(str)]`
+pub trait PyDeriveStr: PyTypeInfo + fmt::Display {}
+
+/// `__eq__` and `__ne__` from `PartialEq`, implemented by `#[class(eq)]`
+pub trait PyDeriveEq: PyTypeInfo + PartialEq {}
+
+/// Rich comparison from `Ord`, implemented by `#[class(ord)]`
+pub trait PyDeriveOrd: PyTypeInfo + Ord {}
+
+/// `__hash__` from `Hash`, implemented by `#[class(hash)]`
+pub trait PyDeriveHash: PyTypeInfo + Hash {}
+
+/// Sets derived slots which are not set yet
+#[doc(hidden)]
+pub fn tp_as_object<T>(type_object: &mut ffi::PyTypeObjectLayout)
+where
+    T: PyTypeInfo,
+{
+    if type_object.tp_repr.is_none() {
+        type_object.tp_repr = <T as PyDeriveReprImpl>::tp_repr();
+    }
+    if type_object.tp_str.is_none() {
+        type_object.tp_str = <T as PyDeriveStrImpl>::tp_str();
+    }
+    if type_object.tp_hash.is_none() {
+        type_object.tp_hash = <T as PyDeriveHashImpl>::tp_hash();
+    }
+    if type_object.tp_richcompare.is_none()
+        && (<T as PyDeriveEqImpl>::has_eq() || <T as PyDeriveOrdImpl>::has_cmp())
+    {
+        type_object.tp_richcompare = Some(richcompare::<T>);
+    }
+}
+
+trait PyDeriveReprImpl {
+    fn tp_repr() -> Option<ffi::reprfunc>;
+}
+impl<T> PyDeriveReprImpl for T {
+    default fn tp_repr() -> Option<ffi::reprfunc> {
+        None
+    }
+}
+impl<T> PyDeriveReprImpl for T
+where
+    T: PyDeriveRepr,
+{
+    fn tp_repr() -> Option<ffi::reprfunc> {
+        unsafe extern "C" fn wrap<T>(slf: *mut ffi::PyObject) -> *mut ffi::PyObject
+        where
+            T: PyDeriveRepr,
+        {
+            let _pool = ::GILPool::new();
+            let py = Python::assume_gil_acquired();
+            let slf = py.from_borrowed_ptr::<T>(slf);
+            PyString::new(py, &format!("{:?}", slf)).into_ptr()
+        }
+        Some(wrap::<T>)
+    }
+}
+
+trait PyDeriveStrImpl {
+    fn tp_str() -> Option<ffi::reprfunc>;
+}
+impl<T> PyDeriveStrImpl for T {
+    default fn tp_str() -> Option<ffi::reprfunc> {
+        None
+    }
+}
+impl<T> PyDeriveStrImpl for T
+where
+    T: PyDeriveStr,
+{
+    fn tp_str() -> Option<ffi::reprfunc> {
+        unsafe extern "C" fn wrap<T>(slf: *mut ffi::PyObject) -> *mut ffi::PyObject
+        where
+            T: PyDeriveStr,
+        {
+            let _pool = ::GILPool::new();
+            let py = Python::assume_gil_acquired();
+            let slf = py.from_borrowed_ptr::<T>(slf);
+            PyString::new(py, &format!("{}", slf)).into_ptr()
+        }
+        Some(wrap::<T>)
+    }
+}
+
+trait PyDeriveHashImpl {
+    fn tp_hash() -> Option<ffi::hashfunc>;
+}
+impl<T> PyDeriveHashImpl for T {
+    default fn tp_hash() -> Option<ffi::hashfunc> {
+        None
+    }
+}
+impl<T> PyDeriveHashImpl for T
+where
+    T: PyDeriveHash,
+{
+    fn tp_hash() -> Option<ffi::hashfunc> {
+        unsafe extern "C" fn wrap<T>(slf: *mut ffi::PyObject) -> ffi::Py_hash_t
+        where
+            T: PyDeriveHash,
+        {
+            let _pool = ::GILPool::new();
+            let py = Python::assume_gil_acquired();
+            let slf = py.from_borrowed_ptr::<T>(slf);
+            let mut hasher = DefaultHasher::new();
+            slf.hash(&mut hasher);
+            cb_convert(HashConverter, py, Ok(hasher.finish()))
+        }
+        Some(wrap::<T>)
+    }
+}
+
+trait PyDeriveEqImpl {
+    fn has_eq() -> bool;
+    fn derived_eq(&self, other: &Self) -> Option<bool>;
+}
+impl<T> PyDeriveEqImpl for T {
+    default fn has_eq() -> bool {
+        false
+    }
+    default fn derived_eq(&self, _other: &Self) -> Option<bool> {
+        None
+    }
+}
+impl<T> PyDeriveEqImpl for T
+where
+    T: PyDeriveEq,
+{
+    fn has_eq() -> bool {
+        true
+    }
+    fn derived_eq(&self, other: &Self) -> Option<bool> {
+        Some(self == other)
+    }
+}
+
+trait PyDeriveOrdImpl {
+    fn has_cmp() -> bool;
+    fn derived_cmp(&self, other: &Self) -> Option<Ordering>;
+}
+impl<T> PyDeriveOrdImpl for T {
+    default fn has_cmp() -> bool {
+        false
+    }
+    default fn derived_cmp(&self, _other: &Self) -> Option<Ordering> {
+        None
+    }
+}
+impl<T> PyDeriveOrdImpl for T
+where
+    T: PyDeriveOrd,
+{
+    fn has_cmp() -> bool {
+        true
+    }
+    fn derived_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Compares instances of `T`, returns `NotImplemented` for other types
+/// and for the operators which are not derived.
+unsafe extern "C" fn richcompare<T>(
+    slf: *mut ffi::PyObject,
+    other: *mut ffi::PyObject,
+    op: c_int,
+) -> *mut ffi::PyObject
+where
+    T: PyTypeInfo,
+{
+    let _pool = ::GILPool::new();
+    let py = Python::assume_gil_acquired();
+
+    let op = match extract_op(op) {
+        Ok(op) => op,
+        Err(e) => {
+            e.restore(py);
+            return ptr::null_mut();
+        }
+    };
+    if !T::is_instance(other) {
+        return py.NotImplemented().into_ptr();
+    }
+    let slf = py.from_borrowed_ptr::<T>(slf);
+    let other = py.from_borrowed_ptr::<T>(other);
+
+    let eq = || {
+        slf.derived_eq(other)
+            .or_else(|| slf.derived_cmp(other).map(|ord| ord == Ordering::Equal))
+    };
+    let result = match op {
+        CompareOp::Eq => eq(),
+        CompareOp::Ne => eq().map(|eq| !eq),
+        CompareOp::Lt => slf.derived_cmp(other).map(|ord| ord == Ordering::Less),
+        CompareOp::Le => slf.derived_cmp(other).map(|ord| ord != Ordering::Greater),
+        CompareOp::Gt => slf.derived_cmp(other).map(|ord| ord == Ordering::Greater),
+        CompareOp::Ge => slf.derived_cmp(other).map(|ord| ord != Ordering::Less),
+    };
+    match result {
+        Some(result) => result.into_object(py).into_ptr(),
+        None => py.NotImplemented().into_ptr(),
+    }
+}
@@ -147,14 +147,14 @@ where
 #[doc(hidden)]
 pub trait PyDescrProtocolImpl {
     fn methods() -> Vec<PyMethodDef>;
-    fn tp_as_descr(type_object: &mut ffi::PyTypeObject);
+    fn tp_as_descr(type_object: &mut ffi::PyTypeObjectLayout);
 }
 
 impl<T> PyDescrProtocolImpl for T {
     default fn methods() -> Vec<PyMethodDef> {
         Vec::new()
     }
-    default fn tp_as_descr(_type_object: &mut ffi::PyTypeObject) {}
+    default fn tp_as_descr(_type_object: &mut ffi::PyTypeObjectLayout) {}
 }
 
 impl<'p, T> PyDescrProtocolImpl for T
@@ -164,7 +164,7 @@ where
     fn methods() -> Vec<PyMethodDef> {
         Vec::new()
     }
-    fn tp_as_descr(type_object: &mut ffi::PyTypeObject) {
+    fn tp_as_descr(type_object: &mut ffi::PyTypeObjectLayout) {
         type_object.tp_descr_get = Self::tp_descr_get();
         type_object.tp_descr_set = Self::tp_descr_set();
     }
@@ -9,7 +9,7 @@ use ffi;
 use python::{Python, ToPyPointer};
 use typeob::PyTypeInfo;
 
-pub struct PyTraverseError(c_int);
+pub struct PyTraverseError(pub(crate) c_int);
 
 /// GC support
 #[allow(unused_variables)]
@@ -28,18 +28,18 @@ pub trait PyGCClearProtocol<'p>: PyGCProtocol<'p> {}
 
 #[doc(hidden)]
 pub trait PyGCProtocolImpl {
-    fn update_type_object(type_object: &mut ffi::PyTypeObject);
+    fn update_type_object(type_object: &mut ffi::PyTypeObjectLayout);
 }
 
 impl<'p, T> PyGCProtocolImpl for T {
-    default fn update_type_object(_type_object: &mut ffi::PyTypeObject) {}
+    default fn update_type_object(_type_object: &mut ffi::PyTypeObjectLayout) {}
 }
 
 impl<'p, T> PyGCProtocolImpl for T
 where
     T: PyGCProtocol<'p>,
 {
-    fn update_type_object(type_object: &mut ffi::PyTypeObject) {
+    fn update_type_object(type_object: &mut ffi::PyTypeObjectLayout) {
         type_object.tp_traverse = Self::tp_traverse();
         type_object.tp_clear = Self::tp_clear();
     }
@@ -47,12 +47,12 @@ where
 
 #[derive(Copy, Clone)]
 pub struct PyVisit<'p> {
-    visit: ffi::visitproc,
-    arg: *mut c_void,
+    pub(crate) visit: ffi::visitproc,
+    pub(crate) arg: *mut c_void,
     /// VisitProc contains a Python instance to ensure that
     /// 1) it is cannot be moved out of the traverse() call
     /// 2) it cannot be sent to other threads
-    _py: Python<'p>,
+    pub(crate) _py: Python<'p>,
 }
 
 impl<'p> PyVisit<'p> {
@@ -44,12 +44,12 @@ pub trait PyIterNextProtocol<'p>: PyIterProtocol<'p> {
 
 #[doc(hidden)]
 pub trait PyIterProtocolImpl {
-    fn tp_as_iter(typeob: &mut ffi::PyTypeObject);
+    fn tp_as_iter(typeob: &mut ffi::PyTypeObjectLayout);
 }
 
 impl<T> PyIterProtocolImpl for T {
     #[inline]
-    default fn tp_as_iter(_: &mut ffi::PyTypeObject) {}
+    default fn tp_as_iter(_: &mut ffi::PyTypeObjectLayout) {}
 }
 
 impl<'p, T> PyIterProtocolImpl for T
@@ -57,7 +57,7 @@ where
     T: PyIterProtocol<'p>,
 {
     #[inline]
-    fn tp_as_iter(typeob: &mut ffi::PyTypeObject) {
+    fn tp_as_iter(typeob: &mut ffi::PyTypeObjectLayout) {
         typeob.tp_iter = Self::tp_iter();
         typeob.tp_iternext = Self::tp_iternext();
     }
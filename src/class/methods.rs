@@ -2,8 +2,12 @@
 
 use std;
 use std::ffi::CString;
+use std::fmt;
 
+use err::PyResult;
 use ffi;
+use object::PyObject;
+use python::Python;
 
 static NO_PY_METHODS: &'static [PyMethodDefType] = &[];
 
@@ -27,12 +31,28 @@ pub enum PyMethodDefType {
     Getter(PyGetterDef),
     /// Represents setter descriptor, used by `#[setter]`
     Setter(PySetterDef),
+    /// Represents deleter descriptor, used by `#[deleter]`
+    Deleter(PyDeleterDef),
+    /// Represents class attribute, used by `#[classattr]`
+    ClassAttribute(PyClassAttributeDef),
 }
 
+/// `METH_FASTCALL | METH_KEYWORDS` function, the positional arguments are followed
+/// by the values of the keyword arguments, `kwnames` is the tuple of the keywords.
+pub type PyCFunctionFastWithKeywords = unsafe extern "C" fn(
+    slf: *mut ffi::PyObject,
+    args: *const *mut ffi::PyObject,
+    nargs: ffi::Py_ssize_t,
+    kwnames: *mut ffi::PyObject,
+) -> *mut ffi::PyObject;
+
 #[derive(Copy, Clone, Debug)]
 pub enum PyMethodType {
     PyCFunction(ffi::PyCFunction),
     PyCFunctionWithKeywords(ffi::PyCFunctionWithKeywords),
+    /// Fast call wrapper and the `METH_VARARGS` wrapper of the same function,
+    /// the second one is used if the interpreter does not support `METH_FASTCALL`
+    PyCFunctionFastWithKeywords(PyCFunctionFastWithKeywords, ffi::PyCFunctionWithKeywords),
     PyNoArgsFunction(ffi::PyNoArgsFunction),
     PyNewFunc(ffi::newfunc),
     PyInitFunc(ffi::initproc),
@@ -60,21 +80,53 @@ pub struct PySetterDef {
     pub doc: &'static str,
 }
 
+/// Deleter is called through the `set` slot of the descriptor with NULL value.
+#[derive(Copy, Clone, Debug)]
+pub struct PyDeleterDef {
+    pub name: &'static str,
+    pub meth: ffi::setter,
+    pub doc: &'static str,
+}
+
+/// Creates the value of a class attribute.
+pub type PyClassAttributeFactory = fn(Python) -> PyResult<PyObject>;
+
+#[derive(Copy, Clone)]
+pub struct PyClassAttributeDef {
+    pub name: &'static str,
+    pub meth: PyClassAttributeFactory,
+}
+
+impl fmt::Debug for PyClassAttributeDef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PyClassAttributeDef")
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
 unsafe impl Sync for PyMethodDef {}
 unsafe impl Sync for ffi::PyMethodDef {}
 
 unsafe impl Sync for PyGetterDef {}
 unsafe impl Sync for PySetterDef {}
+unsafe impl Sync for PyDeleterDef {}
 unsafe impl Sync for ffi::PyGetSetDef {}
 
 impl PyMethodDef {
     /// Convert `PyMethodDef` to Python method definition struct `ffi::PyMethodDef`
     pub fn as_method_def(&self) -> ffi::PyMethodDef {
+        let mut flags = self.ml_flags;
         let meth = match self.ml_meth {
             PyMethodType::PyCFunction(meth) => meth,
             PyMethodType::PyCFunctionWithKeywords(meth) => unsafe {
                 std::mem::transmute::<ffi::PyCFunctionWithKeywords, ffi::PyCFunction>(meth)
             },
+            PyMethodType::PyCFunctionFastWithKeywords(fast, meth) => {
+                let (meth, fast_flags) = fastcall(fast, meth, flags);
+                flags = fast_flags;
+                meth
+            }
             PyMethodType::PyNoArgsFunction(meth) => unsafe {
                 std::mem::transmute::<ffi::PyNoArgsFunction, ffi::PyCFunction>(meth)
             },
@@ -91,12 +143,57 @@ impl PyMethodDef {
                 .expect("Method name must not contain NULL byte")
                 .into_raw(),
             ml_meth: Some(meth),
-            ml_flags: self.ml_flags,
-            ml_doc: self.ml_doc.as_ptr() as *const _,
+            ml_flags: flags,
+            ml_doc: method_doc(self.ml_doc).as_ptr() as *const _,
+        }
+    }
+}
+
+/// Python 3.7 calls `METH_FASTCALL | METH_KEYWORDS` functions without
+/// creating the argument tuple and the keyword dict.
+#[cfg(all(Py_3_7, not(Py_LIMITED_API)))]
+fn fastcall(
+    fast: PyCFunctionFastWithKeywords,
+    _meth: ffi::PyCFunctionWithKeywords,
+    flags: ::c_int,
+) -> (ffi::PyCFunction, ::c_int) {
+    let fast = unsafe { std::mem::transmute::<PyCFunctionFastWithKeywords, ffi::PyCFunction>(fast) };
+    (fast, (flags & !ffi::METH_VARARGS) | ffi::METH_FASTCALL)
+}
+
+/// Older versions and the limited api use the `METH_VARARGS` wrapper.
+#[cfg(not(all(Py_3_7, not(Py_LIMITED_API))))]
+fn fastcall(
+    _fast: PyCFunctionFastWithKeywords,
+    meth: ffi::PyCFunctionWithKeywords,
+    flags: ::c_int,
+) -> (ffi::PyCFunction, ::c_int) {
+    let meth = unsafe { std::mem::transmute::<ffi::PyCFunctionWithKeywords, ffi::PyCFunction>(meth) };
+    (meth, flags)
+}
+
+/// Splits `name(args)\n--\n\n` signature line from the doc string.
+pub(crate) fn split_text_signature(doc: &str) -> (Option<&str>, &str) {
+    match doc.find("\n--\n\n") {
+        Some(idx) if doc[..idx].ends_with(')') && !doc[..idx].contains('\n') => {
+            (Some(&doc[..idx]), &doc[idx + 5..])
         }
+        _ => (None, doc),
     }
 }
 
+/// Python 3 reads `__text_signature__` from the doc string.
+#[cfg(Py_3)]
+fn method_doc(doc: &'static str) -> &'static str {
+    doc
+}
+
+/// Python 2 does not support `__text_signature__`.
+#[cfg(not(Py_3))]
+fn method_doc(doc: &'static str) -> &'static str {
+    split_text_signature(doc).1
+}
+
 impl PyGetterDef {
     /// Copy descriptor information to `ffi::PyGetSetDef`
     pub fn copy_to(&self, dst: &mut ffi::PyGetSetDef) {
@@ -105,6 +202,7 @@ impl PyGetterDef {
                 .expect("Method name must not contain NULL byte")
                 .into_raw();
         }
+        copy_doc(self.doc, dst);
         dst.get = Some(self.meth);
     }
 }
@@ -117,10 +215,61 @@ impl PySetterDef {
                 .expect("Method name must not contain NULL byte")
                 .into_raw();
         }
+        copy_doc(self.doc, dst);
         dst.set = Some(self.meth);
     }
 }
 
+impl PyDeleterDef {
+    /// Copy descriptor information to `ffi::PyGetSetDef`.
+    /// `setter` is the setter of the same property, if any.
+    pub fn copy_to(&self, dst: &mut ffi::PyGetSetDef, setter: Option<ffi::setter>) {
+        if dst.name.is_null() {
+            dst.name = CString::new(self.name)
+                .expect("Method name must not contain NULL byte")
+                .into_raw();
+        }
+        copy_doc(self.doc, dst);
+        match setter {
+            Some(setter) => {
+                // both share the `set` slot, dispatch on NULL value
+                dst.set = Some(set_or_delete);
+                dst.closure = Box::into_raw(Box::new(SetOrDelete {
+                    set: setter,
+                    del: self.meth,
+                })) as *mut _;
+            }
+            None => dst.set = Some(self.meth),
+        }
+    }
+}
+
+/// Uses the doc string of the first descriptor method that has one.
+fn copy_doc(doc: &'static str, dst: &mut ffi::PyGetSetDef) {
+    // doc strings generated by `#[methods]` are NUL terminated
+    if dst.doc.is_null() && doc.len() > 1 && doc.ends_with('\0') {
+        dst.doc = doc.as_ptr() as *mut _;
+    }
+}
+
+struct SetOrDelete {
+    set: ffi::setter,
+    del: ffi::setter,
+}
+
+unsafe extern "C" fn set_or_delete(
+    slf: *mut ffi::PyObject,
+    value: *mut ffi::PyObject,
+    closure: *mut ::c_void,
+) -> ::c_int {
+    let funcs = &*(closure as *const SetOrDelete);
+    if value.is_null() {
+        (funcs.del)(slf, value, ::std::ptr::null_mut())
+    } else {
+        (funcs.set)(slf, value, ::std::ptr::null_mut())
+    }
+}
+
 #[doc(hidden)]
 pub trait PyMethodsProtocolImpl {
     fn py_methods() -> &'static [PyMethodDefType];
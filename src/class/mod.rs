@@ -9,26 +9,32 @@ pub mod async;
 pub mod basic;
 pub mod buffer;
 pub mod context;
+pub mod copy;
+pub mod derived;
 pub mod descr;
 pub mod gc;
 pub mod iter;
 pub mod mapping;
 pub mod methods;
 pub mod number;
+pub mod pickle;
 pub mod sequence;
 
 pub use self::async::PyAsyncProtocol;
 pub use self::basic::PyObjectProtocol;
 pub use self::buffer::PyBufferProtocol;
 pub use self::context::PyContextProtocol;
+pub use self::copy::{PyCloneProtocol, PyDeepCopy, PyShallowCopy};
 pub use self::descr::PyDescrProtocol;
 pub use self::iter::PyIterProtocol;
 pub use self::mapping::PyMappingProtocol;
 pub use self::number::PyNumberProtocol;
+pub use self::pickle::PyPickleProtocol;
 pub use self::sequence::PySequenceProtocol;
 
 pub use self::gc::{PyGCProtocol, PyTraverseError, PyVisit};
-pub use self::methods::{PyGetterDef, PyMethodDef, PyMethodDefType, PyMethodType, PySetterDef};
+pub use self::methods::{PyClassAttributeDef, PyDeleterDef, PyGetterDef, PyMethodDef,
+                        PyMethodDefType, PyMethodType, PySetterDef};
 
 use ffi;
 
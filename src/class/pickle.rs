@@ -0,0 +1,185 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Pickle support
+//! Trait and support implementation for `#[class(pickle)]`
+//!
+//! `__reduce__` returns `(type(self), self.__getnewargs__(), self.__getstate__())`,
+//! both methods are optional. Unpickling calls the class with these arguments
+//! and passes the state to `__setstate__`.
+//!
+//! With the `serde-pickle` feature `__getstate__` and `__setstate__` are
+//! generated for classes implementing `Serialize` and `Deserialize`,
+//! methods defined in `#[methods]` take precedence. The state is a JSON string,
+//! so it has the limits of `serde_json`: map keys must serialize as strings
+//! or integers, non-finite floats become `null` and fail to deserialize,
+//! byte buffers are stored as lists of integers.
+
+use callback::{cb_convert, PyObjectCallbackConverter};
+use class::methods::{PyMethodDef, PyMethodType};
+use conversion::ToPyObject;
+use err::PyResult;
+use ffi;
+use object::PyObject;
+use objectprotocol::ObjectProtocol;
+use objects::{PyObjectRef, PyTuple, PyType};
+use python::{Python, ToPyPointer};
+use pythonrun::GILPool;
+use typeob::PyTypeInfo;
+
+/// Pickle interface, implemented by `#[class(pickle)]`
+pub trait PyPickleProtocol: PyTypeInfo {}
+
+#[doc(hidden)]
+pub trait PyPickleProtocolImpl {
+    fn methods() -> Vec<PyMethodDef>;
+}
+
+impl<T> PyPickleProtocolImpl for T {
+    #[inline]
+    default fn methods() -> Vec<PyMethodDef> {
+        Vec::new()
+    }
+}
+
+impl<T> PyPickleProtocolImpl for T
+where
+    T: PyPickleProtocol,
+{
+    fn methods() -> Vec<PyMethodDef> {
+        let mut methods = vec![
+            PyMethodDef {
+                ml_name: "__reduce__",
+                ml_meth: PyMethodType::PyCFunction(reduce),
+                ml_flags: ffi::METH_NOARGS,
+                ml_doc: "Helper for pickle.\0",
+            },
+        ];
+        methods.extend(<T as PyPickleStateImpl>::methods());
+        methods
+    }
+}
+
+#[doc(hidden)]
+pub trait PyPickleStateImpl {
+    fn methods() -> Vec<PyMethodDef>;
+}
+
+impl<T> PyPickleStateImpl for T {
+    #[inline]
+    default fn methods() -> Vec<PyMethodDef> {
+        Vec::new()
+    }
+}
+
+unsafe extern "C" fn reduce(
+    slf: *mut ffi::PyObject,
+    _args: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    let _pool = GILPool::new();
+    let py = Python::assume_gil_acquired();
+    let slf = py.from_borrowed_ptr::<PyObjectRef>(slf);
+    cb_convert(PyObjectCallbackConverter, py, reduce_object(py, slf))
+}
+
+fn reduce_object(py: Python, slf: &PyObjectRef) -> PyResult<PyObject> {
+    let cls: &PyObjectRef = slf.get_type().as_ref();
+    let args = if slf.hasattr("__getnewargs__")? {
+        slf.call_method0("__getnewargs__")?.to_object(py)
+    } else {
+        PyTuple::empty(py).into()
+    };
+
+    if has_getstate(py, slf.get_type())? {
+        let state = slf.call_method0("__getstate__")?;
+        Ok(PyTuple::new(py, &[cls.to_object(py), args, state.to_object(py)]).into())
+    } else {
+        Ok(PyTuple::new(py, &[cls.to_object(py), args]).into())
+    }
+}
+
+/// `object.__getstate__` exists since python 3.11, the state is only
+/// pickled if the class overrides it
+fn has_getstate(py: Python, cls: &PyType) -> PyResult<bool> {
+    if !cls.hasattr("__getstate__")? {
+        return Ok(false);
+    }
+    let object = py.get_type::<PyObjectRef>();
+    if !object.hasattr("__getstate__")? {
+        return Ok(true);
+    }
+    Ok(cls.getattr("__getstate__")?.as_ptr() != object.getattr("__getstate__")?.as_ptr())
+}
+
+#[cfg(feature = "serde-pickle")]
+mod serde_state {
+    use serde::Serialize;
+    use serde::de::DeserializeOwned;
+    use serde_json;
+
+    use super::{PyPickleProtocol, PyPickleStateImpl};
+    use callback::{cb_convert, PyObjectCallbackConverter};
+    use class::methods::{PyMethodDef, PyMethodType};
+    use err::{PyErr, PyResult};
+    use ffi;
+    use objectprotocol::ObjectProtocol;
+    use objects::{exc, PyObjectRef, PyString};
+    use python::Python;
+    use pythonrun::GILPool;
+
+    impl<T> PyPickleStateImpl for T
+    where
+        T: PyPickleProtocol + Serialize + DeserializeOwned,
+    {
+        fn methods() -> Vec<PyMethodDef> {
+            vec![
+                PyMethodDef {
+                    ml_name: "__getstate__",
+                    ml_meth: PyMethodType::PyCFunction(getstate::<T>),
+                    ml_flags: ffi::METH_NOARGS,
+                    ml_doc: "Returns the serialized state of the object.\0",
+                },
+                PyMethodDef {
+                    ml_name: "__setstate__",
+                    ml_meth: PyMethodType::PyCFunction(setstate::<T>),
+                    ml_flags: ffi::METH_O,
+                    ml_doc: "Restores the object from the serialized state.\0",
+                },
+            ]
+        }
+    }
+
+    unsafe extern "C" fn getstate<T>(
+        slf: *mut ffi::PyObject,
+        _args: *mut ffi::PyObject,
+    ) -> *mut ffi::PyObject
+    where
+        T: PyPickleProtocol + Serialize,
+    {
+        let _pool = GILPool::new();
+        let py = Python::assume_gil_acquired();
+        let slf = py.mut_from_borrowed_ptr::<T>(slf);
+        let result = serde_json::to_string(slf)
+            .map(|state| PyString::new(py, &state))
+            .map_err(|e| PyErr::new::<exc::ValueError, _>(e.to_string()));
+        cb_convert(PyObjectCallbackConverter, py, result)
+    }
+
+    unsafe extern "C" fn setstate<T>(
+        slf: *mut ffi::PyObject,
+        state: *mut ffi::PyObject,
+    ) -> *mut ffi::PyObject
+    where
+        T: PyPickleProtocol + DeserializeOwned,
+    {
+        let _pool = GILPool::new();
+        let py = Python::assume_gil_acquired();
+        let slf = py.mut_from_borrowed_ptr::<T>(slf);
+        let state = py.from_borrowed_ptr::<PyObjectRef>(state);
+        let result: PyResult<()> = state.extract::<&str>().and_then(|state| {
+            *slf = serde_json::from_str(state)
+                .map_err(|e| PyErr::new::<exc::ValueError, _>(e.to_string()))?;
+            Ok(())
+        });
+        cb_convert(PyObjectCallbackConverter, py, result)
+    }
+}
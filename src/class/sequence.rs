@@ -2,10 +2,14 @@
 
 //! Python Sequence Interface
 //! Trait and support implementation for implementing sequence
+//!
+//! `__getitem__`, `__setitem__` and `__delitem__` accept any key type that can be
+//! created from an `isize`. Use `SliceOrIndex` as key type to support extended
+//! slicing, i.e. `obj[1:10:2]`.
 
 use std::os::raw::c_int;
 
-use callback::{BoolCallbackConverter, LenResultConverter, PyObjectCallbackConverter};
+use callback::{cb_convert, BoolCallbackConverter, LenResultConverter, PyObjectCallbackConverter};
 use conversion::{FromPyObject, IntoPyObject};
 use err::{PyErr, PyResult};
 use ffi;
@@ -24,21 +28,21 @@ pub trait PySequenceProtocol<'p>: PyTypeInfo + Sized {
         unimplemented!()
     }
 
-    fn __getitem__(&'p self, key: isize) -> Self::Result
+    fn __getitem__(&'p self, key: Self::Index) -> Self::Result
     where
         Self: PySequenceGetItemProtocol<'p>,
     {
         unimplemented!()
     }
 
-    fn __setitem__(&'p mut self, key: isize, value: Self::Value) -> Self::Result
+    fn __setitem__(&'p mut self, key: Self::Index, value: Self::Value) -> Self::Result
     where
         Self: PySequenceSetItemProtocol<'p>,
     {
         unimplemented!()
     }
 
-    fn __delitem__(&'p mut self, key: isize) -> Self::Result
+    fn __delitem__(&'p mut self, key: Self::Index) -> Self::Result
     where
         Self: PySequenceDelItemProtocol<'p>,
     {
@@ -89,16 +93,19 @@ pub trait PySequenceLenProtocol<'p>: PySequenceProtocol<'p> {
 }
 
 pub trait PySequenceGetItemProtocol<'p>: PySequenceProtocol<'p> {
+    type Index: FromPyObject<'p> + From<isize>;
     type Success: IntoPyObject;
     type Result: Into<PyResult<Self::Success>>;
 }
 
 pub trait PySequenceSetItemProtocol<'p>: PySequenceProtocol<'p> {
+    type Index: FromPyObject<'p> + From<isize>;
     type Value: FromPyObject<'p>;
     type Result: Into<PyResult<()>>;
 }
 
 pub trait PySequenceDelItemProtocol<'p>: PySequenceProtocol<'p> {
+    type Index: FromPyObject<'p> + From<isize>;
     type Result: Into<PyResult<()>>;
 }
 
@@ -130,6 +137,7 @@ pub trait PySequenceInplaceRepeatProtocol<'p>: PySequenceProtocol<'p> + IntoPyOb
 #[doc(hidden)]
 pub trait PySequenceProtocolImpl {
     fn tp_as_sequence() -> Option<ffi::PySequenceMethods>;
+    fn tp_as_mapping() -> Option<ffi::PyMappingMethods>;
 }
 
 impl<T> PySequenceProtocolImpl for T {
@@ -137,6 +145,10 @@ impl<T> PySequenceProtocolImpl for T {
     default fn tp_as_sequence() -> Option<ffi::PySequenceMethods> {
         None
     }
+    #[inline]
+    default fn tp_as_mapping() -> Option<ffi::PyMappingMethods> {
+        None
+    }
 }
 
 impl<'p, T> PySequenceProtocolImpl for T
@@ -187,6 +199,27 @@ where
             sq_inplace_repeat: Self::sq_inplace_repeat(),
         })
     }
+
+    /// Subscription slots, used for slice keys.
+    /// Integer keys are forwarded to the sequence slots.
+    #[inline]
+    fn tp_as_mapping() -> Option<ffi::PyMappingMethods> {
+        let f = if let Some(df) = Self::mp_del_subscript() {
+            Some(df)
+        } else {
+            Self::mp_ass_subscript()
+        };
+        let subscript = Self::mp_subscript();
+
+        if subscript.is_none() && f.is_none() {
+            return None;
+        }
+        Some(ffi::PyMappingMethods {
+            mp_length: None,
+            mp_subscript: subscript,
+            mp_ass_subscript: f,
+        })
+    }
 }
 
 trait PySequenceLenProtocolImpl {
@@ -276,15 +309,15 @@ where
 
             if value.is_null() {
                 let e = PyErr::new::<exc::NotImplementedError, _>(format!(
-                    "Item deletion not supported by {:?}",
-                    stringify!(T)
+                    "Item deletion not supported by {}",
+                    T::NAME
                 ));
                 e.restore(py);
                 -1
             } else {
                 let value = py.from_borrowed_ptr::<PyObjectRef>(value);
                 let result = match value.extract() {
-                    Ok(value) => slf.__setitem__(key as isize, value).into(),
+                    Ok(value) => slf.__setitem__((key as isize).into(), value).into(),
                     Err(e) => Err(e),
                 };
                 match result {
@@ -332,7 +365,7 @@ where
             let slf = py.mut_from_borrowed_ptr::<T>(slf);
 
             if value.is_null() {
-                let result = slf.__delitem__(key as isize).into();
+                let result = slf.__delitem__((key as isize).into()).into();
                 match result {
                     Ok(_) => 0,
                     Err(e) => {
@@ -342,8 +375,8 @@ where
                 }
             } else {
                 let e = PyErr::new::<exc::NotImplementedError, _>(format!(
-                    "Item assignment not supported by {:?}",
-                    stringify!(T)
+                    "Item assignment not supported by {}",
+                    T::NAME
                 ));
                 e.restore(py);
                 -1
@@ -372,7 +405,7 @@ where
             let slf = py.mut_from_borrowed_ptr::<T>(slf);
 
             if value.is_null() {
-                let result = slf.__delitem__(key as isize).into();
+                let result = slf.__delitem__((key as isize).into()).into();
                 match result {
                     Ok(_) => 0,
                     Err(e) => {
@@ -383,7 +416,7 @@ where
             } else {
                 let value = py.from_borrowed_ptr::<PyObjectRef>(value);
                 let result = match value.extract() {
-                    Ok(value) => slf.__setitem__(key as isize, value).into(),
+                    Ok(value) => slf.__setitem__((key as isize).into(), value).into(),
                     Err(e) => Err(e),
                 };
                 match result {
@@ -399,6 +432,275 @@ where
     }
 }
 
+#[cfg(not(Py_LIMITED_API))]
+unsafe fn is_index(key: *mut ffi::PyObject) -> bool {
+    ffi::PyIndex_Check(key) != 0
+}
+
+/// `PyIndex_Check` is not a part of the limited API
+#[cfg(Py_LIMITED_API)]
+unsafe fn is_index(key: *mut ffi::PyObject) -> bool {
+    let ty = ffi::Py_TYPE(key) as *mut ffi::PyObject;
+    ffi::PyObject_HasAttrString(ty, "__index__\0".as_ptr() as *const _) != 0
+}
+
+/// Forwards integer subscription keys to the `sq_item` slot,
+/// which normalizes negative indices.
+unsafe fn get_item_by_index(
+    slf: *mut ffi::PyObject,
+    key: *mut ffi::PyObject,
+) -> Option<*mut ffi::PyObject> {
+    if !is_index(key) {
+        return None;
+    }
+    let idx = ffi::PyNumber_AsSsize_t(key, ffi::PyExc_IndexError);
+    if idx == -1 && !ffi::PyErr_Occurred().is_null() {
+        return Some(::std::ptr::null_mut());
+    }
+    Some(ffi::PySequence_GetItem(slf, idx))
+}
+
+/// Forwards integer subscription keys to the `sq_ass_item` slot,
+/// which normalizes negative indices.
+unsafe fn ass_item_by_index(
+    slf: *mut ffi::PyObject,
+    key: *mut ffi::PyObject,
+    value: *mut ffi::PyObject,
+) -> Option<c_int> {
+    if !is_index(key) {
+        return None;
+    }
+    let idx = ffi::PyNumber_AsSsize_t(key, ffi::PyExc_IndexError);
+    if idx == -1 && !ffi::PyErr_Occurred().is_null() {
+        return Some(-1);
+    }
+    if value.is_null() {
+        Some(ffi::PySequence_DelItem(slf, idx))
+    } else {
+        Some(ffi::PySequence_SetItem(slf, idx, value))
+    }
+}
+
+trait PySequenceSubscriptProtocolImpl {
+    fn mp_subscript() -> Option<ffi::binaryfunc>;
+}
+
+impl<'p, T> PySequenceSubscriptProtocolImpl for T
+where
+    T: PySequenceProtocol<'p>,
+{
+    #[inline]
+    default fn mp_subscript() -> Option<ffi::binaryfunc> {
+        None
+    }
+}
+
+impl<T> PySequenceSubscriptProtocolImpl for T
+where
+    T: for<'p> PySequenceGetItemProtocol<'p>,
+{
+    #[inline]
+    fn mp_subscript() -> Option<ffi::binaryfunc> {
+        unsafe extern "C" fn wrap<T>(
+            slf: *mut ffi::PyObject,
+            key: *mut ffi::PyObject,
+        ) -> *mut ffi::PyObject
+        where
+            T: for<'p> PySequenceGetItemProtocol<'p>,
+        {
+            if let Some(result) = get_item_by_index(slf, key) {
+                return result;
+            }
+
+            let _pool = ::GILPool::new();
+            let py = Python::assume_gil_acquired();
+            let slf = py.mut_from_borrowed_ptr::<T>(slf);
+            let key = py.from_borrowed_ptr::<PyObjectRef>(key);
+
+            let result = match key.extract() {
+                Ok(key) => slf.__getitem__(key).into(),
+                Err(e) => Err(e),
+            };
+            cb_convert(PyObjectCallbackConverter, py, result)
+        }
+        Some(wrap::<T>)
+    }
+}
+
+trait PySequenceAssSubscriptProtocolImpl {
+    fn mp_ass_subscript() -> Option<ffi::objobjargproc>;
+}
+
+impl<'p, T> PySequenceAssSubscriptProtocolImpl for T
+where
+    T: PySequenceProtocol<'p>,
+{
+    #[inline]
+    default fn mp_ass_subscript() -> Option<ffi::objobjargproc> {
+        None
+    }
+}
+
+impl<T> PySequenceAssSubscriptProtocolImpl for T
+where
+    T: for<'p> PySequenceSetItemProtocol<'p>,
+{
+    #[inline]
+    fn mp_ass_subscript() -> Option<ffi::objobjargproc> {
+        unsafe extern "C" fn wrap<T>(
+            slf: *mut ffi::PyObject,
+            key: *mut ffi::PyObject,
+            value: *mut ffi::PyObject,
+        ) -> c_int
+        where
+            T: for<'p> PySequenceSetItemProtocol<'p>,
+        {
+            if let Some(result) = ass_item_by_index(slf, key, value) {
+                return result;
+            }
+
+            let _pool = ::GILPool::new();
+            let py = Python::assume_gil_acquired();
+            let slf = py.mut_from_borrowed_ptr::<T>(slf);
+            let key = py.from_borrowed_ptr::<PyObjectRef>(key);
+
+            let result = if value.is_null() {
+                Err(PyErr::new::<exc::TypeError, _>(format!(
+                    "'{}' object does not support slice deletion",
+                    T::NAME
+                )))
+            } else {
+                let value = py.from_borrowed_ptr::<PyObjectRef>(value);
+                match key.extract() {
+                    Ok(key) => match value.extract() {
+                        Ok(value) => slf.__setitem__(key, value).into(),
+                        Err(e) => Err(e),
+                    },
+                    Err(e) => Err(e),
+                }
+            };
+            match result {
+                Ok(_) => 0,
+                Err(e) => {
+                    e.restore(py);
+                    -1
+                }
+            }
+        }
+        Some(wrap::<T>)
+    }
+}
+
+trait PySequenceDelSubscriptProtocolImpl {
+    fn mp_del_subscript() -> Option<ffi::objobjargproc>;
+}
+
+impl<'p, T> PySequenceDelSubscriptProtocolImpl for T
+where
+    T: PySequenceProtocol<'p>,
+{
+    #[inline]
+    default fn mp_del_subscript() -> Option<ffi::objobjargproc> {
+        None
+    }
+}
+
+impl<T> PySequenceDelSubscriptProtocolImpl for T
+where
+    T: for<'p> PySequenceDelItemProtocol<'p>,
+{
+    #[inline]
+    default fn mp_del_subscript() -> Option<ffi::objobjargproc> {
+        unsafe extern "C" fn wrap<T>(
+            slf: *mut ffi::PyObject,
+            key: *mut ffi::PyObject,
+            value: *mut ffi::PyObject,
+        ) -> c_int
+        where
+            T: for<'p> PySequenceDelItemProtocol<'p>,
+        {
+            if let Some(result) = ass_item_by_index(slf, key, value) {
+                return result;
+            }
+
+            let _pool = ::GILPool::new();
+            let py = Python::assume_gil_acquired();
+            let slf = py.mut_from_borrowed_ptr::<T>(slf);
+            let key = py.from_borrowed_ptr::<PyObjectRef>(key);
+
+            let result = if value.is_null() {
+                match key.extract() {
+                    Ok(key) => slf.__delitem__(key).into(),
+                    Err(e) => Err(e),
+                }
+            } else {
+                Err(PyErr::new::<exc::TypeError, _>(format!(
+                    "'{}' object does not support slice assignment",
+                    T::NAME
+                )))
+            };
+            match result {
+                Ok(_) => 0,
+                Err(e) => {
+                    e.restore(py);
+                    -1
+                }
+            }
+        }
+        Some(wrap::<T>)
+    }
+}
+
+impl<T> PySequenceDelSubscriptProtocolImpl for T
+where
+    T: for<'p> PySequenceSetItemProtocol<'p> + for<'p> PySequenceDelItemProtocol<'p>,
+{
+    #[inline]
+    fn mp_del_subscript() -> Option<ffi::objobjargproc> {
+        unsafe extern "C" fn wrap<T>(
+            slf: *mut ffi::PyObject,
+            key: *mut ffi::PyObject,
+            value: *mut ffi::PyObject,
+        ) -> c_int
+        where
+            T: for<'p> PySequenceSetItemProtocol<'p> + for<'p> PySequenceDelItemProtocol<'p>,
+        {
+            if let Some(result) = ass_item_by_index(slf, key, value) {
+                return result;
+            }
+
+            let _pool = ::GILPool::new();
+            let py = Python::assume_gil_acquired();
+            let slf = py.mut_from_borrowed_ptr::<T>(slf);
+            let key = py.from_borrowed_ptr::<PyObjectRef>(key);
+
+            let result = if value.is_null() {
+                match key.extract() {
+                    Ok(key) => slf.__delitem__(key).into(),
+                    Err(e) => Err(e),
+                }
+            } else {
+                let value = py.from_borrowed_ptr::<PyObjectRef>(value);
+                match key.extract() {
+                    Ok(key) => match value.extract() {
+                        Ok(value) => slf.__setitem__(key, value).into(),
+                        Err(e) => Err(e),
+                    },
+                    Err(e) => Err(e),
+                }
+            };
+            match result {
+                Ok(_) => 0,
+                Err(e) => {
+                    e.restore(py);
+                    -1
+                }
+            }
+        }
+        Some(wrap::<T>)
+    }
+}
+
 trait PySequenceContainsProtocolImpl {
     fn sq_contains() -> Option<ffi::objobjproc>;
 }
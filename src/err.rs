@@ -88,8 +88,9 @@ macro_rules! py_exception {
 
         impl $crate::typeob::PyTypeObject for $name {
             #[inline(always)]
-            fn init_type() {
+            fn init_type() -> $crate::PyResult<()> {
                 let _ = $name::type_object();
+                Ok(())
             }
 
             #[inline]
@@ -377,7 +378,7 @@ impl PyErr {
     /// Retrieves the exception instance for this error.
     /// This method takes `mut self` because the error might need
     /// to be normalized in order to create the exception instance.
-    fn instance(mut self, py: Python) -> PyObject {
+    pub(crate) fn instance(mut self, py: Python) -> PyObject {
         self.normalize(py);
         match self.pvalue {
             PyErrValue::Value(ref instance) => instance.clone_ref(py),
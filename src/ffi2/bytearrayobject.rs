@@ -2,12 +2,12 @@ use ffi2::object::*;
 use ffi2::pyport::Py_ssize_t;
 use std::os::raw::{c_char, c_int};
 
-/*#[repr(C)]
-#[deriving(Copy)]
-struct PyByteArrayObject {
-    #[cfg(py_sys_config="Py_TRACE_REFS")]
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct PyByteArrayObject {
+    #[cfg(py_sys_config = "Py_TRACE_REFS")]
     pub _ob_next: *mut PyObject,
-    #[cfg(py_sys_config="Py_TRACE_REFS")]
+    #[cfg(py_sys_config = "Py_TRACE_REFS")]
     pub _ob_prev: *mut PyObject,
     pub ob_refcnt: Py_ssize_t,
     pub ob_type: *mut PyTypeObject,
@@ -15,7 +15,7 @@ struct PyByteArrayObject {
     pub ob_exports: c_int,
     pub ob_alloc: Py_ssize_t,
     pub ob_bytes: *mut c_char,
-}*/
+}
 
 #[cfg_attr(windows, link(name = "pythonXY"))]
 extern "C" {
@@ -1,8 +1,36 @@
 use ffi2::object::*;
 use ffi2::pyport::Py_ssize_t;
-use std::os::raw::{c_char, c_int};
+use std::os::raw::{c_char, c_int, c_long};
 
-//pub enum PyDictObject { /* representation hidden */ }
+pub const PyDict_MINSIZE: usize = 8;
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct PyDictEntry {
+    pub me_hash: Py_ssize_t,
+    pub me_key: *mut PyObject,
+    pub me_value: *mut PyObject,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct PyDictObject {
+    #[cfg(py_sys_config = "Py_TRACE_REFS")]
+    pub _ob_next: *mut PyObject,
+    #[cfg(py_sys_config = "Py_TRACE_REFS")]
+    pub _ob_prev: *mut PyObject,
+    pub ob_refcnt: Py_ssize_t,
+    pub ob_type: *mut PyTypeObject,
+    pub ma_fill: Py_ssize_t,
+    pub ma_used: Py_ssize_t,
+    pub ma_mask: Py_ssize_t,
+    pub ma_table: *mut PyDictEntry,
+    pub ma_lookup: Option<
+        unsafe extern "C" fn(mp: *mut PyDictObject, key: *mut PyObject, hash: c_long)
+            -> *mut PyDictEntry,
+    >,
+    pub ma_smalltable: [PyDictEntry; PyDict_MINSIZE],
+}
 
 #[cfg_attr(windows, link(name = "pythonXY"))]
 extern "C" {
@@ -423,6 +423,9 @@ impl Clone for PyTypeObject {
     }
 }
 
+/// Layout of the type object filled by the protocol implementations
+pub type PyTypeObjectLayout = PyTypeObject;
+
 #[cfg(py_sys_config = "Py_TRACE_REFS")]
 pub const PyTypeObject_INIT: PyTypeObject = PyTypeObject {
     _ob_next: ::std::ptr::null_mut(),
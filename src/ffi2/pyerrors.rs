@@ -6,6 +6,76 @@ use ffi2::stringobject::PyString_AS_STRING;
 use ffi2::unicodeobject::Py_UNICODE;
 use std::os::raw::{c_char, c_int};
 
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct PyBaseExceptionObject {
+    #[cfg(py_sys_config = "Py_TRACE_REFS")]
+    pub _ob_next: *mut PyObject,
+    #[cfg(py_sys_config = "Py_TRACE_REFS")]
+    pub _ob_prev: *mut PyObject,
+    pub ob_refcnt: Py_ssize_t,
+    pub ob_type: *mut PyTypeObject,
+    pub dict: *mut PyObject,
+    pub args: *mut PyObject,
+    pub message: *mut PyObject,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct PySyntaxErrorObject {
+    pub ob_base: PyBaseExceptionObject,
+    pub msg: *mut PyObject,
+    pub filename: *mut PyObject,
+    pub lineno: *mut PyObject,
+    pub offset: *mut PyObject,
+    pub text: *mut PyObject,
+    pub print_file_and_line: *mut PyObject,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct PyUnicodeErrorObject {
+    pub ob_base: PyBaseExceptionObject,
+    pub encoding: *mut PyObject,
+    pub object: *mut PyObject,
+    pub start: Py_ssize_t,
+    pub end: Py_ssize_t,
+    pub reason: *mut PyObject,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct PySystemExitObject {
+    pub ob_base: PyBaseExceptionObject,
+    pub code: *mut PyObject,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct PyEnvironmentErrorObject {
+    pub ob_base: PyBaseExceptionObject,
+    pub myerrno: *mut PyObject,
+    pub strerror: *mut PyObject,
+    pub filename: *mut PyObject,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+#[cfg(windows)]
+pub struct PyWindowsErrorObject {
+    pub ob_base: PyBaseExceptionObject,
+    pub myerrno: *mut PyObject,
+    pub strerror: *mut PyObject,
+    pub filename: *mut PyObject,
+    pub winerror: *mut PyObject,
+}
+
+/// `OSError` and `IOError` are subclasses of `EnvironmentError`
+pub type PyOSErrorObject = PyEnvironmentErrorObject;
+/// `ImportError` and `StopIteration` use the base exception layout
+pub type PyImportErrorObject = PyBaseExceptionObject;
+pub type PyStopIterationObject = PyBaseExceptionObject;
+
 #[cfg_attr(windows, link(name = "pythonXY"))]
 extern "C" {
     pub fn PyErr_SetNone(arg1: *mut PyObject);
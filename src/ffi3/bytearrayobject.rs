@@ -2,6 +2,17 @@ use ffi3::object::*;
 use ffi3::pyport::Py_ssize_t;
 use std::os::raw::{c_char, c_int};
 
+#[repr(C)]
+#[derive(Copy, Clone)]
+#[cfg(not(Py_LIMITED_API))]
+pub struct PyByteArrayObject {
+    pub ob_base: PyVarObject,
+    pub ob_alloc: Py_ssize_t,
+    pub ob_bytes: *mut c_char,
+    pub ob_start: *mut c_char,
+    pub ob_exports: c_int,
+}
+
 #[cfg_attr(windows, link(name = "pythonXY"))]
 extern "C" {
     pub static mut PyByteArray_Type: PyTypeObject;
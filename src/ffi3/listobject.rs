@@ -2,6 +2,15 @@ use ffi3::object::*;
 use ffi3::pyport::Py_ssize_t;
 use std::os::raw::c_int;
 
+#[repr(C)]
+#[derive(Copy, Clone)]
+#[cfg(not(Py_LIMITED_API))]
+pub struct PyListObject {
+    pub ob_base: PyVarObject,
+    pub ob_item: *mut *mut PyObject,
+    pub allocated: Py_ssize_t,
+}
+
 #[cfg_attr(windows, link(name = "pythonXY"))]
 extern "C" {
     pub static mut PyList_Type: PyTypeObject;
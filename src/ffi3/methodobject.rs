@@ -23,6 +23,14 @@ pub type _PyCFunctionFast = unsafe extern "C" fn(
     kwnames: *mut PyObject,
 ) -> *mut PyObject;
 
+#[cfg(all(Py_3_7, not(Py_LIMITED_API)))]
+pub type _PyCFunctionFastWithKeywords = unsafe extern "C" fn(
+    slf: *mut PyObject,
+    args: *const *mut PyObject,
+    nargs: ::ffi3::pyport::Py_ssize_t,
+    kwnames: *mut PyObject,
+) -> *mut PyObject;
+
 pub type PyCFunctionWithKeywords =
     unsafe extern "C" fn(slf: *mut PyObject, args: *mut PyObject, kwds: *mut PyObject)
         -> *mut PyObject;
@@ -100,6 +108,9 @@ pub const METH_COEXIST: c_int = 0x0040;
 #[cfg(all(Py_3_6, not(Py_LIMITED_API)))]
 pub const METHOD_FASTCALL: c_int = 0x0080;
 
+#[cfg(all(Py_3_6, not(Py_LIMITED_API)))]
+pub const METH_FASTCALL: c_int = 0x0080;
+
 #[cfg_attr(windows, link(name = "pythonXY"))]
 extern "C" {
     pub fn PyCFunction_ClearFreeList() -> c_int;
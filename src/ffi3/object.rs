@@ -84,7 +84,6 @@ pub type ssizessizeobjargproc = unsafe extern "C" fn(
 pub type objobjargproc =
     unsafe extern "C" fn(arg1: *mut PyObject, arg2: *mut PyObject, arg3: *mut PyObject) -> c_int;
 
-#[cfg(not(Py_LIMITED_API))]
 mod bufferinfo {
     use ffi3::pyport::Py_ssize_t;
     use std::os::raw::{c_char, c_int, c_void};
@@ -149,7 +148,6 @@ mod bufferinfo {
     pub const PyBUF_READ: c_int = 0x100;
     pub const PyBUF_WRITE: c_int = 0x200;
 }
-#[cfg(not(Py_LIMITED_API))]
 pub use self::bufferinfo::*;
 
 pub type objobjproc = unsafe extern "C" fn(arg1: *mut PyObject, arg2: *mut PyObject) -> c_int;
@@ -159,7 +157,6 @@ pub type traverseproc =
 
 pub type freefunc = unsafe extern "C" fn(arg1: *mut c_void);
 pub type destructor = unsafe extern "C" fn(arg1: *mut PyObject);
-#[cfg(not(Py_LIMITED_API))]
 pub type printfunc =
     unsafe extern "C" fn(arg1: *mut PyObject, arg2: *mut ::libc::FILE, arg3: c_int) -> c_int;
 pub type getattrfunc =
@@ -189,11 +186,15 @@ pub type newfunc =
 pub type allocfunc =
     unsafe extern "C" fn(arg1: *mut PyTypeObject, arg2: Py_ssize_t) -> *mut PyObject;
 
+/// Type objects are opaque in the limited API
 #[cfg(Py_LIMITED_API)]
-pub enum PyTypeObject {
+#[repr(C)]
+pub struct PyTypeObject {
+    _private: [u8; 0],
 }
 
-#[cfg(not(Py_LIMITED_API))]
+/// With the limited API the layout of the type object is used as a template only,
+/// `PyType_FromSpec` slots are created from it
 mod typeobject {
     use ffi3::pyport::Py_ssize_t;
     use ffi3::{self, object};
@@ -537,6 +538,7 @@ mod typeobject {
     pub const PyTypeObject_INIT: PyTypeObject =
         py_type_object_init_with_count_allocs!(tp_as_async, tp_finalize: None,);
 
+    #[cfg(not(Py_LIMITED_API))]
     #[repr(C)]
     #[derive(Copy, Clone)]
     pub struct PyHeapTypeObject {
@@ -552,6 +554,7 @@ mod typeobject {
         pub ht_cached_keys: *mut c_void,
     }
 
+    #[cfg(not(Py_LIMITED_API))]
     impl Default for PyHeapTypeObject {
         #[inline]
         fn default() -> Self {
@@ -559,6 +562,7 @@ mod typeobject {
         }
     }
 
+    #[cfg(not(Py_LIMITED_API))]
     #[inline]
     pub unsafe fn PyHeapType_GET_MEMBERS(
         etype: *mut PyHeapTypeObject,
@@ -571,6 +575,18 @@ mod typeobject {
 #[cfg(not(Py_LIMITED_API))]
 pub use self::typeobject::*;
 
+/// Layout of the type object filled by the protocol implementations,
+/// the same type as `PyTypeObject` unless the limited API is used
+#[cfg(not(Py_LIMITED_API))]
+pub type PyTypeObjectLayout = PyTypeObject;
+
+#[cfg(Py_LIMITED_API)]
+pub use self::typeobject::{PyAsyncMethods, PyAsyncMethods_INIT, PyBufferProcs, PyBufferProcs_INIT,
+                           PyMappingMethods, PyMappingMethods_INIT, PyNumberMethods,
+                           PyNumberMethods_INIT, PySequenceMethods, PySequenceMethods_INIT,
+                           PyTypeObject as PyTypeObjectLayout,
+                           PyTypeObject_INIT as PyTypeObjectLayout_INIT};
+
 #[repr(C)]
 #[derive(Copy, Clone)]
 pub struct PyType_Slot {
@@ -2,6 +2,93 @@ use ffi3::object::*;
 use ffi3::pyport::Py_ssize_t;
 use std::os::raw::{c_char, c_int};
 
+/// The layout is used for the size of the exception objects
+#[repr(C)]
+#[derive(Copy, Clone)]
+#[cfg(not(Py_LIMITED_API))]
+pub struct PyBaseExceptionObject {
+    pub ob_base: PyObject,
+    pub dict: *mut PyObject,
+    pub args: *mut PyObject,
+    #[cfg(Py_3_11)]
+    pub notes: *mut PyObject,
+    pub traceback: *mut PyObject,
+    pub context: *mut PyObject,
+    pub cause: *mut PyObject,
+    pub suppress_context: c_char,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+#[cfg(not(Py_LIMITED_API))]
+pub struct PySyntaxErrorObject {
+    pub ob_base: PyBaseExceptionObject,
+    pub msg: *mut PyObject,
+    pub filename: *mut PyObject,
+    pub lineno: *mut PyObject,
+    pub offset: *mut PyObject,
+    pub text: *mut PyObject,
+    pub print_file_and_line: *mut PyObject,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+#[cfg(not(Py_LIMITED_API))]
+pub struct PyImportErrorObject {
+    pub ob_base: PyBaseExceptionObject,
+    pub msg: *mut PyObject,
+    pub name: *mut PyObject,
+    pub path: *mut PyObject,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+#[cfg(not(Py_LIMITED_API))]
+pub struct PyUnicodeErrorObject {
+    pub ob_base: PyBaseExceptionObject,
+    pub encoding: *mut PyObject,
+    pub object: *mut PyObject,
+    pub start: Py_ssize_t,
+    pub end: Py_ssize_t,
+    pub reason: *mut PyObject,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+#[cfg(not(Py_LIMITED_API))]
+pub struct PySystemExitObject {
+    pub ob_base: PyBaseExceptionObject,
+    pub code: *mut PyObject,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+#[cfg(not(Py_LIMITED_API))]
+pub struct PyOSErrorObject {
+    pub ob_base: PyBaseExceptionObject,
+    pub myerrno: *mut PyObject,
+    pub strerror: *mut PyObject,
+    pub filename: *mut PyObject,
+    pub filename2: *mut PyObject,
+    #[cfg(windows)]
+    pub winerror: *mut PyObject,
+    pub written: Py_ssize_t,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+#[cfg(not(Py_LIMITED_API))]
+pub struct PyStopIterationObject {
+    pub ob_base: PyBaseExceptionObject,
+    pub value: *mut PyObject,
+}
+
+/// `EnvironmentError`, `IOError` and `WindowsError` are aliases of `OSError`
+#[cfg(not(Py_LIMITED_API))]
+pub type PyEnvironmentErrorObject = PyOSErrorObject;
+#[cfg(all(windows, not(Py_LIMITED_API)))]
+pub type PyWindowsErrorObject = PyOSErrorObject;
+
 #[cfg_attr(windows, link(name = "pythonXY"))]
 extern "C" {
     pub fn PyErr_SetNone(arg1: *mut PyObject) -> ();
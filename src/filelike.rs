@@ -0,0 +1,709 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Adapters between Python file objects and Rust streams.
+//!
+//! `PyFileLike` exposes any Python object with `read`/`readinto`/`write`/`seek`
+//! methods as `std::io::Read`, `BufRead`, `Write` and `Seek`.
+//! `PyRawIO` exposes a Rust stream to Python as `io.RawIOBase` compatible object.
+
+use std::io::{self, BufRead, Read, Seek, SeekFrom, Write};
+use std::os::raw::c_void;
+use std::sync::atomic::{AtomicBool, Ordering, ATOMIC_BOOL_INIT};
+#[cfg(Py_3)]
+use std::ptr;
+use std::{cmp, mem, slice};
+
+use buffer::PyBuffer;
+use callback::{cb_convert, PyObjectCallbackConverter};
+use class::methods::{PyGetterDef, PyMethodDef, PyMethodDefType, PyMethodType,
+                     PyMethodsProtocolImpl};
+use conversion::IntoPyObject;
+use err::{PyErr, PyResult};
+use instance::{AsPyRef, Py, PyToken};
+use object::PyObject;
+use objectprotocol::ObjectProtocol;
+#[cfg(Py_3)]
+use objects::PyByteArray;
+use objects::{exc, PyBytes, PyObjectRef, PyTuple};
+use python::Python;
+use pythonrun::GILPool;
+use typeob::PyTypeObject;
+use ffi;
+
+const DEFAULT_BUF_SIZE: usize = 8 * 1024;
+
+/// Upper bound of a single `PyRawIO.read(size)`, raw streams may return less than requested
+const MAX_READ_SIZE: usize = 1024 * 1024;
+
+/// Wraps a Python file object, i.e. an opened file or `io.BytesIO`,
+/// as Rust `Read`, `BufRead`, `Write` and `Seek` stream.
+///
+/// The GIL is acquired for every call into the Python object.
+/// Reads are buffered, the buffer is discarded on `write` and `seek`.
+pub struct PyFileLike {
+    inner: PyObject,
+    buf: Box<[u8]>,
+    pos: usize,
+    cap: usize,
+}
+
+impl PyFileLike {
+    /// Wraps the Python file object `obj`, with the default read buffer size.
+    pub fn new(obj: PyObject) -> PyFileLike {
+        PyFileLike::with_capacity(DEFAULT_BUF_SIZE, obj)
+    }
+
+    /// Wraps the Python file object `obj`, with the given read buffer size.
+    pub fn with_capacity(capacity: usize, obj: PyObject) -> PyFileLike {
+        PyFileLike {
+            inner: obj,
+            buf: vec![0; capacity].into_boxed_slice(),
+            pos: 0,
+            cap: 0,
+        }
+    }
+
+    /// Gets a reference to the underlying Python object.
+    pub fn get_ref(&self) -> &PyObject {
+        &self.inner
+    }
+
+    /// Unwraps the underlying Python object. Buffered data is lost.
+    pub fn into_inner(self) -> PyObject {
+        self.inner
+    }
+
+    /// Reads directly from the Python object, bypassing the read buffer.
+    fn read_raw(obj: &PyObject, buf: &mut [u8]) -> io::Result<usize> {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        match read_into(py, obj.as_ref(py), buf)? {
+            Some(n) => Ok(n),
+            None => Err(io::Error::new(
+                io::ErrorKind::WouldBlock,
+                "no data available on non-blocking stream",
+            )),
+        }
+    }
+
+    fn seek_raw(&self, offset: i64, whence: i32) -> io::Result<u64> {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let obj = self.inner.as_ref(py);
+
+        let pos = obj.call_method1("seek", (offset, whence))?;
+        // Python 2 file objects return `None` from `seek()`
+        if pos.is_none() {
+            Ok(obj.call_method0("tell")?.extract()?)
+        } else {
+            Ok(pos.extract()?)
+        }
+    }
+
+    /// Moves the Python object position back to the first unread byte
+    /// and clears the read buffer.
+    fn discard_buffer(&mut self) -> io::Result<()> {
+        let remaining = self.cap - self.pos;
+        if remaining > 0 {
+            self.seek_raw(-(remaining as i64), 1)?;
+        }
+        self.pos = 0;
+        self.cap = 0;
+        Ok(())
+    }
+}
+
+#[cfg(Py_3)]
+fn read_into(py: Python, obj: &PyObjectRef, buf: &mut [u8]) -> PyResult<Option<usize>> {
+    if !obj.hasattr("readinto")? {
+        return read_copy(py, obj, buf);
+    }
+
+    // python code may keep a reference to the buffer passed to `readinto()`,
+    // so it is a bytearray owned by python instead of a memoryview of `buf`
+    let array: &PyByteArray = unsafe {
+        py.from_owned_ptr_or_err(ffi::PyByteArray_FromStringAndSize(
+            ptr::null(),
+            buf.len() as ffi::Py_ssize_t,
+        ))?
+    };
+    let n = obj.call_method1("readinto", (array,))?;
+    if n.is_none() {
+        return Ok(None);
+    }
+    let n: usize = n.extract()?;
+    let data = array.to_vec()?;
+    if n > buf.len() || n > data.len() {
+        return Err(PyErr::new::<exc::ValueError, _>(format!(
+            "readinto() returned {} bytes, {} requested",
+            n,
+            buf.len()
+        )));
+    }
+    buf[..n].copy_from_slice(&data[..n]);
+    Ok(Some(n))
+}
+
+#[cfg(not(Py_3))]
+fn read_into(py: Python, obj: &PyObjectRef, buf: &mut [u8]) -> PyResult<Option<usize>> {
+    read_copy(py, obj, buf)
+}
+
+fn read_copy(_py: Python, obj: &PyObjectRef, buf: &mut [u8]) -> PyResult<Option<usize>> {
+    let data = obj.call_method1("read", (buf.len(),))?;
+    if data.is_none() {
+        return Ok(None);
+    }
+    let data = data.extract::<&PyBytes>()?.data();
+    if data.len() > buf.len() {
+        return Err(PyErr::new::<exc::ValueError, _>(format!(
+            "read() returned {} bytes, {} requested",
+            data.len(),
+            buf.len()
+        )));
+    }
+    buf[..data.len()].copy_from_slice(data);
+    Ok(Some(data.len()))
+}
+
+impl Read for PyFileLike {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // bypass the read buffer for large reads
+        if self.pos == self.cap && buf.len() >= self.buf.len() {
+            return PyFileLike::read_raw(&self.inner, buf);
+        }
+        let nread = {
+            let mut rem = self.fill_buf()?;
+            rem.read(buf)?
+        };
+        self.consume(nread);
+        Ok(nread)
+    }
+}
+
+impl BufRead for PyFileLike {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.pos >= self.cap {
+            self.cap = PyFileLike::read_raw(&self.inner, &mut self.buf)?;
+            self.pos = 0;
+        }
+        Ok(&self.buf[self.pos..self.cap])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = cmp::min(self.pos + amt, self.cap);
+    }
+}
+
+impl Write for PyFileLike {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.discard_buffer()?;
+
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let n = self.inner
+            .call_method1(py, "write", (PyBytes::new(py, buf),))?;
+        // Python 2 file objects return `None` from `write()`
+        if n.is_none() {
+            Ok(buf.len())
+        } else {
+            Ok(n.extract(py)?)
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let obj = self.inner.as_ref(py);
+
+        if obj.hasattr("flush")? {
+            obj.call_method0("flush")?;
+        }
+        Ok(())
+    }
+}
+
+impl Seek for PyFileLike {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let (offset, whence) = match pos {
+            SeekFrom::Start(offset) => (offset as i64, 0),
+            // the Python object position is ahead of the unread buffered data
+            SeekFrom::Current(offset) => (offset - (self.cap - self.pos) as i64, 1),
+            SeekFrom::End(offset) => (offset, 2),
+        };
+        self.pos = 0;
+        self.cap = 0;
+        self.seek_raw(offset, whence)
+    }
+}
+
+/// Rust stream that can be exposed to Python with `PyRawIO`.
+pub trait RawStream: Read + Write + Seek + Send {}
+
+impl<T> RawStream for T
+where
+    T: Read + Write + Seek + Send,
+{
+}
+
+/// Python raw binary stream backed by a Rust stream.
+///
+/// Implements the `io.RawIOBase` interface and is registered as its virtual subclass,
+/// so it can be wrapped with `io.BufferedReader` or `io.TextIOWrapper`.
+pub struct PyRawIO {
+    stream: Box<RawStream>,
+    closed: bool,
+    token: PyToken,
+}
+
+impl PyRawIO {
+    /// Creates a new Python stream object from a Rust stream.
+    pub fn new(py: Python, stream: Box<RawStream>) -> PyResult<Py<PyRawIO>> {
+        register_raw_io_base(py)?;
+        Py::new(py, |token| PyRawIO {
+            stream: stream,
+            closed: false,
+            token: token,
+        })
+    }
+
+    /// Closes the stream and returns the underlying Rust stream.
+    pub fn take(&mut self) -> PyResult<Box<RawStream>> {
+        self.check_closed()?;
+        self.closed = true;
+        Ok(mem::replace(&mut self.stream, Box::new(io::Cursor::new(Vec::new()))))
+    }
+
+    fn check_closed(&self) -> PyResult<()> {
+        if self.closed {
+            Err(PyErr::new::<exc::ValueError, _>(
+                "I/O operation on closed file.",
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn readinto(&mut self, b: &PyObjectRef) -> PyResult<usize> {
+        self.check_closed()?;
+        let buf = contiguous_buffer(b)?;
+        if buf.readonly() {
+            return Err(PyErr::new::<exc::TypeError, _>(
+                "readinto() argument must be read-write bytes-like object",
+            ));
+        }
+        let data = unsafe { slice::from_raw_parts_mut(buf.buf_ptr() as *mut u8, buf.len_bytes()) };
+        Ok(self.stream.read(data)?)
+    }
+
+    fn read(&mut self, py: Python, size: isize) -> PyResult<PyObject> {
+        self.check_closed()?;
+        if size < 0 {
+            return self.readall(py);
+        }
+        let mut data = vec![0; cmp::min(size as usize, MAX_READ_SIZE)];
+        let n = self.stream.read(&mut data)?;
+        Ok(PyBytes::new(py, &data[..n]).into())
+    }
+
+    fn readall(&mut self, py: Python) -> PyResult<PyObject> {
+        self.check_closed()?;
+        let mut data = Vec::new();
+        self.stream.read_to_end(&mut data)?;
+        Ok(PyBytes::new(py, &data).into())
+    }
+
+    fn write(&mut self, b: &PyObjectRef) -> PyResult<usize> {
+        self.check_closed()?;
+        let buf = contiguous_buffer(b)?;
+        let data = unsafe { slice::from_raw_parts(buf.buf_ptr() as *const u8, buf.len_bytes()) };
+        Ok(self.stream.write(data)?)
+    }
+
+    fn seek(&mut self, offset: i64, whence: i32) -> PyResult<u64> {
+        self.check_closed()?;
+        let pos = match whence {
+            0 if offset >= 0 => SeekFrom::Start(offset as u64),
+            0 => {
+                return Err(PyErr::new::<exc::ValueError, _>(format!(
+                    "negative seek position {}",
+                    offset
+                )))
+            }
+            1 => SeekFrom::Current(offset),
+            2 => SeekFrom::End(offset),
+            _ => {
+                return Err(PyErr::new::<exc::ValueError, _>(format!(
+                    "invalid whence ({}, should be 0, 1 or 2)",
+                    whence
+                )))
+            }
+        };
+        Ok(self.stream.seek(pos)?)
+    }
+
+    fn tell(&mut self) -> PyResult<u64> {
+        self.check_closed()?;
+        Ok(self.stream.seek(SeekFrom::Current(0))?)
+    }
+
+    fn flush(&mut self) -> PyResult<()> {
+        self.check_closed()?;
+        Ok(self.stream.flush()?)
+    }
+
+    fn close(&mut self) -> PyResult<()> {
+        if !self.closed {
+            let result = self.stream.flush();
+            self.closed = true;
+            result?;
+        }
+        Ok(())
+    }
+}
+
+fn contiguous_buffer(ob: &PyObjectRef) -> PyResult<PyBuffer> {
+    let buf = PyBuffer::get(ob.py(), ob)?;
+    if !buf.is_c_contiguous() {
+        return Err(PyErr::new::<exc::BufferError, _>(
+            "bytes-like object must be C-contiguous",
+        ));
+    }
+    Ok(buf)
+}
+
+/// Registers `PyRawIO` as virtual subclass of `io.RawIOBase`.
+///
+/// Importing `io` runs python code, so another thread may register the class
+/// concurrently, which is harmless. A failed registration is retried by the next call.
+fn register_raw_io_base(py: Python) -> PyResult<()> {
+    static REGISTERED: AtomicBool = ATOMIC_BOOL_INIT;
+    if REGISTERED.load(Ordering::Acquire) {
+        return Ok(());
+    }
+
+    <PyRawIO as PyTypeObject>::init_type()?;
+    let base = py.import("io")?.get("RawIOBase")?;
+    base.call_method1("register", (<PyRawIO as PyTypeObject>::type_object(),))?;
+    REGISTERED.store(true, Ordering::Release);
+    Ok(())
+}
+
+pyobject_rusttype!(PyRawIO, "RawIO", "Raw binary stream backed by a Rust stream");
+
+impl PyMethodsProtocolImpl for PyRawIO {
+    fn py_methods() -> &'static [PyMethodDefType] {
+        static METHODS: &'static [PyMethodDefType] = &[
+            PyMethodDefType::Method(PyMethodDef {
+                ml_name: "readable",
+                ml_meth: PyMethodType::PyCFunction(raw_io_true),
+                ml_flags: ffi::METH_NOARGS,
+                ml_doc: "Returns True, the stream supports reading.\0",
+            }),
+            PyMethodDefType::Method(PyMethodDef {
+                ml_name: "writable",
+                ml_meth: PyMethodType::PyCFunction(raw_io_true),
+                ml_flags: ffi::METH_NOARGS,
+                ml_doc: "Returns True, the stream supports writing.\0",
+            }),
+            PyMethodDefType::Method(PyMethodDef {
+                ml_name: "seekable",
+                ml_meth: PyMethodType::PyCFunction(raw_io_true),
+                ml_flags: ffi::METH_NOARGS,
+                ml_doc: "Returns True, the stream supports random access.\0",
+            }),
+            PyMethodDefType::Method(PyMethodDef {
+                ml_name: "isatty",
+                ml_meth: PyMethodType::PyCFunction(raw_io_false),
+                ml_flags: ffi::METH_NOARGS,
+                ml_doc: "Returns False, the stream is not interactive.\0",
+            }),
+            PyMethodDefType::Method(PyMethodDef {
+                ml_name: "readinto",
+                ml_meth: PyMethodType::PyCFunction(raw_io_readinto),
+                ml_flags: ffi::METH_O,
+                ml_doc: "Read bytes into a pre-allocated, writable bytes-like object.\0",
+            }),
+            PyMethodDefType::Method(PyMethodDef {
+                ml_name: "read",
+                ml_meth: PyMethodType::PyCFunction(raw_io_read),
+                ml_flags: ffi::METH_VARARGS,
+                ml_doc: "Read up to size bytes, read until EOF if size is omitted or negative.\0",
+            }),
+            PyMethodDefType::Method(PyMethodDef {
+                ml_name: "readall",
+                ml_meth: PyMethodType::PyCFunction(raw_io_readall),
+                ml_flags: ffi::METH_NOARGS,
+                ml_doc: "Read until EOF.\0",
+            }),
+            PyMethodDefType::Method(PyMethodDef {
+                ml_name: "write",
+                ml_meth: PyMethodType::PyCFunction(raw_io_write),
+                ml_flags: ffi::METH_O,
+                ml_doc: "Write the given bytes-like object, return the number of bytes written.\0",
+            }),
+            PyMethodDefType::Method(PyMethodDef {
+                ml_name: "seek",
+                ml_meth: PyMethodType::PyCFunction(raw_io_seek),
+                ml_flags: ffi::METH_VARARGS,
+                ml_doc: "Change stream position, return the new absolute position.\0",
+            }),
+            PyMethodDefType::Method(PyMethodDef {
+                ml_name: "tell",
+                ml_meth: PyMethodType::PyCFunction(raw_io_tell),
+                ml_flags: ffi::METH_NOARGS,
+                ml_doc: "Return current stream position.\0",
+            }),
+            PyMethodDefType::Method(PyMethodDef {
+                ml_name: "flush",
+                ml_meth: PyMethodType::PyCFunction(raw_io_flush),
+                ml_flags: ffi::METH_NOARGS,
+                ml_doc: "Flush the underlying Rust stream.\0",
+            }),
+            PyMethodDefType::Method(PyMethodDef {
+                ml_name: "close",
+                ml_meth: PyMethodType::PyCFunction(raw_io_close),
+                ml_flags: ffi::METH_NOARGS,
+                ml_doc: "Flush and close the stream.\0",
+            }),
+            PyMethodDefType::Getter(PyGetterDef {
+                name: "closed",
+                meth: raw_io_closed,
+                doc: "True if the stream is closed.\0",
+            }),
+        ];
+        METHODS
+    }
+}
+
+/// Calls `f` with the `PyRawIO` instance and converts the result.
+unsafe fn raw_io_call<F, R>(slf: *mut ffi::PyObject, f: F) -> *mut ffi::PyObject
+where
+    F: FnOnce(Python, &mut PyRawIO) -> PyResult<R>,
+    R: IntoPyObject,
+{
+    let _pool = GILPool::new();
+    let py = Python::assume_gil_acquired();
+    let slf = py.mut_from_borrowed_ptr::<PyRawIO>(slf);
+    cb_convert(PyObjectCallbackConverter, py, f(py, slf))
+}
+
+/// Extracts optional positional argument `idx` from the `args` tuple.
+fn optional_arg<'a>(args: &'a PyTuple, idx: usize) -> Option<&'a PyObjectRef> {
+    if idx < args.len() {
+        Some(args.get_item(idx))
+    } else {
+        None
+    }
+}
+
+unsafe extern "C" fn raw_io_true(
+    slf: *mut ffi::PyObject,
+    _args: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    raw_io_call(slf, |_, slf| slf.check_closed().map(|_| true))
+}
+
+unsafe extern "C" fn raw_io_false(
+    slf: *mut ffi::PyObject,
+    _args: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    raw_io_call(slf, |_, slf| slf.check_closed().map(|_| false))
+}
+
+unsafe extern "C" fn raw_io_readinto(
+    slf: *mut ffi::PyObject,
+    arg: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    raw_io_call(slf, |py, slf| {
+        slf.readinto(py.from_borrowed_ptr::<PyObjectRef>(arg))
+    })
+}
+
+unsafe extern "C" fn raw_io_read(
+    slf: *mut ffi::PyObject,
+    args: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    raw_io_call(slf, |py, slf| {
+        let args = py.from_borrowed_ptr::<PyTuple>(args);
+        let size = match optional_arg(args, 0) {
+            Some(size) if !size.is_none() => size.extract()?,
+            _ => -1,
+        };
+        slf.read(py, size)
+    })
+}
+
+unsafe extern "C" fn raw_io_readall(
+    slf: *mut ffi::PyObject,
+    _args: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    raw_io_call(slf, |py, slf| slf.readall(py))
+}
+
+unsafe extern "C" fn raw_io_write(
+    slf: *mut ffi::PyObject,
+    arg: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    raw_io_call(slf, |py, slf| {
+        slf.write(py.from_borrowed_ptr::<PyObjectRef>(arg))
+    })
+}
+
+unsafe extern "C" fn raw_io_seek(
+    slf: *mut ffi::PyObject,
+    args: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    raw_io_call(slf, |py, slf| {
+        let args = py.from_borrowed_ptr::<PyTuple>(args);
+        let offset = match optional_arg(args, 0) {
+            Some(offset) => offset.extract()?,
+            None => {
+                return Err(PyErr::new::<exc::TypeError, _>(
+                    "seek() missing required argument 'offset'",
+                ))
+            }
+        };
+        let whence = match optional_arg(args, 1) {
+            Some(whence) => whence.extract()?,
+            None => 0,
+        };
+        slf.seek(offset, whence)
+    })
+}
+
+unsafe extern "C" fn raw_io_tell(
+    slf: *mut ffi::PyObject,
+    _args: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    raw_io_call(slf, |_, slf| slf.tell())
+}
+
+unsafe extern "C" fn raw_io_flush(
+    slf: *mut ffi::PyObject,
+    _args: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    raw_io_call(slf, |_, slf| slf.flush())
+}
+
+unsafe extern "C" fn raw_io_close(
+    slf: *mut ffi::PyObject,
+    _args: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    raw_io_call(slf, |_, slf| slf.close())
+}
+
+unsafe extern "C" fn raw_io_closed(
+    slf: *mut ffi::PyObject,
+    _closure: *mut c_void,
+) -> *mut ffi::PyObject {
+    raw_io_call(slf, |_, slf| Ok(slf.closed))
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{BufRead, Cursor, Read, Seek, SeekFrom, Write};
+
+    use super::{PyFileLike, PyRawIO};
+    use objects::PyDict;
+    use python::Python;
+
+    #[test]
+    fn test_file_like_read_seek() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let io = py.import("io").unwrap();
+        let ob = io.call_method1("BytesIO", (&b"line 1\nline 2\n"[..],))
+            .unwrap();
+        let mut file = PyFileLike::with_capacity(4, ob.into());
+
+        let mut line = String::new();
+        file.read_line(&mut line).unwrap();
+        assert_eq!("line 1\n", line);
+
+        assert_eq!(3, file.seek(SeekFrom::Current(-4)).unwrap());
+        let mut rest = String::new();
+        file.read_to_string(&mut rest).unwrap();
+        assert_eq!("e 1\nline 2\n", rest);
+    }
+
+    #[test]
+    #[cfg(Py_3)]
+    fn test_file_like_readinto_keeps_buffer() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let d = PyDict::new(py);
+        py.run(
+            r#"
+import io
+class Keeper(io.RawIOBase):
+    def readable(self):
+        return True
+    def readinto(self, b):
+        self.kept = b
+        b[:3] = b'abc'
+        return 3
+keeper = Keeper()
+"#,
+            None,
+            Some(d),
+        ).unwrap();
+
+        let mut file = PyFileLike::new(d.get_item("keeper").unwrap().into());
+        let mut buf = [0; 8];
+        assert_eq!(3, file.read(&mut buf).unwrap());
+        assert_eq!(b"abc", &buf[..3]);
+        drop(file);
+
+        // the buffer kept by python is not the rust buffer
+        py.run("keeper.kept[:3] = b'xyz'", None, Some(d)).unwrap();
+        assert_eq!(b"abc", &buf[..3]);
+    }
+
+    #[test]
+    fn test_file_like_write() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let io = py.import("io").unwrap();
+        let ob = io.call_method0("BytesIO").unwrap();
+        let mut file = PyFileLike::new(ob.into());
+        file.write_all(b"Hello").unwrap();
+        file.flush().unwrap();
+
+        let data: Vec<u8> = ob.call_method0("getvalue").unwrap().extract().unwrap();
+        assert_eq!(b"Hello".to_vec(), data);
+    }
+
+    #[test]
+    fn test_raw_io() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let raw = PyRawIO::new(py, Box::new(Cursor::new(b"line 1\nline 2\n".to_vec()))).unwrap();
+        let d = PyDict::new(py);
+        d.set_item("raw", raw).unwrap();
+
+        py.run("import io; assert isinstance(raw, io.RawIOBase)", None, Some(d))
+            .unwrap();
+        py.run(
+            "f = io.TextIOWrapper(io.BufferedReader(raw)); assert f.readlines() == ['line 1\\n', 'line 2\\n']",
+            None,
+            Some(d),
+        ).unwrap();
+        py.run("raw.seek(0); assert raw.read(4) == b'line'", None, Some(d))
+            .unwrap();
+        py.run("raw.seek(0, 2); raw.write(b'end'); raw.seek(-3, 2); assert raw.read() == b'end'", None, Some(d))
+            .unwrap();
+        // the size is not allocated upfront
+        py.run("raw.seek(0); assert raw.read(1 << 50) == b'line 1\\nline 2\\nend'", None, Some(d))
+            .unwrap();
+        py.run("raw.close(); assert raw.closed", None, Some(d))
+            .unwrap();
+        assert!(py.run("raw.read()", None, Some(d)).is_err());
+    }
+}
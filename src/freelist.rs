@@ -6,7 +6,7 @@ use std;
 use err::PyResult;
 use ffi;
 use python::Python;
-use typeob::{PyObjectAlloc, PyTypeInfo};
+use typeob::{type_free, PyObjectAlloc, PyTypeInfo};
 
 /// Implementing this trait for custom class adds free allocation list to class.
 /// The performance improvement applies to types that are often created and deleted in a row,
@@ -86,12 +86,15 @@ where
     unsafe fn dealloc(py: Python, obj: *mut ffi::PyObject) {
         Self::drop(py, obj);
 
-        if ffi::PyObject_CallFinalizerFromDealloc(obj) < 0 {
-            return;
+        #[cfg(not(Py_LIMITED_API))]
+        {
+            if ffi::PyObject_CallFinalizerFromDealloc(obj) < 0 {
+                return;
+            }
         }
 
         if let Some(obj) = <T as PyObjectWithFreeList>::get_free_list().insert(obj) {
-            match (*T::type_object()).tp_free {
+            match type_free(T::type_object()) {
                 Some(free) => free(obj as *mut ::c_void),
                 None => {
                     let ty = ffi::Py_TYPE(obj);
@@ -116,7 +119,7 @@ where
         Self::drop(py, obj);
 
         if let Some(obj) = <T as PyObjectWithFreeList>::get_free_list().insert(obj) {
-            match (*T::type_object()).tp_free {
+            match type_free(T::type_object()) {
                 Some(free) => free(obj as *mut ::c_void),
                 None => {
                     let ty = ffi::Py_TYPE(obj);
@@ -0,0 +1,92 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Free functions
+//!
+//! `#[function]` exports a free function, the function can be added to any number
+//! of modules with `PyModule::add_function(wrap_function!(name))`.
+
+use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use class::methods::PyMethodDef;
+use err::PyResult;
+use ffi;
+use object::PyObject;
+use objects::{PyModule, PyString};
+use python::{Python, ToPyPointer};
+use stubs::StubFn;
+
+/// Definition of the function exported with `#[function]`
+pub struct PyFunctionDef {
+    pub method: PyMethodDef,
+    pub stub: StubFn,
+    /// Address of the `ffi::PyMethodDef` shared by the function objects,
+    /// created by the first `create()` call
+    #[doc(hidden)]
+    pub ffi_def: AtomicUsize,
+}
+
+/// Implemented by `#[function]` for the type with the name of the function
+#[doc(hidden)]
+pub trait PyFunctionInfo {
+    fn function_def() -> &'static PyFunctionDef;
+}
+
+impl PyFunctionDef {
+    /// Python name of the function
+    pub fn name(&self) -> &'static str {
+        self.method.ml_name
+    }
+
+    /// Creates the function object, `module` is the module of the function
+    pub fn create(&self, py: Python, module: Option<&PyModule>) -> PyResult<PyObject> {
+        unsafe {
+            match module {
+                Some(module) => {
+                    let name = PyString::new(py, module.name()?);
+                    PyObject::from_owned_ptr_or_err(
+                        py,
+                        ffi::PyCFunction_NewEx(self.method_def(), module.as_ptr(), name.as_ptr()),
+                    )
+                }
+                None => PyObject::from_owned_ptr_or_err(
+                    py,
+                    ffi::PyCFunction_New(self.method_def(), ptr::null_mut()),
+                ),
+            }
+        }
+    }
+
+    /// The function objects keep a pointer to the definition,
+    /// so it is created once and lives as long as the `PyFunctionDef`
+    fn method_def(&self) -> *mut ffi::PyMethodDef {
+        let def = self.ffi_def.load(Ordering::Acquire);
+        if def != 0 {
+            return def as *mut ffi::PyMethodDef;
+        }
+
+        let new_def = Box::into_raw(Box::new(self.method.as_method_def())) as usize;
+        match self.ffi_def.compare_exchange(0, new_def, Ordering::AcqRel, Ordering::Acquire) {
+            Ok(_) => new_def as *mut ffi::PyMethodDef,
+            Err(def) => {
+                // another thread created the definition first
+                unsafe { drop(Box::from_raw(new_def as *mut ffi::PyMethodDef)) };
+                def as *mut ffi::PyMethodDef
+            }
+        }
+    }
+}
+
+/// Returns the `PyFunctionDef` of the function exported with `#[function]`,
+/// the function object can be created with `create()`.
+///
+/// ```rust,ignore
+/// m.add_function(wrap_function!(double))?;
+/// let double = wrap_function!(double).create(py, None)?;
+/// ```
+#[macro_export]
+macro_rules! wrap_function {
+    ($name:path) => {
+        <$name as $crate::function::PyFunctionInfo>::function_def()
+    };
+}
@@ -14,6 +14,9 @@ use python::{IntoPyPointer, Python, ToPyPointer};
 use pythonrun;
 use typeob::{PyTypeInfo, PyTypeObject};
 
+/// Tokens carry no data, all tokens are equal.
+/// This allows `#[class]` structs to derive comparison and hashing traits.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct PyToken(PhantomData<Rc<()>>);
 
 impl PyToken {
@@ -28,6 +31,27 @@ impl PyToken {
     }
 }
 
+impl std::fmt::Debug for PyToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("PyToken")
+    }
+}
+
+/// Token is serialized as unit, so `#[class]` structs can derive serde traits.
+#[cfg(feature = "serde-pickle")]
+impl ::serde::Serialize for PyToken {
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_unit()
+    }
+}
+
+#[cfg(feature = "serde-pickle")]
+impl<'de> ::serde::Deserialize<'de> for PyToken {
+    fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        <() as ::serde::Deserialize>::deserialize(deserializer).map(|_| PyToken::new())
+    }
+}
+
 /// Any instance that is managed Python can have access to `gil`.
 pub trait PyObjectWithToken: Sized {
     fn py(&self) -> Python;
@@ -142,6 +142,10 @@ extern crate pyo3cls;
 extern crate spin;
 #[macro_use]
 extern crate log;
+#[cfg(feature = "serde-pickle")]
+extern crate serde;
+#[cfg(feature = "serde-pickle")]
+extern crate serde_json;
 
 #[cfg(not(Py_3))]
 mod ffi2;
@@ -162,6 +166,8 @@ pub use conversion::{FromPyObject, IntoPyObject, IntoPyTuple, PyTryFrom, PyTryIn
                      ToBorrowedObject, ToPyObject};
 pub use err::{PyDowncastError, PyErr, PyErrArguments, PyErrValue, PyResult};
 pub use instance::{AsPyRef, Py, PyNativeType, PyObjectWithToken, PyToken};
+#[cfg(Py_3)]
+pub use modulestate::PyModuleState;
 pub use noargs::NoArgs;
 pub use object::PyObject;
 pub use objectprotocol::ObjectProtocol;
@@ -174,7 +180,7 @@ pub use class::*;
 
 /// Procedural macros
 pub mod py {
-    pub use pyo3cls::{class, methods, proto};
+    pub use pyo3cls::{class, function, methods, proto, submodule};
 
     #[cfg(Py_3)]
     pub use pyo3cls::mod3init as modinit;
@@ -193,15 +199,59 @@ macro_rules! cstr(
     );
 );
 
+/// Implements `PyTypeInfo` for a Rust class defined by pyo3 itself,
+/// i.e. without the `#[class]` macro.
+macro_rules! pyobject_rusttype(
+    ($name: ident, $py_name: expr, $doc: expr) => (
+        impl $crate::typeob::PyTypeInfo for $name {
+            type Type = $name;
+            type BaseType = $crate::PyObjectRef;
+
+            const NAME: &'static str = $py_name;
+            const DESCRIPTION: &'static str = concat!($doc, "\0");
+
+            const SIZE: usize = $name::OFFSET as usize + ::std::mem::size_of::<$name>();
+            const OFFSET: isize = {
+                // round base_size up to next multiple of align
+                ((<$crate::PyObjectRef as $crate::typeob::PyTypeInfo>::SIZE
+                    + ::std::mem::align_of::<$name>() - 1)
+                    / ::std::mem::align_of::<$name>()
+                    * ::std::mem::align_of::<$name>()) as isize
+            };
+
+            #[inline]
+            unsafe fn type_object() -> &'static mut $crate::ffi::PyTypeObject {
+                match <Self as $crate::typeob::PyTypeInfo>::type_storage() {
+                    Some(storage) => storage.type_object(),
+                    None => unreachable!(),
+                }
+            }
+
+            #[inline]
+            unsafe fn type_storage() -> Option<&'static mut $crate::typeob::PyTypeStorage> {
+                static mut TYPE_OBJECT: $crate::typeob::PyTypeStorage =
+                    $crate::typeob::PyTypeStorage::INIT;
+                Some(&mut TYPE_OBJECT)
+            }
+        }
+    );
+);
+
 #[doc(hidden)]
 pub mod argparse;
+#[cfg(not(Py_LIMITED_API))]
 pub mod buffer;
+#[cfg(not(Py_LIMITED_API))]
+pub mod filelike;
 #[doc(hidden)]
 pub mod callback;
 mod conversion;
 mod err;
 pub mod freelist;
+pub mod function;
 mod instance;
+#[cfg(Py_3)]
+pub mod modulestate;
 mod noargs;
 mod object;
 mod objectprotocol;
@@ -209,7 +259,9 @@ mod objects;
 pub mod prelude;
 mod python;
 mod pythonrun;
+pub mod stubs;
 pub mod typeob;
+pub mod view;
 
 // re-export for simplicity
 #[doc(hidden)]
@@ -0,0 +1,434 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Per-module state of the multi-phase initialized modules (PEP 489)
+//!
+//! `#[modinit(name, state = "ModuleState")]` creates the module with `Py_mod_exec` slot,
+//! the state is allocated by python for every module object, so `importlib.reload()`
+//! and sub-interpreters get their own state. The state is created with `Default::default()`
+//! before the initialization function gets called and it is dropped with the module.
+//!
+//! ```rust,ignore
+//! #[derive(Default)]
+//! struct Counter {
+//!     count: usize,
+//!     callback: Option<PyObject>,
+//! }
+//!
+//! impl PyModuleState for Counter {
+//!     fn traverse(&self, visit: PyVisit) -> Result<(), PyTraverseError> {
+//!         if let Some(ref callback) = self.callback {
+//!             visit.call(callback)?;
+//!         }
+//!         Ok(())
+//!     }
+//!
+//!     fn clear(&mut self) {
+//!         self.callback = None;
+//!     }
+//! }
+//!
+//! #[modinit(counter, state = "Counter")]
+//! fn init(py: Python, m: &PyModule) -> PyResult<()> {
+//!     m.state_mut::<Counter>()?.count += 1;
+//!     Ok(())
+//! }
+//! ```
+//!
+//! The module state also owns the heap types of the `#[class(per_interpreter)]` classes,
+//! `PyModule::add_class()` creates the type for the interpreter of the module.
+//! `PyTypeInfo::type_object()` of such classes returns the type owned by the state of the module
+//! of the current interpreter the class is added to, `is_instance()` checks the type of the instance.
+//! The types are released with the module.
+
+use std::any::TypeId;
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::{Deref, DerefMut};
+use std::os::raw::{c_int, c_void};
+use std::ptr;
+use std::sync::{Once, ONCE_INIT};
+
+use spin;
+
+use class::gc::{PyTraverseError, PyVisit};
+use err::PyResult;
+use ffi;
+use instance::{Py, PyObjectWithToken};
+use objects::{exc, PyModule, PyType};
+use python::{Python, ToPyPointer};
+use typeob::{self, PyTypeInfo};
+
+/// State of the module, traversed and cleared by the garbage collector
+pub trait PyModuleState: Default + 'static {
+    /// Visits the python objects owned by the state
+    fn traverse(&self, _visit: PyVisit) -> Result<(), PyTraverseError> {
+        Ok(())
+    }
+
+    /// Drops the python objects owned by the state to break reference cycles
+    fn clear(&mut self) {}
+}
+
+/// State of the modules without state type, holds the per-interpreter classes only
+impl PyModuleState for () {}
+
+/// Memory allocated by python is zeroed, `initialized` is false until `init_state()`
+#[repr(C)]
+struct StateCell<T> {
+    initialized: bool,
+    type_id: TypeId,
+    /// Number of the `state()` borrows, `-1` while borrowed by `state_mut()`
+    borrow: isize,
+    /// Heap types of the per-interpreter classes keyed by `type_key()`,
+    /// placed before `value` so the offset does not depend on `T`
+    types: Vec<(usize, *mut ffi::PyObject)>,
+    value: T,
+}
+
+impl PyModule {
+    /// Borrows the state of the module created by `#[modinit(name, state = "T")]`
+    ///
+    /// Raises `TypeError` if the module does not have state of type `T`
+    /// and `RuntimeError` if the state is borrowed mutably.
+    pub fn state<T>(&self) -> PyResult<StateRef<T>>
+    where
+        T: PyModuleState,
+    {
+        let cell = self.typed_state_cell::<T>()?;
+        unsafe {
+            if (*cell).borrow < 0 {
+                return Err(exc::RuntimeError::new("module state is already borrowed mutably"));
+            }
+            (*cell).borrow += 1;
+        }
+        Ok(StateRef {
+            cell: cell,
+            _marker: PhantomData,
+        })
+    }
+
+    /// Borrows the state of the module mutably
+    ///
+    /// Raises `TypeError` if the module does not have state of type `T`
+    /// and `RuntimeError` if the state is borrowed already.
+    pub fn state_mut<T>(&self) -> PyResult<StateRefMut<T>>
+    where
+        T: PyModuleState,
+    {
+        let cell = self.typed_state_cell::<T>()?;
+        unsafe {
+            if (*cell).borrow != 0 {
+                return Err(exc::RuntimeError::new("module state is already borrowed"));
+            }
+            (*cell).borrow = -1;
+        }
+        Ok(StateRefMut {
+            cell: cell,
+            _marker: PhantomData,
+        })
+    }
+
+    /// The cell is only accessed through the pointer,
+    /// the value may be borrowed by `StateRef` guards
+    fn typed_state_cell<T: PyModuleState>(&self) -> PyResult<*mut StateCell<T>> {
+        match unsafe { state_cell::<T>(self.as_ptr()) } {
+            Some(cell) if unsafe { (*cell).type_id } == TypeId::of::<T>() => Ok(cell),
+            _ => Err(exc::TypeError::new(format!(
+                "module {} does not have state of the requested type",
+                self.name().unwrap_or("?")
+            ))),
+        }
+    }
+}
+
+/// Shared borrow of the module state, returned by `PyModule::state()`
+pub struct StateRef<'a, T: 'a> {
+    cell: *mut StateCell<T>,
+    _marker: PhantomData<&'a T>,
+}
+
+impl<'a, T> Deref for StateRef<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &(*self.cell).value }
+    }
+}
+
+impl<'a, T> Drop for StateRef<'a, T> {
+    fn drop(&mut self) {
+        unsafe { (*self.cell).borrow -= 1 }
+    }
+}
+
+/// Mutable borrow of the module state, returned by `PyModule::state_mut()`
+pub struct StateRefMut<'a, T: 'a> {
+    cell: *mut StateCell<T>,
+    _marker: PhantomData<&'a mut T>,
+}
+
+impl<'a, T> Deref for StateRefMut<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &(*self.cell).value }
+    }
+}
+
+impl<'a, T> DerefMut for StateRefMut<'a, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut (*self.cell).value }
+    }
+}
+
+impl<'a, T> Drop for StateRefMut<'a, T> {
+    fn drop(&mut self) {
+        unsafe { (*self.cell).borrow = 0 }
+    }
+}
+
+/// The initialized state cell, the fields are accessed separately
+/// because the value may be borrowed
+unsafe fn state_cell<T>(module: *mut ffi::PyObject) -> Option<*mut StateCell<T>> {
+    let cell = ffi::PyModule_GetState(module) as *mut StateCell<T>;
+    if cell.is_null() || !(*cell).initialized {
+        None
+    } else {
+        Some(cell)
+    }
+}
+
+/// `m_size` of the module definition
+#[doc(hidden)]
+pub fn state_size<T: PyModuleState>() -> ffi::Py_ssize_t {
+    mem::size_of::<StateCell<T>>() as ffi::Py_ssize_t
+}
+
+/// Creates the state, called by `Py_mod_exec` slot before the initialization function
+#[doc(hidden)]
+pub fn init_state<T: PyModuleState>(module: &PyModule) -> PyResult<()> {
+    unsafe {
+        let cell = ffi::PyModule_GetState(module.as_ptr()) as *mut StateCell<T>;
+        if cell.is_null() {
+            return Err(exc::SystemError::new("module state is not allocated"));
+        }
+        if (*cell).initialized {
+            if (*cell).borrow != 0 {
+                return Err(exc::RuntimeError::new("module state is borrowed"));
+            }
+            release_types(&mut (*cell).types);
+            ptr::drop_in_place(&mut (*cell).types);
+            ptr::drop_in_place(&mut (*cell).value);
+        }
+        ptr::write(
+            cell,
+            StateCell {
+                initialized: true,
+                type_id: TypeId::of::<T>(),
+                borrow: 0,
+                types: Vec::new(),
+                value: T::default(),
+            },
+        );
+    }
+    Ok(())
+}
+
+/// `m_traverse` of the module definition
+#[doc(hidden)]
+pub unsafe extern "C" fn traverse_state<T: PyModuleState>(
+    module: *mut ffi::PyObject,
+    visit: ffi::visitproc,
+    arg: *mut c_void,
+) -> c_int {
+    match state_cell::<T>(module) {
+        Some(cell) => {
+            for &(_, ty) in (*cell).types.iter() {
+                let code = visit(ty, arg);
+                if code != 0 {
+                    return code;
+                }
+            }
+            // the state is not visited while it is borrowed mutably,
+            // the objects are kept alive instead of being collected
+            if (*cell).borrow < 0 {
+                return 0;
+            }
+            let visit = PyVisit {
+                visit,
+                arg,
+                _py: Python::assume_gil_acquired(),
+            };
+            match (*cell).value.traverse(visit) {
+                Ok(()) => 0,
+                Err(PyTraverseError(code)) => code,
+            }
+        }
+        None => 0,
+    }
+}
+
+/// `m_clear` of the module definition
+#[doc(hidden)]
+pub unsafe extern "C" fn clear_state<T: PyModuleState>(module: *mut ffi::PyObject) -> c_int {
+    if let Some(cell) = state_cell::<T>(module) {
+        release_types(&mut (*cell).types);
+        // the borrowed state is not cleared, the reference cycle is kept
+        if (*cell).borrow == 0 {
+            (*cell).value.clear();
+        }
+    }
+    0
+}
+
+/// `m_free` of the module definition, drops the state
+#[doc(hidden)]
+pub unsafe extern "C" fn free_state<T: PyModuleState>(module: *mut c_void) {
+    class_modules()
+        .lock()
+        .retain(|entry| entry.module != module as usize);
+    if let Some(cell) = state_cell::<T>(module as *mut ffi::PyObject) {
+        (*cell).initialized = false;
+        release_types(&mut (*cell).types);
+        ptr::drop_in_place(&mut (*cell).types);
+        ptr::drop_in_place(&mut (*cell).value);
+    }
+}
+
+unsafe fn release_types(types: &mut Vec<(usize, *mut ffi::PyObject)>) {
+    for (_, ty) in types.drain(..) {
+        ffi::Py_DECREF(ty);
+    }
+}
+
+/// The heap types of the module, the layout of the state prefix does not depend on `T`
+unsafe fn module_types<'a>(
+    module: *mut ffi::PyObject,
+) -> Option<&'a mut Vec<(usize, *mut ffi::PyObject)>> {
+    state_cell::<()>(module).map(|cell| &mut (*cell).types)
+}
+
+/// The address of the type object storage identifies the class
+fn type_key<T: PyTypeInfo>() -> PyResult<usize> {
+    match unsafe { T::type_storage() } {
+        Some(storage) => Ok(storage as *mut typeob::PyTypeStorage as usize),
+        None => Err(exc::TypeError::new(format!(
+            "{}: the type object storage is required",
+            T::NAME
+        ))),
+    }
+}
+
+/// Creates the heap type of the per-interpreter class for the interpreter of the module
+pub(crate) fn add_interpreter_class<T>(module: &PyModule) -> PyResult<()>
+where
+    T: PyTypeInfo,
+{
+    let py = module.py();
+    let key = type_key::<T>()?;
+    let name = module.name()?;
+    let module_ptr = module.as_ptr() as usize;
+
+    let ty = unsafe {
+        let types = match module_types(module.as_ptr()) {
+            Some(types) => types,
+            None => {
+                return Err(exc::TypeError::new(format!(
+                    "{}: per-interpreter class requires a module created by \
+                     #[modinit(name, multi_phase)]",
+                    T::NAME
+                )))
+            }
+        };
+        let ty = typeob::create_interpreter_type::<T>(py, name)?;
+        // the module state owns the type
+        match types.iter().position(|&(k, _)| k == key) {
+            Some(idx) => ffi::Py_DECREF(mem::replace(&mut types[idx].1, ty)),
+            None => types.push((key, ty)),
+        }
+        ty
+    };
+
+    {
+        let interpreter = current_interpreter();
+        let mut modules = class_modules().lock();
+        modules.retain(|entry| entry.key != key || entry.module != module_ptr);
+        // the type of the latest module takes precedence
+        modules.push(ClassModule {
+            interpreter,
+            key,
+            module: module_ptr,
+        });
+    }
+
+    // class attributes, the type is reachable already,
+    // so a class attribute can be an instance of the class
+    typeob::py_class_attributes::<T>(py, ty as *mut ffi::PyTypeObject)?;
+
+    let ty: Py<PyType> = unsafe { Py::from_borrowed_ptr(ty) };
+    ::stubs::register_class::<T>(module)?;
+    module.add(T::NAME, ty)
+}
+
+/// The heap type of the per-interpreter class for the current interpreter,
+/// owned by the state of the latest module the class is added to.
+///
+/// The type is valid while the module state owns it.
+pub(crate) fn interpreter_type_object<T: PyTypeInfo>() -> PyResult<*mut ffi::PyTypeObject> {
+    let key = type_key::<T>()?;
+    let interpreter = current_interpreter();
+
+    let modules = class_modules().lock();
+    let entries = modules
+        .iter()
+        .rev()
+        .filter(|entry| entry.interpreter == interpreter && entry.key == key);
+    for entry in entries {
+        // `m_clear` of the module releases the types
+        let types = unsafe { module_types(entry.module as *mut ffi::PyObject) };
+        if let Some(&(_, ty)) = types.and_then(|types| types.iter().find(|&&(k, _)| k == key)) {
+            return Ok(ty as *mut ffi::PyTypeObject);
+        }
+    }
+    Err(exc::TypeError::new(format!(
+        "{}: per-interpreter class is not added to a module of the current interpreter",
+        T::NAME
+    )))
+}
+
+/// The module holding the heap type of a per-interpreter class
+struct ClassModule {
+    /// Identifies the interpreter of the module, see `current_interpreter()`
+    interpreter: i64,
+    key: usize,
+    module: usize,
+}
+
+/// The ID of the interpreter of the current thread, the IDs are not reused.
+/// Python 3.5 and 3.6 have no interpreter IDs, the address of the interpreter state is used.
+fn current_interpreter() -> i64 {
+    unsafe {
+        #[cfg(Py_3_9)]
+        let interp = ffi::PyInterpreterState_Get();
+        #[cfg(not(Py_3_9))]
+        let interp = (*ffi::PyThreadState_Get()).interp;
+
+        #[cfg(Py_3_7)]
+        return ffi::PyInterpreterState_GetID(interp);
+        #[cfg(not(Py_3_7))]
+        return interp as i64;
+    }
+}
+
+/// The modules holding the heap types of the per-interpreter classes,
+/// the entries are removed by `m_free` of the modules
+fn class_modules() -> &'static spin::Mutex<Vec<ClassModule>> {
+    static INIT: Once = ONCE_INIT;
+    static mut REGISTRY: *mut spin::Mutex<Vec<ClassModule>> = ptr::null_mut();
+    unsafe {
+        INIT.call_once(|| {
+            REGISTRY = Box::into_raw(Box::new(spin::Mutex::new(Vec::new())));
+        });
+        &*REGISTRY
+    }
+}
@@ -1,12 +1,22 @@
 // Copyright (c) 2017-present PyO3 Project and Contributors
 
+#[cfg(not(Py_LIMITED_API))]
+use err;
 use err::{PyErr, PyResult};
+#[cfg(not(Py_LIMITED_API))]
+use exc;
 use ffi;
+#[cfg(not(Py_LIMITED_API))]
+use instance::Py;
 use instance::PyObjectWithToken;
 use object::PyObject;
 use python::{Python, ToPyPointer};
 use std;
+#[cfg(not(Py_LIMITED_API))]
+use std::mem;
 use std::os::raw::c_char;
+#[cfg(not(Py_LIMITED_API))]
+use std::os::raw::c_int;
 
 /// Represents a Python `bytearray`.
 pub struct PyByteArray(PyObject);
@@ -47,6 +57,11 @@ impl PyByteArray {
     }
 
     /// Gets the Python bytearray data as byte slice.
+    ///
+    /// The slice is not borrow-checked: it is invalidated as soon as the bytearray
+    /// gets resized, and it aliases every other reference to the same bytearray,
+    /// including python code writing to it. `with_data()`, `with_data_mut()` and
+    /// `to_vec()` are checked.
     #[cfg_attr(feature = "cargo-clippy", allow(mut_from_ref))]
     pub fn data(&self) -> &mut [u8] {
         unsafe {
@@ -56,6 +71,65 @@ impl PyByteArray {
         }
     }
 
+    /// Calls `f` with the bytearray data.
+    ///
+    /// Raises `BufferError` if the data is already exported, i.e. by a `memoryview`
+    /// which can write to it or by `with_data_mut`. The data is exported for
+    /// the duration of the call, so any attempt to resize or to borrow
+    /// the bytearray mutably raises `BufferError`.
+    #[cfg(not(Py_LIMITED_API))]
+    pub fn with_data<F, R>(&self, f: F) -> PyResult<R>
+    where
+        F: FnOnce(&[u8]) -> R,
+    {
+        let export = Export::new(self, ffi::PyBUF_SIMPLE)?;
+        Ok(f(export.as_slice()))
+    }
+
+    /// Calls `f` with the mutable bytearray data.
+    ///
+    /// Raises `BufferError` if the data is already exported, i.e. by a `memoryview`.
+    /// The data is exported for the duration of the call, so any attempt
+    /// to resize or to borrow the bytearray raises `BufferError`.
+    #[cfg(not(Py_LIMITED_API))]
+    pub fn with_data_mut<F, R>(&self, f: F) -> PyResult<R>
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        let mut export = Export::new(self, ffi::PyBUF_WRITABLE)?;
+        Ok(f(export.as_mut_slice()))
+    }
+
+    /// Copies the bytearray data into a new `Vec<u8>`.
+    ///
+    /// Raises `BufferError` if the data is borrowed mutably by `with_data_mut`.
+    #[cfg(not(Py_LIMITED_API))]
+    pub fn to_vec(&self) -> PyResult<Vec<u8>> {
+        self.with_data(|data| data.to_vec())
+    }
+
+    /// Copies the bytearray data into a new `Vec<u8>`.
+    #[cfg(Py_LIMITED_API)]
+    pub fn to_vec(&self) -> PyResult<Vec<u8>> {
+        Ok(self.data().to_vec())
+    }
+
+    /// Appends all bytes of `other` to the bytearray.
+    ///
+    /// Raises `BufferError` if the data is exported.
+    pub fn extend_from_slice(&self, other: &[u8]) -> PyResult<()> {
+        let len = self.len();
+        self.resize(len + other.len())?;
+        self.data()[len..].copy_from_slice(other);
+        Ok(())
+    }
+
+    /// Number of active buffer exports of the bytearray data.
+    #[cfg(not(Py_LIMITED_API))]
+    fn exports(&self) -> c_int {
+        unsafe { (*(self.0.as_ptr() as *mut ffi::PyByteArrayObject)).ob_exports }
+    }
+
     /// Resize bytearray object.
     pub fn resize(&self, len: usize) -> PyResult<()> {
         unsafe {
@@ -69,10 +143,62 @@ impl PyByteArray {
     }
 }
 
+/// Borrow guard of the bytearray data, the data stays exported until dropped,
+/// which prevents resizing and other borrows.
+///
+/// The guard holds a reference to the bytearray, the exported buffer
+/// can not be released or reallocated while it is alive.
+#[cfg(not(Py_LIMITED_API))]
+struct Export {
+    _array: Py<PyByteArray>,
+    view: Box<ffi::Py_buffer>,
+}
+
+#[cfg(not(Py_LIMITED_API))]
+impl Export {
+    fn new(ob: &PyByteArray, flags: c_int) -> PyResult<Export> {
+        // every export can be written to, i.e. by a memoryview
+        if ob.exports() != 0 {
+            return Err(PyErr::new::<exc::BufferError, _>(
+                "Existing exports of data: bytearray can not be borrowed",
+            ));
+        }
+        unsafe {
+            // Python expects the Py_buffer struct to have a stable memory address
+            let mut view = Box::new(mem::zeroed::<ffi::Py_buffer>());
+            err::error_on_minusone(
+                ob.py(),
+                ffi::PyObject_GetBuffer(ob.as_ptr(), &mut *view, flags),
+            )?;
+            Ok(Export {
+                _array: Py::from_borrowed_ptr(ob.as_ptr()),
+                view,
+            })
+        }
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.view.buf as *const u8, self.view.len as usize) }
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        unsafe { std::slice::from_raw_parts_mut(self.view.buf as *mut u8, self.view.len as usize) }
+    }
+}
+
+#[cfg(not(Py_LIMITED_API))]
+impl Drop for Export {
+    fn drop(&mut self) {
+        unsafe { ffi::PyBuffer_Release(&mut *self.view) }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use exc;
     use object::PyObject;
+    #[cfg(not(Py_LIMITED_API))]
+    use objectprotocol::ObjectProtocol;
     use objects::PyByteArray;
     use python::Python;
 
@@ -84,13 +210,13 @@ mod test {
         let src = b"Hello Python";
         let bytearray = PyByteArray::new(py, src);
         assert_eq!(src.len(), bytearray.len());
-        assert_eq!(src, bytearray.data());
+        assert_eq!(&src[..], &bytearray.to_vec().unwrap()[..]);
 
         let ba: PyObject = bytearray.into();
         let bytearray = PyByteArray::from(py, &ba).unwrap();
 
         assert_eq!(src.len(), bytearray.len());
-        assert_eq!(src, bytearray.data());
+        assert_eq!(&src[..], &bytearray.to_vec().unwrap()[..]);
 
         bytearray.resize(20).unwrap();
         assert_eq!(20, bytearray.len());
@@ -103,4 +229,65 @@ mod test {
         }
         drop(none);
     }
+
+    #[test]
+    #[cfg(not(Py_LIMITED_API))]
+    fn test_bytearray_with_data() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let bytearray = PyByteArray::new(py, b"Hello");
+        let len = bytearray.with_data(|data| data.len()).unwrap();
+        assert_eq!(5, len);
+
+        bytearray
+            .with_data_mut(|data| data[0] = b'J')
+            .unwrap();
+        assert_eq!(b"Jello".to_vec(), bytearray.to_vec().unwrap());
+
+        // exported data can not be resized
+        let err = bytearray
+            .with_data(|_| bytearray.resize(10).unwrap_err())
+            .unwrap();
+        assert!(err.is_instance::<exc::BufferError>(py));
+        assert_eq!(5, bytearray.len());
+
+        // exported data can not be borrowed again
+        let errs = bytearray
+            .with_data(|_| {
+                (
+                    bytearray.with_data_mut(|_| ()).unwrap_err(),
+                    bytearray.with_data(|_| ()).unwrap_err(),
+                )
+            })
+            .unwrap();
+        assert!(errs.0.is_instance::<exc::BufferError>(py));
+        assert!(errs.1.is_instance::<exc::BufferError>(py));
+
+        // the data exported to a memoryview can be written by python
+        let view = py.eval("memoryview", None, None)
+            .unwrap()
+            .call1((bytearray,))
+            .unwrap();
+        assert!(bytearray.with_data(|_| ()).unwrap_err().is_instance::<exc::BufferError>(py));
+        view.call_method0("release").unwrap();
+
+        // mutably borrowed data can not be borrowed again
+        let errs = bytearray
+            .with_data_mut(|_| {
+                (
+                    bytearray.with_data(|_| ()).unwrap_err(),
+                    bytearray.with_data_mut(|_| ()).unwrap_err(),
+                    bytearray.to_vec().unwrap_err(),
+                )
+            })
+            .unwrap();
+        assert!(errs.0.is_instance::<exc::BufferError>(py));
+        assert!(errs.1.is_instance::<exc::BufferError>(py));
+        assert!(errs.2.is_instance::<exc::BufferError>(py));
+        assert_eq!(b"Jello".to_vec(), bytearray.to_vec().unwrap());
+
+        bytearray.extend_from_slice(b" world").unwrap();
+        assert_eq!(b"Jello world".to_vec(), bytearray.to_vec().unwrap());
+    }
 }
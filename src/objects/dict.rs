@@ -15,6 +15,9 @@ use python::{IntoPyDictPointer, IntoPyPointer, Python, ToPyPointer};
 pub struct PyDict(PyObject);
 
 pyobject_convert!(PyDict);
+#[cfg(not(Py_LIMITED_API))]
+pyobject_nativetype!(PyDict, PyDict_Type, PyDict_Check, PyDictObject);
+#[cfg(Py_LIMITED_API)]
 pyobject_nativetype!(PyDict, PyDict_Type, PyDict_Check);
 
 impl PyDict {
@@ -12,10 +12,13 @@ use ffi;
 use instance::Py;
 use objects::{PyObjectRef, PyTuple, PyType};
 use python::{Python, ToPyPointer};
-use typeob::PyTypeObject;
+use typeob::{PyTypeInfo, PyTypeObject};
 
 macro_rules! exc_type(
     ($name:ident, $exc_name:ident) => (
+        exc_type!($name, $exc_name, PyBaseExceptionObject);
+    );
+    ($name:ident, $exc_name:ident, $layout:ident) => (
         pub struct $name;
 
         impl std::convert::From<$name> for PyErr {
@@ -37,9 +40,34 @@ macro_rules! exc_type(
                 PyErr::new::<$name, V>(args).into()
             }
         }
+        impl PyTypeInfo for $name {
+            type Type = ();
+            type BaseType = PyObjectRef;
+
+            const NAME: &'static str = stringify!($name);
+            // the layout of the exception objects is not a part of the limited API
+            #[cfg(not(Py_LIMITED_API))]
+            const SIZE: usize = mem::size_of::<ffi::$layout>();
+            #[cfg(Py_LIMITED_API)]
+            const SIZE: usize = mem::size_of::<ffi::PyObject>();
+            const OFFSET: isize = 0;
+
+            #[inline]
+            unsafe fn type_object() -> &'static mut ffi::PyTypeObject {
+                &mut *(ffi::$exc_name as *mut ffi::PyTypeObject)
+            }
+
+            #[inline]
+            unsafe fn native_base_type() -> *mut ffi::PyTypeObject {
+                ffi::$exc_name as *mut ffi::PyTypeObject
+            }
+        }
+
         impl PyTypeObject for $name {
             #[inline(always)]
-            fn init_type() {}
+            fn init_type() -> PyResult<()> {
+                Ok(())
+            }
 
             #[inline]
             fn type_object() -> Py<PyType> {
@@ -55,7 +83,7 @@ exc_type!(BaseException, PyExc_BaseException);
 exc_type!(Exception, PyExc_Exception);
 #[cfg(Py_3)]
 exc_type!(StopAsyncIteration, PyExc_StopAsyncIteration);
-exc_type!(StopIteration, PyExc_StopIteration);
+exc_type!(StopIteration, PyExc_StopIteration, PyStopIterationObject);
 exc_type!(GeneratorExit, PyExc_GeneratorExit);
 exc_type!(ArithmeticError, PyExc_ArithmeticError);
 exc_type!(LookupError, PyExc_LookupError);
@@ -65,11 +93,11 @@ exc_type!(AttributeError, PyExc_AttributeError);
 exc_type!(BufferError, PyExc_BufferError);
 exc_type!(EOFError, PyExc_EOFError);
 exc_type!(FloatingPointError, PyExc_FloatingPointError);
-exc_type!(OSError, PyExc_OSError);
-exc_type!(ImportError, PyExc_ImportError);
+exc_type!(OSError, PyExc_OSError, PyOSErrorObject);
+exc_type!(ImportError, PyExc_ImportError, PyImportErrorObject);
 
 #[cfg(Py_3_6)]
-exc_type!(ModuleNotFoundError, PyExc_ModuleNotFoundError);
+exc_type!(ModuleNotFoundError, PyExc_ModuleNotFoundError, PyImportErrorObject);
 
 exc_type!(IndexError, PyExc_IndexError);
 exc_type!(KeyError, PyExc_KeyError);
@@ -81,54 +109,54 @@ exc_type!(RuntimeError, PyExc_RuntimeError);
 #[cfg(Py_3)]
 exc_type!(RecursionError, PyExc_RecursionError);
 exc_type!(NotImplementedError, PyExc_NotImplementedError);
-exc_type!(SyntaxError, PyExc_SyntaxError);
+exc_type!(SyntaxError, PyExc_SyntaxError, PySyntaxErrorObject);
 exc_type!(ReferenceError, PyExc_ReferenceError);
 exc_type!(SystemError, PyExc_SystemError);
-exc_type!(SystemExit, PyExc_SystemExit);
+exc_type!(SystemExit, PyExc_SystemExit, PySystemExitObject);
 exc_type!(TypeError, PyExc_TypeError);
 exc_type!(UnboundLocalError, PyExc_UnboundLocalError);
-exc_type!(UnicodeError, PyExc_UnicodeError);
-exc_type!(UnicodeDecodeError, PyExc_UnicodeDecodeError);
-exc_type!(UnicodeEncodeError, PyExc_UnicodeEncodeError);
-exc_type!(UnicodeTranslateError, PyExc_UnicodeTranslateError);
+exc_type!(UnicodeError, PyExc_UnicodeError, PyUnicodeErrorObject);
+exc_type!(UnicodeDecodeError, PyExc_UnicodeDecodeError, PyUnicodeErrorObject);
+exc_type!(UnicodeEncodeError, PyExc_UnicodeEncodeError, PyUnicodeErrorObject);
+exc_type!(UnicodeTranslateError, PyExc_UnicodeTranslateError, PyUnicodeErrorObject);
 exc_type!(ValueError, PyExc_ValueError);
 exc_type!(ZeroDivisionError, PyExc_ZeroDivisionError);
 
 #[cfg(Py_3)]
-exc_type!(BlockingIOError, PyExc_BlockingIOError);
+exc_type!(BlockingIOError, PyExc_BlockingIOError, PyOSErrorObject);
 #[cfg(Py_3)]
-exc_type!(BrokenPipeError, PyExc_BrokenPipeError);
+exc_type!(BrokenPipeError, PyExc_BrokenPipeError, PyOSErrorObject);
 #[cfg(Py_3)]
-exc_type!(ChildProcessError, PyExc_ChildProcessError);
+exc_type!(ChildProcessError, PyExc_ChildProcessError, PyOSErrorObject);
 #[cfg(Py_3)]
-exc_type!(ConnectionError, PyExc_ConnectionError);
+exc_type!(ConnectionError, PyExc_ConnectionError, PyOSErrorObject);
 #[cfg(Py_3)]
-exc_type!(ConnectionAbortedError, PyExc_ConnectionAbortedError);
+exc_type!(ConnectionAbortedError, PyExc_ConnectionAbortedError, PyOSErrorObject);
 #[cfg(Py_3)]
-exc_type!(ConnectionRefusedError, PyExc_ConnectionRefusedError);
+exc_type!(ConnectionRefusedError, PyExc_ConnectionRefusedError, PyOSErrorObject);
 #[cfg(Py_3)]
-exc_type!(ConnectionResetError, PyExc_ConnectionResetError);
+exc_type!(ConnectionResetError, PyExc_ConnectionResetError, PyOSErrorObject);
 #[cfg(Py_3)]
-exc_type!(FileExistsError, PyExc_FileExistsError);
+exc_type!(FileExistsError, PyExc_FileExistsError, PyOSErrorObject);
 #[cfg(Py_3)]
-exc_type!(FileNotFoundError, PyExc_FileNotFoundError);
+exc_type!(FileNotFoundError, PyExc_FileNotFoundError, PyOSErrorObject);
 #[cfg(Py_3)]
-exc_type!(InterruptedError, PyExc_InterruptedError);
+exc_type!(InterruptedError, PyExc_InterruptedError, PyOSErrorObject);
 #[cfg(Py_3)]
-exc_type!(IsADirectoryError, PyExc_IsADirectoryError);
+exc_type!(IsADirectoryError, PyExc_IsADirectoryError, PyOSErrorObject);
 #[cfg(Py_3)]
-exc_type!(NotADirectoryError, PyExc_NotADirectoryError);
+exc_type!(NotADirectoryError, PyExc_NotADirectoryError, PyOSErrorObject);
 #[cfg(Py_3)]
-exc_type!(PermissionError, PyExc_PermissionError);
+exc_type!(PermissionError, PyExc_PermissionError, PyOSErrorObject);
 #[cfg(Py_3)]
-exc_type!(ProcessLookupError, PyExc_ProcessLookupError);
+exc_type!(ProcessLookupError, PyExc_ProcessLookupError, PyOSErrorObject);
 #[cfg(Py_3)]
-exc_type!(TimeoutError, PyExc_TimeoutError);
+exc_type!(TimeoutError, PyExc_TimeoutError, PyOSErrorObject);
 
-exc_type!(EnvironmentError, PyExc_EnvironmentError);
-exc_type!(IOError, PyExc_IOError);
+exc_type!(EnvironmentError, PyExc_EnvironmentError, PyEnvironmentErrorObject);
+exc_type!(IOError, PyExc_IOError, PyEnvironmentErrorObject);
 #[cfg(target_os = "windows")]
-exc_type!(WindowsError, PyExc_WindowsError);
+exc_type!(WindowsError, PyExc_WindowsError, PyWindowsErrorObject);
 
 impl UnicodeDecodeError {
     #[cfg_attr(feature = "cargo-clippy", allow(needless_pass_by_value))]
@@ -72,7 +72,9 @@ macro_rules! import_exception {
 
         impl $crate::typeob::PyTypeObject for $name {
             #[inline(always)]
-            fn init_type() {}
+            fn init_type() -> $crate::PyResult<()> {
+                Ok(())
+            }
 
             #[inline]
             fn type_object() -> $crate::Py<$crate::PyType> {
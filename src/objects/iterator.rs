@@ -23,7 +23,7 @@ impl<'p> PyIterator<'p> {
         unsafe {
             let ptr = ffi::PyObject_GetIter(obj.as_ptr());
 
-            if ffi::PyIter_Check(ptr) != 0 {
+            if is_iterator(ptr) {
                 // this is not right, but this cause of segfault check #71
                 Ok(PyIterator(py.from_borrowed_ptr(ptr)))
             } else {
@@ -33,6 +33,18 @@ impl<'p> PyIterator<'p> {
     }
 }
 
+#[cfg(not(Py_LIMITED_API))]
+unsafe fn is_iterator(ptr: *mut ffi::PyObject) -> bool {
+    ffi::PyIter_Check(ptr) != 0
+}
+
+/// `PyObject_GetIter` checks the result itself, `PyIter_Check` is not a part
+/// of the limited API
+#[cfg(Py_LIMITED_API)]
+unsafe fn is_iterator(ptr: *mut ffi::PyObject) -> bool {
+    !ptr.is_null()
+}
+
 impl<'p> Iterator for PyIterator<'p> {
     type Item = PyResult<&'p PyObjectRef>;
 
@@ -16,6 +16,9 @@ use python::{IntoPyPointer, Python, ToPyPointer};
 pub struct PyList(PyObject);
 
 pyobject_convert!(PyList);
+#[cfg(not(Py_LIMITED_API))]
+pyobject_nativetype!(PyList, PyList_Type, PyList_Check, PyListObject);
+#[cfg(Py_LIMITED_API)]
 pyobject_nativetype!(PyList, PyList_Type, PyList_Check);
 
 impl PyList {
@@ -9,10 +9,10 @@ pub use self::dict::PyDict;
 pub use self::floatob::PyFloat;
 pub use self::iterator::PyIterator;
 pub use self::list::PyList;
-pub use self::module::PyModule;
+pub use self::module::{ExportKind, ModuleExport, PyModule};
 pub use self::sequence::PySequence;
 pub use self::set::{PyFrozenSet, PySet};
-pub use self::slice::{PySlice, PySliceIndices};
+pub use self::slice::{PySlice, PySliceIndices, SliceOrIndex};
 pub use self::stringdata::PyStringData;
 pub use self::tuple::PyTuple;
 pub use self::typeobject::PyType;
@@ -20,6 +20,9 @@ pub use self::typeobject::PyType;
 #[cfg(Py_3)]
 pub use self::string::{PyBytes, PyString};
 
+#[cfg(Py_3)]
+pub use self::structseq::{PyStructSequenceField, PyStructSequenceType};
+
 #[cfg(not(Py_3))]
 pub use self::string2::{PyBytes, PyString};
 
@@ -93,6 +96,10 @@ macro_rules! pyobject_nativetype(
     };
 
     ($name: ident, $typeobject: ident, $checkfunction: ident) => {
+        pyobject_nativetype!($name, $typeobject, $checkfunction, PyObject);
+    };
+
+    ($name: ident, $typeobject: ident, $checkfunction: ident, $layout: ident) => {
         pyobject_nativetype!($name);
 
         impl $crate::typeob::PyTypeInfo for $name {
@@ -100,7 +107,7 @@ macro_rules! pyobject_nativetype(
             type BaseType = $crate::PyObjectRef;
 
             const NAME: &'static str = stringify!($name);
-            const SIZE: usize = $crate::std::mem::size_of::<$crate::ffi::PyObject>();
+            const SIZE: usize = $crate::std::mem::size_of::<$crate::ffi::$layout>();
             const OFFSET: isize = 0;
 
             #[inline]
@@ -108,6 +115,11 @@ macro_rules! pyobject_nativetype(
                 &mut $crate::ffi::$typeobject
             }
 
+            #[inline]
+            unsafe fn native_base_type() -> *mut $crate::ffi::PyTypeObject {
+                &mut $crate::ffi::$typeobject
+            }
+
             #[cfg_attr(feature = "cargo-clippy", allow(not_unsafe_ptr_arg_deref))]
             fn is_instance(ptr: *mut $crate::ffi::PyObject) -> bool {
                 #[allow(unused_unsafe)]
@@ -117,7 +129,9 @@ macro_rules! pyobject_nativetype(
 
         impl $crate::typeob::PyTypeObject for $name {
             #[inline(always)]
-            fn init_type() {}
+            fn init_type() -> $crate::PyResult<()> {
+                Ok(())
+            }
 
             #[inline]
             fn type_object() -> $crate::Py<$crate::PyType> {
@@ -226,5 +240,8 @@ mod num2;
 #[cfg(Py_3)]
 mod string;
 
+#[cfg(Py_3)]
+mod structseq;
+
 #[cfg(not(Py_3))]
 mod string2;
@@ -4,21 +4,47 @@
 
 use std;
 use std::ffi::{CStr, CString};
-use std::os::raw::c_char;
+use std::os::raw::{c_char, c_void};
+use std::ptr;
+use std::sync::{Once, ONCE_INIT};
 
-use conversion::{IntoPyTuple, ToPyObject};
+use callback::{cb_convert, PyObjectCallbackConverter};
+use class::methods::{PyMethodDef, PyMethodType};
+use conversion::{IntoPyObject, IntoPyTuple, PyTryFrom, ToPyObject};
 use err::{PyErr, PyResult};
 use ffi;
-use instance::PyObjectWithToken;
+use function::PyFunctionDef;
+use instance::{Py, PyObjectWithToken};
 use object::PyObject;
 use objectprotocol::ObjectProtocol;
-use objects::{exc, PyDict, PyObjectRef, PyType};
+use objects::{exc, PyDict, PyList, PyObjectRef, PySequence, PyString, PyTuple, PyType};
 use python::{IntoPyDictPointer, Python, ToPyPointer};
+use pythonrun::GILPool;
 use typeob::{initialize_type, PyTypeInfo};
 
 /// Represents a Python `module` object.
 pub struct PyModule(PyObject);
 
+/// Kind of the module member listed by `PyModule::exports()`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportKind {
+    Function,
+    Class,
+    Module,
+    Constant,
+    /// Attribute added with `PyModule::add_lazy()` which is not initialized yet
+    Lazy,
+}
+
+/// Member of the module listed in `__all__`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleExport {
+    pub name: String,
+    pub kind: ExportKind,
+    /// `__doc__` of the functions, classes and modules
+    pub doc: Option<String>,
+}
+
 pyobject_convert!(PyModule);
 pyobject_nativetype!(PyModule, PyModule_Type, PyModule_Check);
 
@@ -108,36 +134,381 @@ impl PyModule {
     /// Adds a member to the module.
     ///
     /// This is a convenience function which can be used from the module's initialization function.
+    /// The name is appended to `__all__` of the module unless it starts with an underscore,
+    /// use `setattr()` for the members which should not be exported.
     pub fn add<V>(&self, name: &str, value: V) -> PyResult<()>
     where
         V: ToPyObject,
     {
-        self.setattr(name, value)
+        self.setattr(name, value)?;
+        self.export(name)
+    }
+
+    /// Appends the name to `__all__` of the module
+    fn export(&self, name: &str) -> PyResult<()> {
+        if name.starts_with('_') {
+            return Ok(());
+        }
+        let dict = self.dict();
+        let all = match dict.get_item("__all__") {
+            Some(all) => match <PyList as PyTryFrom>::try_from(all) {
+                Ok(all) => all,
+                Err(_) => {
+                    // a tuple or other sequence is replaced by a list
+                    let all = <PySequence as PyTryFrom>::try_from(all)?.list()?;
+                    dict.set_item("__all__", all)?;
+                    all
+                }
+            },
+            None => {
+                let all = PyList::empty(self.py());
+                dict.set_item("__all__", all)?;
+                all
+            }
+        };
+        for item in all.iter() {
+            if item.extract::<&str>()? == name {
+                return Ok(());
+            }
+        }
+        all.append(name)
+    }
+
+    /// Lists the members of the module in `__all__` with their kinds and docs.
+    ///
+    /// `add()`, `add_function()`, `add_class()`, `add_submodule()`, `add_lazy()` and `#[pyfn]`
+    /// maintain `__all__`, `#[pyfn(m, "name", hidden)]` functions are not listed.
+    pub fn exports(&self) -> PyResult<Vec<ModuleExport>> {
+        let dict = self.dict();
+        let all = match dict.get_item("__all__") {
+            Some(all) => <PySequence as PyTryFrom>::try_from(all)?.list()?,
+            None => return Ok(Vec::new()),
+        };
+        let lazy = match self.lazy_state() {
+            Some(state) => Some(state.get_item(1).extract::<&PyDict>()?),
+            None => None,
+        };
+
+        let mut exports = Vec::new();
+        for name in all.iter() {
+            let name = name.extract::<String>()?;
+            let value = match dict.get_item(name.as_str()) {
+                Some(value) => value,
+                None => {
+                    if let Some(lazy) = lazy {
+                        if lazy.contains(name.as_str())? {
+                            exports.push(ModuleExport {
+                                name,
+                                kind: ExportKind::Lazy,
+                                doc: None,
+                            });
+                        }
+                    }
+                    continue;
+                }
+            };
+            let kind = if <PyType as PyTypeInfo>::is_instance(value.as_ptr()) {
+                ExportKind::Class
+            } else if <PyModule as PyTypeInfo>::is_instance(value.as_ptr()) {
+                ExportKind::Module
+            } else {
+                match value.get_type().name().as_ref() {
+                    "builtin_function_or_method" | "function" => ExportKind::Function,
+                    _ => ExportKind::Constant,
+                }
+            };
+            let doc = match kind {
+                ExportKind::Constant => None,
+                _ => value.getattr("__doc__")?.extract::<Option<String>>()?,
+            };
+            exports.push(ModuleExport { name, kind, doc });
+        }
+        Ok(exports)
+    }
+
+    /// Adds a lazily initialized member to the module.
+    ///
+    /// `init` is called on the first access of the attribute, the value is cached
+    /// in the module dict, so the following lookups are plain attribute lookups.
+    /// The module gets `__getattr__` and `__dir__` functions (PEP 562), the functions set before
+    /// handle the other attributes. On Python 3.5 and 3.6
+    /// the class of the module is replaced with a `ModuleType` subclass calling them.
+    /// Python 2 modules can not change their class, `init` is called immediately.
+    ///
+    /// ```rust,ignore
+    /// m.add_lazy("table", |py| Ok(build_table(py)))?;
+    /// ```
+    pub fn add_lazy<F, V>(&self, name: &str, init: F) -> PyResult<()>
+    where
+        F: Fn(Python) -> PyResult<V> + Send + 'static,
+        V: IntoPyObject,
+    {
+        if cfg!(not(Py_3)) {
+            let value = init(self.py())?;
+            return self.add(name, value.into_object(self.py()));
+        }
+
+        let init: Box<LazyInit> = Box::new(move |py| init(py).map(|v| v.into_object(py)));
+        let capsule = unsafe {
+            let ptr = Box::into_raw(Box::new(init)) as *mut c_void;
+            let capsule = ffi::PyCapsule_New(ptr, ptr::null(), Some(drop_lazy_init));
+            if capsule.is_null() {
+                drop(Box::from_raw(ptr as *mut Box<LazyInit>));
+            }
+            PyObject::from_owned_ptr_or_err(self.py(), capsule)?
+        };
+        self.lazy_attrs()?.set_item(name, capsule)?;
+        self.export(name)
+    }
+
+    /// Initializers of the lazy attributes, `__getattr__` and `__dir__`
+    /// are installed when the first lazy attribute is added, the functions
+    /// defined before are called for the other attributes
+    fn lazy_attrs(&self) -> PyResult<&PyDict> {
+        if let Some(state) = self.lazy_state() {
+            return state.get_item(1).extract::<&PyDict>();
+        }
+
+        let py = self.py();
+        let dict = self.dict();
+        let lazy = PyDict::new(py);
+        let previous = |name: &str| dict.get_item(name).map_or(py.None(), |f| f.to_object(py));
+        let state = PyTuple::new(
+            py,
+            &[
+                self.to_object(py),
+                lazy.to_object(py),
+                previous("__getattr__"),
+                previous("__dir__"),
+            ],
+        );
+        let (getattr_def, dir_def) = lazy_method_defs();
+        dict.set_item("__getattr__", self.state_function(getattr_def, &state)?)?;
+        dict.set_item("__dir__", self.state_function(dir_def, &state)?)?;
+
+        #[cfg(all(Py_3, not(Py_3_7)))]
+        {
+            let locals = PyDict::new(py);
+            py.run(LAZY_MODULE_CLASS, None, Some(locals))?;
+            if let Some(class) = locals.get_item("LazyModule") {
+                self.setattr("__class__", class)?;
+            }
+        }
+        Ok(lazy)
+    }
+
+    /// State of the lazy attributes, `self` of the `__getattr__` function
+    /// installed by `lazy_attrs()`: `(module, initializers, __getattr__, __dir__)`
+    fn lazy_state(&self) -> Option<&PyTuple> {
+        let getattr = self.dict().get_item("__getattr__")?;
+        unsafe {
+            if ffi::PyCFunction_Check(getattr.as_ptr()) == 0
+                || ffi::PyCFunction_GetFunction(getattr.as_ptr())
+                    != Some(lazy_getattr as ffi::PyCFunction)
+            {
+                return None;
+            }
+            Some(self.py().from_borrowed_ptr::<PyTuple>(ffi::PyCFunction_GetSelf(getattr.as_ptr())))
+        }
+    }
+
+    /// Creates the builtin function of this module with `state` as `self`
+    fn state_function(&self, def: *mut ffi::PyMethodDef, state: &Py<PyTuple>) -> PyResult<PyObject> {
+        unsafe {
+            let name = PyString::new(self.py(), self.name()?);
+            PyObject::from_owned_ptr_or_err(
+                self.py(),
+                ffi::PyCFunction_NewEx(def, state.as_ptr(), name.as_ptr()),
+            )
+        }
+    }
+
+    /// Adds a function exported with `#[function]` to the module.
+    ///
+    /// The function is added with its python name, `__module__` of the function
+    /// is the name of this module.
+    ///
+    /// ```rust,ignore
+    /// m.add_function(wrap_function!(double))?;
+    /// ```
+    pub fn add_function(&self, def: &'static PyFunctionDef) -> PyResult<()> {
+        let func = def.create(self.py(), Some(self))?;
+        ::stubs::register_function(self, &def.stub)?;
+        self.add(def.name(), func)
+    }
+
+    /// Adds a submodule to the module.
+    ///
+    /// The submodule is added as an attribute with its short name, `__name__` of the submodule
+    /// gets qualified with the name of this module (i.e. `mylib.io`) and `__package__` is set
+    /// to the name of this module. The submodule is registered in `sys.modules`,
+    /// so `import mylib.io` and `from mylib.io import x` work.
+    ///
+    /// Classes take the module name in `add_class()`,
+    /// the submodule should be added before its classes.
+    pub fn add_submodule(&self, module: &PyModule) -> PyResult<()> {
+        let package = self.name()?.to_owned();
+        let name = module.name()?.to_owned();
+        let short_name = name.rsplit('.').next().unwrap_or(&name).to_owned();
+        let qualified_name = format!("{}.{}", package, short_name);
+
+        if name != qualified_name {
+            module.setattr("__name__", &qualified_name)?;
+        }
+        module.setattr("__package__", &package)?;
+
+        let modules = self.py().import("sys")?.get("modules")?;
+        modules.set_item(&qualified_name, module)?;
+        self.add(short_name.as_str(), module)
     }
 
     /// Adds a new extension type to the module.
     ///
     /// This is a convenience function that initializes the `class`,
     /// sets `new_type.__module__` to this module's name,
-    /// and adds the type to this module.
+    /// and adds the type to this module. The class is registered for the
+    /// stub file of the module, see `stubs::module_stub()`.
+    ///
+    /// `#[class(per_interpreter)]` classes get a new heap type owned by the
+    /// state of the module, see `modulestate`.
     pub fn add_class<T>(&self) -> PyResult<()>
     where
         T: PyTypeInfo,
     {
-        let ty = unsafe {
-            let ty = <T as PyTypeInfo>::type_object();
+        #[cfg(Py_3)]
+        {
+            if ::typeob::is_per_interpreter::<T>() {
+                return ::modulestate::add_interpreter_class::<T>(self);
+            }
+        }
 
-            if ((*ty).tp_flags & ffi::Py_TPFLAGS_READY) != 0 {
-                PyType::new::<T>()
-            } else {
-                // automatically initialize the class
-                initialize_type::<T>(self.py(), Some(self.name()?)).expect(
-                    format!("An error occurred while initializing class {}", T::NAME).as_ref(),
-                );
-                PyType::new::<T>()
+        // initializes the class unless it is initialized already
+        initialize_type::<T>(self.py(), Some(self.name()?))?;
+        let ty = PyType::new::<T>();
+
+        ::stubs::register_class::<T>(self)?;
+        self.add(T::NAME, ty)
+    }
+}
+
+/// Initializer of the lazy module attribute
+type LazyInit = Fn(Python) -> PyResult<PyObject> + Send;
+
+/// Emulates PEP 562 for python versions before 3.7
+#[cfg(all(Py_3, not(Py_3_7)))]
+const LAZY_MODULE_CLASS: &str = "
+import types
+class LazyModule(types.ModuleType):
+    def __getattr__(self, name):
+        return self.__dict__['__getattr__'](name)
+    def __dir__(self):
+        return self.__dict__['__dir__']()
+";
+
+const LAZY_GETATTR: PyMethodDef = PyMethodDef {
+    ml_name: "__getattr__",
+    ml_meth: PyMethodType::PyCFunction(lazy_getattr),
+    ml_flags: ffi::METH_O,
+    ml_doc: "Initializes the lazy attribute of the module.\0",
+};
+
+const LAZY_DIR: PyMethodDef = PyMethodDef {
+    ml_name: "__dir__",
+    ml_meth: PyMethodType::PyCFunction(lazy_dir),
+    ml_flags: ffi::METH_NOARGS,
+    ml_doc: "Lists the attributes of the module.\0",
+};
+
+/// `__getattr__` and `__dir__` definitions shared by all modules
+fn lazy_method_defs() -> (*mut ffi::PyMethodDef, *mut ffi::PyMethodDef) {
+    static INIT: Once = ONCE_INIT;
+    static mut DEFS: [ffi::PyMethodDef; 2] = [ffi::PyMethodDef_INIT, ffi::PyMethodDef_INIT];
+    unsafe {
+        INIT.call_once(|| {
+            DEFS = [LAZY_GETATTR.as_method_def(), LAZY_DIR.as_method_def()];
+        });
+        (&mut DEFS[0], &mut DEFS[1])
+    }
+}
+
+unsafe extern "C" fn lazy_getattr(
+    state: *mut ffi::PyObject,
+    name: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    let _pool = GILPool::new();
+    let py = Python::assume_gil_acquired();
+    let state = py.from_borrowed_ptr::<PyTuple>(state);
+    let result = getattr_lazy(py, state, py.from_borrowed_ptr::<PyObjectRef>(name));
+    cb_convert(PyObjectCallbackConverter, py, result)
+}
+
+unsafe extern "C" fn lazy_dir(
+    state: *mut ffi::PyObject,
+    _args: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    let _pool = GILPool::new();
+    let py = Python::assume_gil_acquired();
+    let state = py.from_borrowed_ptr::<PyTuple>(state);
+    let result = dir_lazy(state).map(|names| names.to_object(py));
+    cb_convert(PyObjectCallbackConverter, py, result)
+}
+
+/// Module `__getattr__`, initializes the lazy attribute and caches the value
+fn getattr_lazy(py: Python, state: &PyTuple, name: &PyObjectRef) -> PyResult<PyObject> {
+    let module = state.get_item(0).extract::<&PyModule>()?;
+    let lazy = state.get_item(1).extract::<&PyDict>()?;
+    let capsule = match lazy.get_item(name) {
+        Some(capsule) => capsule,
+        None => {
+            let getattr = state.get_item(2);
+            if !getattr.is_none() {
+                return Ok(getattr.call1((name,))?.to_object(py));
             }
-        };
+            return Err(exc::AttributeError::new(format!(
+                "module '{}' has no attribute '{}'",
+                module.name()?,
+                name
+            )));
+        }
+    };
+
+    let value = unsafe {
+        let init = ffi::PyCapsule_GetPointer(capsule.as_ptr(), ptr::null()) as *const Box<LazyInit>;
+        if init.is_null() {
+            return Err(PyErr::fetch(py));
+        }
+        (*init)(py)?
+    };
+    // another thread could initialize the attribute while `init` released the GIL
+    if lazy.contains(name)? {
+        module.dict().set_item(name, &value)?;
+        lazy.del_item(name)?;
+        Ok(value)
+    } else {
+        Ok(module.dict().get_item(name).map_or(value, |v| v.to_object(py)))
+    }
+}
+
+/// Module `__dir__`, lists the lazy attributes with the module dict
+/// or the names listed by the previous `__dir__`
+fn dir_lazy(state: &PyTuple) -> PyResult<&PyList> {
+    let module = state.get_item(0).extract::<&PyModule>()?;
+    let dir = state.get_item(3);
+    let names = if dir.is_none() {
+        module.dict().keys()
+    } else {
+        PyList::new(state.py(), &dir.call0()?.extract::<Vec<PyObject>>()?)
+    };
+    for name in state.get_item(1).extract::<&PyDict>()?.keys().iter() {
+        names.append(name)?;
+    }
+    names.call_method0("sort")?;
+    Ok(names)
+}
 
-        self.setattr(T::NAME, ty)
+unsafe extern "C" fn drop_lazy_init(capsule: *mut ffi::PyObject) {
+    let init = ffi::PyCapsule_GetPointer(capsule, ptr::null()) as *mut Box<LazyInit>;
+    if !init.is_null() {
+        drop(Box::from_raw(init));
     }
 }
@@ -2,6 +2,7 @@
 
 use std;
 
+#[cfg(not(Py_LIMITED_API))]
 use buffer;
 use conversion::{FromPyObject, PyTryFrom, ToBorrowedObject};
 use err::{self, PyDowncastError, PyErr, PyResult};
@@ -245,6 +246,7 @@ where
     }
 }
 
+#[cfg(not(Py_LIMITED_API))]
 impl<'source, T> FromPyObject<'source> for Vec<T>
 where
     for<'a> T: FromPyObject<'a> + buffer::Element + Copy,
@@ -2,11 +2,13 @@
 
 use std::os::raw::c_long;
 
-use conversion::ToPyObject;
+use conversion::{FromPyObject, ToPyObject};
 use err::{PyErr, PyResult};
 use ffi::{self, Py_ssize_t};
 use instance::PyObjectWithToken;
 use object::PyObject;
+use objectprotocol::ObjectProtocol;
+use objects::{exc, PyObjectRef};
 use python::{Python, ToPyPointer};
 
 /// Represents a Python `slice`.
@@ -80,6 +82,58 @@ impl PySlice {
     }
 }
 
+/// Represents a sequence subscription key: either a single index or a `slice`.
+///
+/// Use it as the key type of `PySequenceProtocol` methods to support
+/// extended slicing, i.e. `obj[1:10:2]`.
+pub enum SliceOrIndex<'a> {
+    Slice(&'a PySlice),
+    Index(isize),
+}
+
+impl<'a> SliceOrIndex<'a> {
+    /// Normalize the key against a sequence of length `length`.
+    ///
+    /// A single index is converted to a slice of length one, negative
+    /// indices are counted from the end of the sequence.
+    /// Raises `IndexError` if the index is out of range.
+    pub fn indices(&self, length: c_long) -> PyResult<PySliceIndices> {
+        match *self {
+            SliceOrIndex::Slice(slice) => slice.indices(length),
+            SliceOrIndex::Index(idx) => {
+                let length = length as isize;
+                let idx = if idx < 0 { idx + length } else { idx };
+                if idx < 0 || idx >= length {
+                    Err(PyErr::new::<exc::IndexError, _>("index out of range"))
+                } else {
+                    Ok(PySliceIndices {
+                        start: idx,
+                        stop: idx + 1,
+                        step: 1,
+                        slicelength: 1,
+                    })
+                }
+            }
+        }
+    }
+}
+
+impl<'a> From<isize> for SliceOrIndex<'a> {
+    fn from(idx: isize) -> SliceOrIndex<'a> {
+        SliceOrIndex::Index(idx)
+    }
+}
+
+impl<'a> FromPyObject<'a> for SliceOrIndex<'a> {
+    fn extract(ob: &'a PyObjectRef) -> PyResult<Self> {
+        if let Ok(slice) = ob.cast_as::<PySlice>() {
+            Ok(SliceOrIndex::Slice(slice))
+        } else {
+            Ok(SliceOrIndex::Index(ob.extract()?))
+        }
+    }
+}
+
 impl ToPyObject for PySliceIndices {
     fn to_object(&self, py: Python) -> PyObject {
         PySlice::new(py, self.start, self.stop, self.step).into()
@@ -55,6 +55,7 @@ impl PyString {
     }
 
     /// Gets the python string data in its underlying representation.
+    #[cfg(not(Py_LIMITED_API))]
     pub fn data(&self) -> PyStringData {
         // TODO: return the original representation instead
         // of forcing the UTF-8 representation to be created.
@@ -69,6 +70,25 @@ impl PyString {
         }
     }
 
+    /// Gets the python string data in its underlying representation.
+    ///
+    /// The UTF-8 buffer of the string is not a part of the limited API,
+    /// the data is encoded into a bytes object owned by the release pool.
+    #[cfg(Py_LIMITED_API)]
+    pub fn data(&self) -> PyStringData {
+        unsafe {
+            let bytes = ffi::PyUnicode_AsUTF8String(self.0.as_ptr());
+            if bytes.is_null() {
+                PyErr::fetch(self.py()).print(self.py());
+                panic!("PyUnicode_AsUTF8String failed");
+            }
+            let bytes: &PyObjectRef = self.py().from_owned_ptr(bytes);
+            let data = ffi::PyBytes_AsString(bytes.as_ptr()) as *const u8;
+            let size = ffi::PyBytes_Size(bytes.as_ptr());
+            PyStringData::Utf8(std::slice::from_raw_parts(data, size as usize))
+        }
+    }
+
     /// Convert the `PyString` into a Rust string.
     ///
     /// Returns a `UnicodeDecodeError` if the input is not valid unicode
@@ -97,6 +117,27 @@ impl PyBytes {
         unsafe { Py::from_owned_ptr_or_panic(ffi::PyBytes_FromStringAndSize(ptr, len)) }
     }
 
+    /// Creates a new Python byte string object of length `len` and
+    /// fills it in place with `init`, without an intermediate copy.
+    /// The buffer passed to `init` is zero-initialized.
+    ///
+    /// If `init` returns an error, the byte string is released and the error is returned.
+    pub fn new_with<F>(py: Python, len: usize, init: F) -> PyResult<Py<PyBytes>>
+    where
+        F: FnOnce(&mut [u8]) -> PyResult<()>,
+    {
+        unsafe {
+            let ob: Py<PyBytes> = Py::from_owned_ptr_or_err(
+                py,
+                ffi::PyBytes_FromStringAndSize(std::ptr::null(), len as ffi::Py_ssize_t),
+            )?;
+            let buffer = ffi::PyBytes_AsString(ob.as_ptr()) as *mut u8;
+            std::ptr::write_bytes(buffer, 0, len);
+            init(std::slice::from_raw_parts_mut(buffer, len))?;
+            Ok(ob)
+        }
+    }
+
     /// Creates a new Python byte string object from raw pointer.
     ///
     /// Panics if out of memory.
@@ -119,7 +160,10 @@ impl PyBytes {
 
 #[cfg(test)]
 mod test {
+    use super::PyBytes;
     use conversion::{FromPyObject, ToPyObject};
+    use err::PyErr;
+    use exc;
     use instance::AsPyRef;
     use python::Python;
 
@@ -142,4 +186,19 @@ mod test {
         let s2: &str = FromPyObject::extract(py_string.as_ref(py)).unwrap();
         assert_eq!(s, s2);
     }
+
+    #[test]
+    fn test_bytes_new_with() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+        let bytes = PyBytes::new_with(py, 5, |buf| {
+            buf.copy_from_slice(b"Hello");
+            Ok(())
+        }).unwrap();
+        assert_eq!(b"Hello", bytes.as_ref(py).data());
+
+        let err = PyBytes::new_with(py, 5, |_| Err(PyErr::new::<exc::ValueError, _>("error")))
+            .unwrap_err();
+        assert!(err.is_instance::<exc::ValueError>(py));
+    }
 }
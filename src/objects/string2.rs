@@ -112,6 +112,27 @@ impl PyBytes {
         unsafe { Py::from_owned_ptr_or_panic(ffi::PyBytes_FromStringAndSize(ptr, len)) }
     }
 
+    /// Creates a new Python byte string object of length `len` and
+    /// fills it in place with `init`, without an intermediate copy.
+    /// The buffer passed to `init` is zero-initialized.
+    ///
+    /// If `init` returns an error, the byte string is released and the error is returned.
+    pub fn new_with<F>(py: Python, len: usize, init: F) -> PyResult<Py<PyBytes>>
+    where
+        F: FnOnce(&mut [u8]) -> PyResult<()>,
+    {
+        unsafe {
+            let ob: Py<PyBytes> = Py::from_owned_ptr_or_err(
+                py,
+                ffi::PyBytes_FromStringAndSize(std::ptr::null(), len as ffi::Py_ssize_t),
+            )?;
+            let buffer = ffi::PyBytes_AsString(ob.as_ptr()) as *mut u8;
+            std::ptr::write_bytes(buffer, 0, len);
+            init(std::slice::from_raw_parts_mut(buffer, len))?;
+            Ok(ob)
+        }
+    }
+
     /// Gets the Python string data as byte slice.
     pub fn data(&self) -> &[u8] {
         unsafe {
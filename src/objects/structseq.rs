@@ -0,0 +1,220 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+use std::ffi::CString;
+use std::os::raw::c_int;
+use std::ptr;
+
+use conversion::{IntoPyTuple, ToPyObject};
+use err::{PyErr, PyResult};
+use ffi;
+use instance::{AsPyRef, Py};
+use object::PyObject;
+use objects::{exc, PyType};
+use python::{Python, ToPyPointer};
+
+/// Field definition of a struct sequence type.
+pub struct PyStructSequenceField<'a> {
+    /// Attribute name of the field
+    pub name: &'a str,
+    /// Field doc string
+    pub doc: &'a str,
+}
+
+/// Represents a Python struct sequence type.
+///
+/// Instances of struct sequence types behave like named tuples: fields are
+/// accessible by attribute and by index, i.e. `os.stat_result`.
+/// Creating instances is cheap compared to `collections.namedtuple` types.
+///
+/// Example:
+/// ```rust
+/// # extern crate pyo3;
+/// # use pyo3::prelude::*;
+/// # fn main() {
+/// # let gil = Python::acquire_gil();
+/// # let py = gil.python();
+/// let point = PyStructSequenceType::new(
+///     py,
+///     "geometry.Point",
+///     "A point in the plane",
+///     &[
+///         PyStructSequenceField { name: "x", doc: "x coordinate" },
+///         PyStructSequenceField { name: "y", doc: "y coordinate" },
+///     ],
+///     2,
+/// ).unwrap();
+///
+/// let p = point.instance(py, (1, 2)).unwrap();
+/// # }
+/// ```
+pub struct PyStructSequenceType {
+    ty: Py<PyType>,
+    n_fields: usize,
+}
+
+impl PyStructSequenceType {
+    /// Creates a new struct sequence type.
+    ///
+    /// `name` should contain the module name, i.e. `module.TypeName`.
+    /// Only the first `n_in_sequence` fields are visible when an instance
+    /// is used as a tuple, remaining fields are accessible by attribute only.
+    /// Raises `ValueError` if a name or doc string contains a NUL byte.
+    pub fn new(
+        py: Python,
+        name: &str,
+        doc: &str,
+        fields: &[PyStructSequenceField],
+        n_in_sequence: usize,
+    ) -> PyResult<PyStructSequenceType> {
+        if n_in_sequence > fields.len() {
+            return Err(PyErr::new::<exc::ValueError, _>(format!(
+                "{}: n_in_sequence is greater than number of fields",
+                name
+            )));
+        }
+
+        // names and docs containing NUL bytes raise ValueError
+        let strings = fields
+            .iter()
+            .map(|field| Ok((CString::new(field.name)?, CString::new(field.doc)?)))
+            .collect::<PyResult<Vec<_>>>()?;
+        let name = CString::new(name)?;
+        let doc = CString::new(doc)?;
+
+        // Python keeps pointers to the type name, doc and field names
+        // for the lifetime of the type object
+        let mut defs: Vec<ffi::PyStructSequence_Field> = strings
+            .into_iter()
+            .map(|(name, doc)| ffi::PyStructSequence_Field {
+                name: name.into_raw(),
+                doc: doc.into_raw(),
+            })
+            .collect();
+        defs.push(ffi::PyStructSequence_Field {
+            name: ptr::null_mut(),
+            doc: ptr::null_mut(),
+        });
+
+        let mut desc = ffi::PyStructSequence_Desc {
+            name: name.into_raw(),
+            doc: doc.into_raw(),
+            fields: defs.as_mut_ptr(),
+            n_in_sequence: n_in_sequence as c_int,
+        };
+
+        unsafe {
+            let ty = ffi::PyStructSequence_NewType(&mut desc);
+            Ok(PyStructSequenceType {
+                ty: Py::from_owned_ptr_or_err(py, ty as *mut ffi::PyObject)?,
+                n_fields: fields.len(),
+            })
+        }
+    }
+
+    /// Gets the type object.
+    pub fn type_object(&self) -> &Py<PyType> {
+        &self.ty
+    }
+
+    /// Creates a new instance of the struct sequence type.
+    ///
+    /// `values` must provide a value for every field, in field order.
+    /// Implement `IntoPyTuple` to construct instances from Rust structs.
+    pub fn instance<V>(&self, py: Python, values: V) -> PyResult<PyObject>
+    where
+        V: IntoPyTuple,
+    {
+        let values = values.into_tuple(py);
+        let values = values.as_ref(py);
+        if values.len() != self.n_fields {
+            return Err(PyErr::new::<exc::TypeError, _>(format!(
+                "{} takes a {}-sequence ({}-sequence given)",
+                self.ty.as_ref(py).name(),
+                self.n_fields,
+                values.len()
+            )));
+        }
+
+        unsafe {
+            let ob = ffi::PyStructSequence_New(self.ty.as_ptr() as *mut ffi::PyTypeObject);
+            if ob.is_null() {
+                return Err(PyErr::fetch(py));
+            }
+            for (idx, item) in values.iter().enumerate() {
+                // PyStructSequence_SetItem steals the reference
+                ffi::Py_INCREF(item.as_ptr());
+                ffi::PyStructSequence_SetItem(ob, idx as ffi::Py_ssize_t, item.as_ptr());
+            }
+            Ok(PyObject::from_owned_ptr(py, ob))
+        }
+    }
+}
+
+impl ToPyObject for PyStructSequenceType {
+    fn to_object(&self, py: Python) -> PyObject {
+        self.ty.to_object(py)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{PyStructSequenceField, PyStructSequenceType};
+    use conversion::ToPyObject;
+    use exc;
+    use objects::PyDict;
+    use python::Python;
+
+    #[test]
+    fn test_struct_sequence() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let ty = PyStructSequenceType::new(
+            py,
+            "test.Record",
+            "Test record",
+            &[
+                PyStructSequenceField { name: "a", doc: "first" },
+                PyStructSequenceField { name: "b", doc: "second" },
+                PyStructSequenceField { name: "c", doc: "hidden" },
+            ],
+            2,
+        ).unwrap();
+
+        let ob = ty.instance(py, (1, "two", 3.0)).unwrap();
+        let d = PyDict::new(py);
+        d.set_item("ob", ob).unwrap();
+        d.set_item("Record", ty.to_object(py)).unwrap();
+
+        py.run("assert ob.a == 1 and ob.b == 'two' and ob.c == 3.0", None, Some(d))
+            .unwrap();
+        py.run("assert ob[0] == 1 and ob[1] == 'two'", None, Some(d))
+            .unwrap();
+        py.run("assert len(ob) == 2 and tuple(ob) == (1, 'two')", None, Some(d))
+            .unwrap();
+        py.run("assert isinstance(ob, Record)", None, Some(d)).unwrap();
+
+        let err = ty.instance(py, (1, 2)).unwrap_err();
+        assert!(err.is_instance::<exc::TypeError>(py));
+
+        let err = PyStructSequenceType::new(
+            py,
+            "test.Invalid",
+            "",
+            &[PyStructSequenceField { name: "a", doc: "" }],
+            2,
+        ).err()
+            .unwrap();
+        assert!(err.is_instance::<exc::ValueError>(py));
+
+        let err = PyStructSequenceType::new(
+            py,
+            "test.Invalid",
+            "",
+            &[PyStructSequenceField { name: "a\0b", doc: "" }],
+            1,
+        ).err()
+            .unwrap();
+        assert!(err.is_instance::<exc::ValueError>(py));
+    }
+}
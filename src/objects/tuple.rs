@@ -1,14 +1,14 @@
 // Copyright (c) 2017-present PyO3 Project and Contributors
 
 use std;
-use std::slice;
 
 use super::exc;
 use conversion::{FromPyObject, IntoPyObject, IntoPyTuple, PyTryFrom, ToPyObject};
 use err::{PyErr, PyResult};
 use ffi::{self, Py_ssize_t};
-use instance::{AsPyRef, Py, PyObjectWithToken};
+use instance::{Py, PyObjectWithToken};
 use object::PyObject;
+use objectprotocol::ObjectProtocol;
 use objects::PyObjectRef;
 use python::{IntoPyPointer, Python, ToPyPointer};
 
@@ -40,7 +40,7 @@ impl PyTuple {
     pub fn len(&self) -> usize {
         unsafe {
             // non-negative Py_ssize_t should always fit into Rust uint
-            ffi::PyTuple_GET_SIZE(self.as_ptr()) as usize
+            tuple_size(self.as_ptr()) as usize
         }
     }
 
@@ -58,7 +58,7 @@ impl PyTuple {
     pub fn split_from(&self, low: isize) -> Py<PyTuple> {
         unsafe {
             let ptr =
-                ffi::PyTuple_GetSlice(self.as_ptr(), low, ffi::PyTuple_GET_SIZE(self.as_ptr()));
+                ffi::PyTuple_GetSlice(self.as_ptr(), low, tuple_size(self.as_ptr()));
             Py::from_owned_ptr_or_panic(ptr)
         }
     }
@@ -72,35 +72,37 @@ impl PyTuple {
         assert!(index < self.len());
         unsafe {
             self.py()
-                .from_borrowed_ptr(ffi::PyTuple_GET_ITEM(self.as_ptr(), index as Py_ssize_t))
+                .from_borrowed_ptr(tuple_item(self.as_ptr(), index as Py_ssize_t))
         }
     }
 
+    /// The layout of the tuple object is not a part of the limited API
+    #[cfg(not(Py_LIMITED_API))]
     pub fn as_slice(&self) -> &[PyObject] {
         // This is safe because PyObject has the same memory layout as *mut ffi::PyObject,
         // and because tuples are immutable.
         // (We don't even need a Python token, thanks to immutability)
         unsafe {
             let ptr = self.as_ptr() as *mut ffi::PyTupleObject;
-            std::mem::transmute(slice::from_raw_parts((*ptr).ob_item.as_ptr(), self.len()))
+            std::mem::transmute(std::slice::from_raw_parts((*ptr).ob_item.as_ptr(), self.len()))
         }
     }
 
     /// Returns an iterator over the tuple items.
     pub fn iter(&self) -> PyTupleIterator {
         PyTupleIterator {
-            py: self.py(),
-            slice: self.as_slice(),
+            tuple: self,
             index: 0,
+            length: self.len(),
         }
     }
 }
 
 /// Used by `PyTuple::iter()`.
 pub struct PyTupleIterator<'a> {
-    py: Python<'a>,
-    slice: &'a [PyObject],
+    tuple: &'a PyTuple,
     index: usize,
+    length: usize,
 }
 
 impl<'a> Iterator for PyTupleIterator<'a> {
@@ -108,8 +110,8 @@ impl<'a> Iterator for PyTupleIterator<'a> {
 
     #[inline]
     fn next(&mut self) -> Option<&'a PyObjectRef> {
-        if self.index < self.slice.len() {
-            let item = self.slice[self.index].as_ref(self.py);
+        if self.index < self.length {
+            let item = self.tuple.get_item(self.index);
             self.index += 1;
             Some(item)
         } else {
@@ -127,6 +129,30 @@ impl<'a> std::iter::IntoIterator for &'a PyTuple {
     }
 }
 
+#[cfg(not(Py_LIMITED_API))]
+#[inline]
+unsafe fn tuple_size(ptr: *mut ffi::PyObject) -> Py_ssize_t {
+    ffi::PyTuple_GET_SIZE(ptr)
+}
+
+#[cfg(Py_LIMITED_API)]
+#[inline]
+unsafe fn tuple_size(ptr: *mut ffi::PyObject) -> Py_ssize_t {
+    ffi::PyTuple_Size(ptr)
+}
+
+#[cfg(not(Py_LIMITED_API))]
+#[inline]
+unsafe fn tuple_item(ptr: *mut ffi::PyObject, index: Py_ssize_t) -> *mut ffi::PyObject {
+    ffi::PyTuple_GET_ITEM(ptr, index)
+}
+
+#[cfg(Py_LIMITED_API)]
+#[inline]
+unsafe fn tuple_item(ptr: *mut ffi::PyObject, index: Py_ssize_t) -> *mut ffi::PyObject {
+    ffi::PyTuple_GetItem(ptr, index)
+}
+
 impl<'a> IntoPyTuple for &'a PyTuple {
     fn into_tuple(self, _py: Python) -> Py<PyTuple> {
         self.into()
@@ -192,10 +218,9 @@ macro_rules! tuple_conversion ({$length:expr,$(($refN:ident, $n:tt, $T:ident)),+
         fn extract(obj: &'s PyObjectRef) -> PyResult<Self>
         {
             let t = <PyTuple as PyTryFrom>::try_from(obj)?;
-            let slice = t.as_slice();
             if t.len() == $length {
                 Ok((
-                    $( try!(slice[$n].extract::<$T>(obj.py())), )+
+                    $( try!(t.get_item($n).extract::<$T>()), )+
                 ))
             } else {
                 Err(wrong_tuple_length(t, $length))
@@ -3,12 +3,15 @@
 // based on Daniel Grunwald's https://github.com/dgrunwald/rust-cpython
 
 use std::borrow::Cow;
+#[cfg(not(Py_LIMITED_API))]
 use std::ffi::CStr;
 
 use err::{PyErr, PyResult};
 use ffi;
 use instance::{Py, PyObjectWithToken};
 use object::PyObject;
+#[cfg(Py_LIMITED_API)]
+use objectprotocol::ObjectProtocol;
 use python::{Python, ToPyPointer};
 use typeob::{PyTypeInfo, PyTypeObject};
 
@@ -16,6 +19,9 @@ use typeob::{PyTypeInfo, PyTypeObject};
 pub struct PyType(PyObject);
 
 pyobject_convert!(PyType);
+#[cfg(not(Py_LIMITED_API))]
+pyobject_nativetype!(PyType, PyType_Type, PyType_Check, PyHeapTypeObject);
+#[cfg(Py_LIMITED_API)]
 pyobject_nativetype!(PyType, PyType_Type, PyType_Check);
 
 impl PyType {
@@ -39,15 +45,27 @@ impl PyType {
     }
 
     /// Gets the name of the PyType.
+    #[cfg(not(Py_LIMITED_API))]
     pub fn name(&self) -> Cow<str> {
         unsafe { CStr::from_ptr((*self.as_type_ptr()).tp_name).to_string_lossy() }
     }
 
+    /// Gets the name of the PyType.
+    ///
+    /// `tp_name` is not a part of the limited API, the `__name__` attribute is used instead.
+    #[cfg(Py_LIMITED_API)]
+    pub fn name(&self) -> Cow<str> {
+        self.getattr("__name__")
+            .and_then(|name| name.extract::<Cow<str>>())
+            .unwrap_or(Cow::Borrowed("<unknown>"))
+    }
+
     /// Check whether `self` is subclass of type `T` like Python `issubclass` function
     pub fn is_subclass<T>(&self) -> PyResult<bool>
     where
         T: PyTypeObject,
     {
+        T::init_type()?;
         let result = unsafe { ffi::PyObject_IsSubclass(self.as_ptr(), T::type_object().as_ptr()) };
         if result == -1 {
             Err(PyErr::fetch(self.py()))
@@ -16,6 +16,8 @@ pub use conversion::{FromPyObject, IntoPyObject, IntoPyTuple, PyTryFrom, PyTryIn
                      ToBorrowedObject, ToPyObject};
 pub use err::{PyDowncastError, PyErr, PyErrArguments, PyErrValue, PyResult};
 pub use instance::{AsPyRef, Py, PyNativeType, PyObjectWithToken, PyToken};
+#[cfg(Py_3)]
+pub use modulestate::PyModuleState;
 pub use noargs::NoArgs;
 pub use object::PyObject;
 pub use objectprotocol::ObjectProtocol;
@@ -182,19 +182,15 @@ impl<'p> Python<'p> {
                 .unwrap_or_else(|| ffi::PyModule_GetDict(mptr));
             let locals = locals.map(|l| l.as_ptr()).unwrap_or(globals);
 
-            let res_ptr = ffi::PyRun_StringFlags(
-                code.as_ptr(),
-                start,
-                globals,
-                locals,
-                ::std::ptr::null_mut(),
-            );
+            let res_ptr = run_string(code.as_ptr(), start, globals, locals);
 
             self.from_owned_ptr_or_err(res_ptr)
         }
     }
 
     /// Gets the Python type object for type `T`.
+    ///
+    /// Panics if the type object can not be initialized.
     pub fn get_type<T>(self) -> &'p PyType
     where
         T: PyTypeObject,
@@ -209,6 +205,7 @@ impl<'p> Python<'p> {
 
     /// Check whether `obj` is an instance of type `T` like Python `isinstance` function
     pub fn is_instance<T: PyTypeObject, V: ToPyPointer>(self, obj: &V) -> PyResult<bool> {
+        T::init_type()?;
         T::type_object().as_ref(self).is_instance(obj)
     }
 
@@ -218,6 +215,7 @@ impl<'p> Python<'p> {
         T: PyTypeObject,
         U: PyTypeObject,
     {
+        T::init_type()?;
         T::type_object().as_ref(self).is_subclass::<U>()
     }
 
@@ -472,6 +470,33 @@ impl<'p> Python<'p> {
     }
 }
 
+#[cfg(not(Py_LIMITED_API))]
+unsafe fn run_string(
+    code: *const std::os::raw::c_char,
+    start: c_int,
+    globals: *mut ffi::PyObject,
+    locals: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    ffi::PyRun_StringFlags(code, start, globals, locals, ::std::ptr::null_mut())
+}
+
+/// `PyRun_StringFlags` is not a part of the limited API
+#[cfg(Py_LIMITED_API)]
+unsafe fn run_string(
+    code: *const std::os::raw::c_char,
+    start: c_int,
+    globals: *mut ffi::PyObject,
+    locals: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    let code = ffi::Py_CompileString(code, "<string>\0".as_ptr() as *const _, start);
+    if code.is_null() {
+        return code;
+    }
+    let res = ffi::PyEval_EvalCode(code, globals, locals);
+    ffi::Py_DECREF(code);
+    res
+}
+
 #[cfg(test)]
 mod test {
     use objectprotocol::ObjectProtocol;
@@ -0,0 +1,359 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Python type stubs
+//!
+//! `#[class]`, `#[methods]`, `#[proto]` and `#[pyfn]` record the signatures of the exported
+//! items, the rust types are mapped to python annotations at compile time.
+//! Classes get registered by `PyModule::add_class()`, functions by `#[pyfn]`.
+//! `module_stub()` renders the `.pyi` stub file of the module.
+//!
+//! Annotations which can not be inferred from the rust types can be set with the
+//! `#[stub(arg = "annotation", returns = "annotation")]` attribute on functions and methods
+//! and with `#[stub(ty = "annotation")]` on `#[prop]` fields.
+
+use std::mem;
+use std::os::raw::{c_char, c_void};
+
+use err::PyResult;
+use ffi;
+use instance::PyObjectWithToken;
+use object::PyObject;
+use objectprotocol::ObjectProtocol;
+use objects::{PyDict, PyModule, PyObjectRef, PyType};
+use python::{Python, ToPyPointer};
+use typeob::PyTypeInfo;
+
+/// Kind of the stub item
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StubKind {
+    Function,
+    Method,
+    ClassMethod,
+    StaticMethod,
+    New,
+    Getter,
+    Setter,
+    Deleter,
+    Attribute,
+    /// One of the signatures of `#[overload]` method
+    Overload,
+}
+
+/// Signature of the exported item, generated by the macros
+#[derive(Debug)]
+pub struct StubFn {
+    pub name: &'static str,
+    pub kind: StubKind,
+    /// Parameters with annotations, i.e. `self, a: int, b: int = 1`
+    pub params: &'static str,
+    /// Annotation of the return value
+    pub returns: &'static str,
+    pub doc: &'static str,
+}
+
+/// Sources of the class stub items, the `#[class]` properties,
+/// the `#[methods]` block and the `#[proto]` implementations
+#[doc(hidden)]
+pub mod source {
+    pub struct Class;
+    pub struct Methods;
+    pub struct Object;
+    pub struct Async;
+    pub struct Context;
+    pub struct Descriptor;
+    pub struct Iter;
+    pub struct Mapping;
+    pub struct Sequence;
+    pub struct Number;
+}
+
+#[doc(hidden)]
+pub trait PyStubsImpl<S> {
+    fn stubs() -> &'static [StubFn];
+}
+
+impl<T, S> PyStubsImpl<S> for T {
+    default fn stubs() -> &'static [StubFn] {
+        &[]
+    }
+}
+
+/// Stub items of the class
+pub fn class_items<T>() -> Vec<&'static StubFn> {
+    let mut items: Vec<&'static StubFn> = Vec::new();
+    items.extend(<T as PyStubsImpl<source::Class>>::stubs());
+    items.extend(<T as PyStubsImpl<source::Methods>>::stubs());
+    items.extend(<T as PyStubsImpl<source::Object>>::stubs());
+    items.extend(<T as PyStubsImpl<source::Async>>::stubs());
+    items.extend(<T as PyStubsImpl<source::Context>>::stubs());
+    items.extend(<T as PyStubsImpl<source::Descriptor>>::stubs());
+    items.extend(<T as PyStubsImpl<source::Iter>>::stubs());
+    items.extend(<T as PyStubsImpl<source::Mapping>>::stubs());
+    items.extend(<T as PyStubsImpl<source::Sequence>>::stubs());
+    items.extend(<T as PyStubsImpl<source::Number>>::stubs());
+
+    // user methods take precedence
+    let mut unique: Vec<&'static StubFn> = Vec::new();
+    for item in items {
+        if item.kind == StubKind::Overload
+            || !unique.iter().any(|u| u.name == item.name && u.kind == item.kind)
+        {
+            unique.push(item);
+        }
+    }
+    unique
+}
+
+/// Renders the class definition of the stub file
+pub fn class_stub<T>(py: Python) -> String
+where
+    T: PyTypeInfo,
+{
+    let base = unsafe {
+        let base = ::typeob::type_base(T::type_object());
+        if base.is_null() || base == &mut ffi::PyBaseObject_Type as *mut _ {
+            None
+        } else {
+            let name = py.from_borrowed_ptr::<PyType>(base as *mut ffi::PyObject).name();
+            Some(name.rsplit('.').next().unwrap_or("object").to_owned())
+        }
+    };
+
+    let mut out = match base {
+        Some(base) => format!("class {}({}):\n", T::NAME, base),
+        None => format!("class {}:\n", T::NAME),
+    };
+    let doc = T::DESCRIPTION.trim_right_matches('\0');
+    if !doc.is_empty() {
+        out.push_str(&format!("    {}\n", docstring(doc, "    ")));
+    }
+    let items = class_items::<T>();
+    if items.is_empty() && doc.is_empty() {
+        out.push_str("    ...\n");
+    }
+    render_items(&mut out, &items, T::NAME, "    ");
+    out
+}
+
+/// Renders the `.pyi` stub file of the module
+///
+/// The module attributes are rendered in the order of the module dict,
+/// the attributes which are not registered with `#[pyfn]` or `add_class()` are
+/// annotated with the type of the value.
+pub fn module_stub(py: Python, module: &PyModule) -> PyResult<String> {
+    let name = module.name()?;
+
+    let mut out = format!("# Stub file of the `{}` module, generated by pyo3\n", name);
+    if let Ok(doc) = module.get("__doc__").and_then(|doc| doc.extract::<String>()) {
+        if !doc.is_empty() {
+            out.push_str(&format!("{}\n", docstring(&doc, "")));
+        }
+    }
+    out.push_str("from typing import Any, Dict, FrozenSet, List, Optional, Set, Tuple, overload\n\n");
+
+    let registered = match module.dict().get_item(REGISTRY_KEY) {
+        Some(registry) => Some(registry.extract::<&PyDict>()?),
+        None => None,
+    };
+
+    for (key, value) in module.dict().iter() {
+        let attr = key.extract::<String>()?;
+        if attr.starts_with('_') {
+            continue;
+        }
+        let entry = registered
+            .and_then(|registry| registry.get_item(attr.as_str()))
+            .and_then(registered_item);
+
+        match entry {
+            Some(Registered::Function(stub)) => {
+                out.push('\n');
+                render_items(&mut out, &[stub], "", "");
+            }
+            Some(Registered::Class(class_stub)) => {
+                out.push('\n');
+                out.push_str(&class_stub(py));
+            }
+            None => {
+                let ty = value.get_type().name();
+                match ty.as_ref() {
+                    "module" => (),
+                    "builtin_function_or_method" | "function" => out.push_str(&format!(
+                        "\ndef {}(*args: Any, **kwargs: Any) -> Any: ...\n",
+                        attr
+                    )),
+                    ty => out.push_str(&format!("{}: {}\n", attr, type_annotation(ty))),
+                }
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Registers the stub of `#[pyfn]` function, called by the module initialization
+#[doc(hidden)]
+pub fn register_function(module: &PyModule, stub: &'static StubFn) -> PyResult<()> {
+    register(module, stub.name, Registered::Function(stub))
+}
+
+/// Registers the class stub, called by `PyModule::add_class()`
+#[doc(hidden)]
+pub fn register_class<T>(module: &PyModule) -> PyResult<()>
+where
+    T: PyTypeInfo,
+{
+    register(module, T::NAME, Registered::Class(class_stub::<T>))
+}
+
+#[derive(Clone, Copy)]
+enum Registered {
+    Function(&'static StubFn),
+    Class(fn(Python) -> String),
+}
+
+/// The registered items are kept in the module dict, `name -> capsule`,
+/// so modules sharing a name do not see each other's items
+const REGISTRY_KEY: &str = "__pyo3_stubs__";
+const FUNCTION_CAPSULE: &[u8] = b"pyo3.stubs.function\0";
+const CLASS_CAPSULE: &[u8] = b"pyo3.stubs.class\0";
+
+fn register(module: &PyModule, name: &'static str, item: Registered) -> PyResult<()> {
+    let py = module.py();
+    let dict = module.dict();
+    let registry = match dict.get_item(REGISTRY_KEY) {
+        Some(registry) => registry.extract::<&PyDict>()?,
+        None => {
+            let registry = PyDict::new(py);
+            dict.set_item(REGISTRY_KEY, registry)?;
+            registry
+        }
+    };
+
+    let (ptr, capsule_name) = match item {
+        Registered::Function(stub) => (stub as *const StubFn as *mut c_void, FUNCTION_CAPSULE),
+        Registered::Class(class_stub) => (class_stub as *mut c_void, CLASS_CAPSULE),
+    };
+    // modules can be initialized more than once, the item is replaced
+    let capsule = unsafe {
+        PyObject::from_owned_ptr_or_err(
+            py,
+            ffi::PyCapsule_New(ptr, capsule_name.as_ptr() as *const c_char, None),
+        )?
+    };
+    registry.set_item(name, capsule)
+}
+
+/// Pointer of the capsule created by `register()`, `None` for other objects
+fn capsule_pointer(capsule: &PyObjectRef, name: &[u8]) -> Option<*mut c_void> {
+    unsafe {
+        let name = name.as_ptr() as *const c_char;
+        if ffi::PyCapsule_IsValid(capsule.as_ptr(), name) != 0 {
+            Some(ffi::PyCapsule_GetPointer(capsule.as_ptr(), name))
+        } else {
+            None
+        }
+    }
+}
+
+fn registered_item(capsule: &PyObjectRef) -> Option<Registered> {
+    unsafe {
+        if let Some(ptr) = capsule_pointer(capsule, FUNCTION_CAPSULE) {
+            Some(Registered::Function(&*(ptr as *const StubFn)))
+        } else if let Some(ptr) = capsule_pointer(capsule, CLASS_CAPSULE) {
+            Some(Registered::Class(mem::transmute::<*mut c_void, fn(Python) -> String>(ptr)))
+        } else {
+            None
+        }
+    }
+}
+
+fn type_annotation(ty: &str) -> &str {
+    match ty {
+        "bool" | "int" | "float" | "complex" | "str" | "bytes" => ty,
+        "list" => "List[Any]",
+        "dict" => "Dict[Any, Any]",
+        "tuple" => "Tuple[Any, ...]",
+        "set" => "Set[Any]",
+        "frozenset" => "FrozenSet[Any]",
+        "NoneType" => "None",
+        _ => "Any",
+    }
+}
+
+/// Backslashes are escaped, so are the quotes which could end the docstring,
+/// a quote followed by another quote or at the end of the text
+fn docstring(doc: &str, indent: &str) -> String {
+    let mut escaped = String::with_capacity(doc.len());
+    let mut chars = doc.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' if chars.peek().map_or(true, |&next| next == '"') => escaped.push_str("\\\""),
+            c => escaped.push(c),
+        }
+    }
+    format!("\"\"\"{}\"\"\"", escaped.replace('\n', &format!("\n{}", indent)))
+}
+
+fn render_def(out: &mut String, item: &StubFn, returns: &str, indent: &str) {
+    let sig = format!("{}def {}({}) -> {}:", indent, item.name, item.params, returns);
+    if item.doc.is_empty() {
+        out.push_str(&format!("{} ...\n", sig));
+    } else {
+        let body = format!("{}    ", indent);
+        out.push_str(&format!("{}\n{}{}\n{}...\n", sig, body, docstring(item.doc, &body), body));
+    }
+}
+
+fn render_items(out: &mut String, items: &[&StubFn], cls: &str, indent: &str) {
+    let has_getter = |name: &str| {
+        items
+            .iter()
+            .any(|item| item.kind == StubKind::Getter && item.name == name)
+    };
+
+    for item in items.iter() {
+        match item.kind {
+            StubKind::Function | StubKind::Method => render_def(out, item, item.returns, indent),
+            StubKind::New => render_def(out, item, cls, indent),
+            StubKind::ClassMethod => {
+                out.push_str(&format!("{}@classmethod\n", indent));
+                render_def(out, item, item.returns, indent);
+            }
+            StubKind::StaticMethod => {
+                out.push_str(&format!("{}@staticmethod\n", indent));
+                render_def(out, item, item.returns, indent);
+            }
+            StubKind::Overload => {
+                out.push_str(&format!("{}@overload\n", indent));
+                render_def(out, item, item.returns, indent);
+            }
+            StubKind::Attribute => {
+                out.push_str(&format!("{}{}: {}\n", indent, item.name, item.returns));
+            }
+            StubKind::Getter => {
+                out.push_str(&format!("{}@property\n", indent));
+                render_def(out, item, item.returns, indent);
+                for acc in items.iter().filter(|acc| acc.name == item.name) {
+                    match acc.kind {
+                        StubKind::Setter => {
+                            out.push_str(&format!("{}@{}.setter\n", indent, item.name));
+                            render_def(out, acc, "None", indent);
+                        }
+                        StubKind::Deleter => {
+                            out.push_str(&format!("{}@{}.deleter\n", indent, item.name));
+                            render_def(out, acc, "None", indent);
+                        }
+                        _ => (),
+                    }
+                }
+            }
+            // write only property, the annotation of the value is the last one
+            StubKind::Setter if !has_getter(item.name) => {
+                let ty = item.params.rsplit(": ").next().unwrap_or("Any");
+                out.push_str(&format!("{}{}: {}\n", indent, item.name, ty));
+            }
+            StubKind::Setter | StubKind::Deleter => (),
+        }
+    }
+}
@@ -6,12 +6,19 @@ use std;
 use std::collections::HashMap;
 use std::ffi::{CStr, CString};
 use std::mem;
+use std::os::raw::c_char;
+#[cfg(Py_3)]
+use std::os::raw::{c_int, c_uint, c_void};
+#[cfg(Py_3)]
+use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering, ATOMIC_USIZE_INIT};
+use std::sync::{Condvar, Mutex, Once, ONCE_INIT};
 
 use class::methods::PyMethodDefType;
 use err::{PyErr, PyResult};
 use instance::{Py, PyObjectWithToken, PyToken};
-use objects::PyType;
-use python::{IntoPyPointer, Python};
+use objects::{exc, PyType};
+use python::{IntoPyPointer, Python, ToPyPointer};
 use {class, ffi, pythonrun};
 
 /// Python type information.
@@ -22,6 +29,10 @@ pub trait PyTypeInfo {
     /// Class name
     const NAME: &'static str;
 
+    /// Module name, used for the qualified type name if the class
+    /// is not added to a module with `PyModule::add_class()`
+    const MODULE: Option<&'static str> = None;
+
     /// Class doc string
     const DESCRIPTION: &'static str = "\0";
 
@@ -40,6 +51,17 @@ pub trait PyTypeInfo {
     /// PyTypeObject instance for this type
     unsafe fn type_object() -> &'static mut ffi::PyTypeObject;
 
+    /// Storage of the type object, required by `initialize_type()`
+    unsafe fn type_storage() -> Option<&'static mut PyTypeStorage> {
+        None
+    }
+
+    /// PyTypeObject of the nearest base type implemented in C,
+    /// i.e. `PyDict_Type` for a class derived from `PyDict`.
+    unsafe fn native_base_type() -> *mut ffi::PyTypeObject {
+        <Self::BaseType as PyTypeInfo>::native_base_type()
+    }
+
     /// Check if `*mut ffi::PyObject` is instance of this type
     #[cfg_attr(feature = "cargo-clippy", allow(not_unsafe_ptr_arg_deref))]
     fn is_instance(ptr: *mut ffi::PyObject) -> bool {
@@ -65,6 +87,9 @@ pub const PY_TYPE_FLAG_BASETYPE: usize = 1 << 2;
 /// The instances of this type have a dictionary containing instance variables
 pub const PY_TYPE_FLAG_DICT: usize = 1 << 3;
 
+/// Every interpreter gets its own heap type, owned by the module state (python 3)
+pub const PY_TYPE_FLAG_PER_INTERPRETER: usize = 1 << 4;
+
 impl<'a, T: ?Sized> PyTypeInfo for &'a T
 where
     T: PyTypeInfo,
@@ -72,6 +97,7 @@ where
     type Type = T::Type;
     type BaseType = T::BaseType;
     const NAME: &'static str = T::NAME;
+    const MODULE: Option<&'static str> = T::MODULE;
     const DESCRIPTION: &'static str = T::DESCRIPTION;
     const SIZE: usize = T::SIZE;
     const OFFSET: isize = T::OFFSET;
@@ -82,6 +108,16 @@ where
         <T as PyTypeInfo>::type_object()
     }
 
+    #[inline]
+    default unsafe fn type_storage() -> Option<&'static mut PyTypeStorage> {
+        <T as PyTypeInfo>::type_storage()
+    }
+
+    #[inline]
+    default unsafe fn native_base_type() -> *mut ffi::PyTypeObject {
+        <T as PyTypeInfo>::native_base_type()
+    }
+
     #[inline]
     default fn is_instance(ptr: *mut ffi::PyObject) -> bool {
         <T as PyTypeInfo>::is_instance(ptr)
@@ -93,6 +129,216 @@ where
     }
 }
 
+/// Storage of the type object of the classes.
+///
+/// The protocol implementations fill the type object layout. With the limited API
+/// (`abi3` feature) the layout is the template of the `PyType_FromSpec` slots,
+/// the created heap type is stored here and `type_object()` returns it.
+#[repr(C)]
+pub struct PyTypeStorage {
+    layout: ffi::PyTypeObjectLayout,
+    #[cfg(Py_LIMITED_API)]
+    heap_type: *mut ffi::PyTypeObject,
+    /// The layout is filled and the type is ready, a failure of the
+    /// class attributes does not ready the type again
+    ready: TypeInitCell,
+    /// The class attributes are set
+    init: TypeInitCell,
+}
+
+impl PyTypeStorage {
+    #[cfg(not(Py_LIMITED_API))]
+    pub const INIT: PyTypeStorage = PyTypeStorage {
+        layout: ffi::PyTypeObject_INIT,
+        ready: TypeInitCell::INIT,
+        init: TypeInitCell::INIT,
+    };
+
+    #[cfg(Py_LIMITED_API)]
+    pub const INIT: PyTypeStorage = PyTypeStorage {
+        layout: ffi::PyTypeObjectLayout_INIT,
+        heap_type: 0 as *mut ffi::PyTypeObject,
+        ready: TypeInitCell::INIT,
+        init: TypeInitCell::INIT,
+    };
+
+    /// The type object, the layout is returned until the heap type gets created
+    #[cfg(not(Py_LIMITED_API))]
+    pub unsafe fn type_object(&'static mut self) -> &'static mut ffi::PyTypeObject {
+        &mut self.layout
+    }
+
+    /// The type object, the layout is returned until the heap type gets created
+    #[cfg(Py_LIMITED_API)]
+    pub unsafe fn type_object(&'static mut self) -> &'static mut ffi::PyTypeObject {
+        if self.heap_type.is_null() {
+            &mut *(&mut self.layout as *mut ffi::PyTypeObjectLayout as *mut ffi::PyTypeObject)
+        } else {
+            &mut *self.heap_type
+        }
+    }
+}
+
+const UNINITIALIZED: usize = 0;
+const INITIALIZING: usize = 1;
+const INITIALIZED: usize = 2;
+
+/// Once-cell of the type initialization, the initialization runs with the GIL held.
+///
+/// The initializing thread may release the GIL, i.e. while python code runs,
+/// other threads wait for the result without holding the GIL. Re-entrant calls
+/// of the initializing thread return immediately, so class attributes can create
+/// instances of the class. A failed initialization is retried by the next call.
+pub struct TypeInitCell {
+    state: AtomicUsize,
+    owner: AtomicUsize,
+}
+
+impl TypeInitCell {
+    pub const INIT: TypeInitCell = TypeInitCell {
+        state: ATOMIC_USIZE_INIT,
+        owner: ATOMIC_USIZE_INIT,
+    };
+
+    /// Checks whether the initialization has completed, the GIL is not required
+    pub fn is_initialized(&self) -> bool {
+        self.state.load(Ordering::Acquire) == INITIALIZED
+    }
+
+    /// Runs `init` unless the initialization has completed already
+    pub fn get_or_init<F>(&self, py: Python, init: F) -> PyResult<()>
+    where
+        F: FnOnce() -> PyResult<()>,
+    {
+        let this_thread = thread_id();
+        loop {
+            match self.state.compare_exchange(
+                UNINITIALIZED,
+                INITIALIZING,
+                Ordering::Acquire,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => break,
+                Err(INITIALIZED) => return Ok(()),
+                Err(_) => {
+                    if self.owner.load(Ordering::Relaxed) == this_thread {
+                        return Ok(());
+                    }
+                    // the initializing thread released the GIL, wait without it
+                    py.allow_threads(|| self.wait());
+                }
+            }
+        }
+
+        self.owner.store(this_thread, Ordering::Relaxed);
+        let guard = InitGuard(self);
+        let result = init();
+        mem::forget(guard);
+
+        let state = if result.is_ok() {
+            INITIALIZED
+        } else {
+            UNINITIALIZED
+        };
+        self.finish(state);
+        result
+    }
+
+    /// Blocks until the initializing thread finishes
+    fn wait(&self) {
+        let &(ref lock, ref finished) = init_waiters();
+        let mut guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+        while self.state.load(Ordering::Acquire) == INITIALIZING {
+            guard = finished.wait(guard).unwrap_or_else(|e| e.into_inner());
+        }
+    }
+
+    /// Stores the result of the initialization and wakes up the waiting threads,
+    /// the state is changed under the lock so the wake up is not missed
+    fn finish(&self, state: usize) {
+        let &(ref lock, ref finished) = init_waiters();
+        let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+        self.owner.store(0, Ordering::Relaxed);
+        self.state.store(state, Ordering::Release);
+        finished.notify_all();
+    }
+}
+
+/// Resets the cell if the initialization panics, so the waiting threads do not hang
+struct InitGuard<'a>(&'a TypeInitCell);
+
+impl<'a> Drop for InitGuard<'a> {
+    fn drop(&mut self) {
+        self.0.finish(UNINITIALIZED);
+    }
+}
+
+/// Lock and condition variable shared by all `TypeInitCell`s,
+/// the cells are statics and the initializations rarely wait
+fn init_waiters() -> &'static (Mutex<()>, Condvar) {
+    static INIT: Once = ONCE_INIT;
+    static mut WAITERS: *const (Mutex<()>, Condvar) = 0 as *const _;
+    unsafe {
+        INIT.call_once(|| {
+            WAITERS = Box::into_raw(Box::new((Mutex::new(()), Condvar::new())));
+        });
+        &*WAITERS
+    }
+}
+
+/// Address of a thread local, unique for the running threads
+fn thread_id() -> usize {
+    thread_local!(static ID: u8 = 0);
+    ID.with(|id| id as *const u8 as usize)
+}
+
+/// Checks `Py_TPFLAGS_READY` of the type object
+pub fn is_type_ready(ty: *mut ffi::PyTypeObject) -> bool {
+    unsafe { ffi::PyType_HasFeature(ty, ffi::Py_TPFLAGS_READY) != 0 }
+}
+
+#[cfg(not(Py_LIMITED_API))]
+unsafe fn type_alloc(ty: *mut ffi::PyTypeObject) -> ffi::allocfunc {
+    (*ty).tp_alloc.unwrap_or(ffi::PyType_GenericAlloc)
+}
+
+#[cfg(Py_LIMITED_API)]
+unsafe fn type_alloc(ty: *mut ffi::PyTypeObject) -> ffi::allocfunc {
+    let alloc = ffi::PyType_GetSlot(ty, ffi::Py_tp_alloc);
+    if alloc.is_null() {
+        ffi::PyType_GenericAlloc
+    } else {
+        mem::transmute::<*mut c_void, ffi::allocfunc>(alloc)
+    }
+}
+
+#[cfg(not(Py_LIMITED_API))]
+pub(crate) unsafe fn type_free(ty: *mut ffi::PyTypeObject) -> Option<ffi::freefunc> {
+    (*ty).tp_free
+}
+
+#[cfg(Py_LIMITED_API)]
+pub(crate) unsafe fn type_free(ty: *mut ffi::PyTypeObject) -> Option<ffi::freefunc> {
+    let free = ffi::PyType_GetSlot(ty, ffi::Py_tp_free);
+    if free.is_null() {
+        None
+    } else {
+        Some(mem::transmute::<*mut c_void, ffi::freefunc>(free))
+    }
+}
+
+/// Base type of the class, the slots of the type objects are only available
+/// for the heap types with the limited API
+#[cfg(not(Py_LIMITED_API))]
+pub(crate) unsafe fn type_base(ty: *mut ffi::PyTypeObject) -> *mut ffi::PyTypeObject {
+    (*ty).tp_base
+}
+
+#[cfg(Py_LIMITED_API)]
+pub(crate) unsafe fn type_base(ty: *mut ffi::PyTypeObject) -> *mut ffi::PyTypeObject {
+    ffi::PyType_GetSlot(ty, ffi::Py_tp_base) as *mut ffi::PyTypeObject
+}
+
 /// Special object that is used for python object creation.
 /// `pyo3` library automatically creates this object for class `__new__` method.
 /// Behavior is undefined if constructor of custom class does not initialze
@@ -136,8 +382,41 @@ impl PyRawObject {
         tp_ptr: *mut ffi::PyTypeObject,
         curr_ptr: *mut ffi::PyTypeObject,
     ) -> PyResult<PyRawObject> {
-        let alloc = (*curr_ptr).tp_alloc.unwrap_or(ffi::PyType_GenericAlloc);
-        let ptr = alloc(curr_ptr, 0);
+        let ptr = type_alloc(curr_ptr)(curr_ptr, 0);
+
+        if !ptr.is_null() {
+            Ok(PyRawObject {
+                ptr: ptr,
+                tp_ptr: tp_ptr,
+                curr_ptr: curr_ptr,
+                // initialized: 0,
+            })
+        } else {
+            PyErr::fetch(py).into()
+        }
+    }
+
+    /// Allocates new object of class `T` or its python subclass `curr_ptr`.
+    /// `args` and `kwargs` are passed to `__new__` of the native base type,
+    /// i.e. `dict` or `BaseException`.
+    #[must_use]
+    pub unsafe fn new_with_args<T: PyTypeInfo>(
+        py: Python,
+        curr_ptr: *mut ffi::PyTypeObject,
+        args: *mut ffi::PyObject,
+        kwargs: *mut ffi::PyObject,
+    ) -> PyResult<PyRawObject> {
+        let ptr = alloc_instance(T::native_base_type(), curr_ptr, args, kwargs);
+
+        // `curr_ptr` is the type of the per-interpreter class or its subclass
+        #[cfg(Py_3)]
+        let tp_ptr = if is_per_interpreter::<T>() {
+            interpreter_base::<T>(curr_ptr).unwrap_or(curr_ptr)
+        } else {
+            T::type_object()
+        };
+        #[cfg(not(Py_3))]
+        let tp_ptr = T::type_object();
 
         if !ptr.is_null() {
             Ok(PyRawObject {
@@ -200,6 +479,37 @@ impl PyRawObject {
     }
 }
 
+/// Allocates an instance of `curr_ptr`.
+///
+/// Classes derived from a native type other than `object` have to be
+/// initialized by `tp_new` of the native type, i.e. `dict` allocates its hash table.
+#[cfg(not(Py_LIMITED_API))]
+unsafe fn alloc_instance(
+    native_base: *mut ffi::PyTypeObject,
+    curr_ptr: *mut ffi::PyTypeObject,
+    args: *mut ffi::PyObject,
+    kwargs: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    if native_base != &mut ffi::PyBaseObject_Type as *mut _ {
+        if let Some(new) = (*native_base).tp_new {
+            return new(curr_ptr, args, kwargs);
+        }
+    }
+    type_alloc(curr_ptr)(curr_ptr, 0)
+}
+
+/// Allocates an instance of `curr_ptr`, with the limited API
+/// `object` is the only supported native base type.
+#[cfg(Py_LIMITED_API)]
+unsafe fn alloc_instance(
+    _native_base: *mut ffi::PyTypeObject,
+    curr_ptr: *mut ffi::PyTypeObject,
+    _args: *mut ffi::PyObject,
+    _kwargs: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    type_alloc(curr_ptr)(curr_ptr, 0)
+}
+
 impl IntoPyPointer for PyRawObject {
     fn into_ptr(self) -> *mut ffi::PyObject {
         // TODO: panic if not all types initialized
@@ -244,29 +554,46 @@ where
         }
     }
 
-    default unsafe fn alloc(_py: Python) -> PyResult<*mut ffi::PyObject> {
+    default unsafe fn alloc(py: Python) -> PyResult<*mut ffi::PyObject> {
         // TODO: remove this
-        T::init_type();
+        T::init_type()?;
 
-        let tp_ptr = T::type_object();
-        let alloc = (*tp_ptr).tp_alloc.unwrap_or(ffi::PyType_GenericAlloc);
-        let obj = alloc(tp_ptr, 0);
+        let args = ffi::PyTuple_New(0);
+        let obj = alloc_instance(T::native_base_type(), T::type_object(), args, std::ptr::null_mut());
+        ffi::Py_DECREF(args);
 
-        Ok(obj)
+        if obj.is_null() {
+            Err(PyErr::fetch(py))
+        } else {
+            Ok(obj)
+        }
     }
 
     #[cfg(Py_3)]
     default unsafe fn dealloc(py: Python, obj: *mut ffi::PyObject) {
         Self::drop(py, obj);
 
-        if ffi::PyObject_CallFinalizerFromDealloc(obj) < 0 {
+        #[cfg(not(Py_LIMITED_API))]
+        {
+            if ffi::PyObject_CallFinalizerFromDealloc(obj) < 0 {
+                return;
+            }
+        }
+
+        if dealloc_native_base::<T>(obj) {
             return;
         }
 
-        match (*T::type_object()).tp_free {
-            Some(free) => free(obj as *mut ::c_void),
+        // the type of per-interpreter classes may not be reachable anymore
+        let ty = ffi::Py_TYPE(obj);
+        match type_free(ty) {
+            // `PyType_FromSpec` fills `tp_free`, the heap types
+            // are released after the memory is freed
+            Some(free) => {
+                free(obj as *mut ::c_void);
+                release_heap_type::<T>(ty);
+            }
             None => {
-                let ty = ffi::Py_TYPE(obj);
                 if ffi::PyType_IS_GC(ty) != 0 {
                     ffi::PyObject_GC_Del(obj as *mut ::c_void);
                 } else {
@@ -286,7 +613,11 @@ where
     default unsafe fn dealloc(py: Python, obj: *mut ffi::PyObject) {
         Self::drop(py, obj);
 
-        match (*T::type_object()).tp_free {
+        if dealloc_native_base::<T>(obj) {
+            return;
+        }
+
+        match type_free(T::type_object()) {
             Some(free) => free(obj as *mut ::c_void),
             None => {
                 let ty = ffi::Py_TYPE(obj);
@@ -306,12 +637,87 @@ where
     }
 }
 
+/// Instances own a reference to the heap types created by pyo3, taken by
+/// `PyType_GenericAlloc`. `subtype_dealloc` releases it for the python subclasses,
+/// except for the subclasses of heap types since python 3.8. The abi3 build
+/// can run on a newer interpreter, the version is checked at runtime.
+#[cfg(Py_3)]
+unsafe fn release_heap_type<T>(ty: *mut ffi::PyTypeObject)
+where
+    T: PyObjectAlloc<T> + PyTypeInfo,
+{
+    if !is_heap_type::<T>() {
+        return;
+    }
+    let dealloc = ffi::PyType_GetSlot(ty, ffi::Py_tp_dealloc);
+    if interpreter_version() >= (3, 8) || dealloc == tp_dealloc_callback::<T> as *mut c_void {
+        ffi::Py_DECREF(ty as *mut ffi::PyObject);
+    }
+}
+
+/// `(major, minor)` version of the running interpreter, parsed from `Py_GetVersion()` once
+#[cfg(Py_3)]
+fn interpreter_version() -> (u32, u32) {
+    static VERSION: AtomicUsize = ATOMIC_USIZE_INIT;
+
+    let version = VERSION.load(Ordering::Acquire);
+    if version != 0 {
+        return (version as u32 >> 8, version as u32 & 0xff);
+    }
+    let text = unsafe { CStr::from_ptr(ffi::Py_GetVersion()) }.to_string_lossy();
+    let mut numbers = text.split(|c: char| !c.is_digit(10)).map(|n| n.parse().unwrap_or(0));
+    let major: u32 = numbers.next().unwrap_or(0);
+    let minor: u32 = numbers.next().unwrap_or(0);
+    VERSION.store(((major << 8) | minor) as usize, Ordering::Release);
+    (major, minor)
+}
+
+/// Calls `tp_dealloc` of the native base type, if it is not `object`.
+/// The native `tp_dealloc` releases the native part and frees the memory.
+#[cfg(not(Py_LIMITED_API))]
+unsafe fn dealloc_native_base<T>(obj: *mut ffi::PyObject) -> bool
+where
+    T: PyObjectAlloc<T> + PyTypeInfo,
+{
+    let native_base = T::native_base_type();
+    if native_base == &mut ffi::PyBaseObject_Type as *mut _ {
+        return false;
+    }
+    match (*native_base).tp_dealloc {
+        Some(dealloc) => {
+            let ty = ffi::Py_TYPE(obj);
+            dealloc(obj);
+
+            // see `PyObjectAlloc::dealloc`
+            #[cfg(Py_3)]
+            release_heap_type::<T>(ty);
+            #[cfg(not(Py_3))]
+            {
+                if ffi::PyType_HasFeature(ty, ffi::Py_TPFLAGS_HEAPTYPE) != 0 {
+                    ffi::Py_DECREF(ty as *mut ffi::PyObject);
+                }
+            }
+            true
+        }
+        None => false,
+    }
+}
+
+/// `object` is the only supported native base type with the limited API
+#[cfg(Py_LIMITED_API)]
+unsafe fn dealloc_native_base<T>(_obj: *mut ffi::PyObject) -> bool {
+    false
+}
+
 /// Trait implemented by Python object types that have a corresponding type object.
 pub trait PyTypeObject {
-    /// Initialize type object
-    fn init_type();
+    /// Initialize type object, the initialization error is raised as python exception
+    fn init_type() -> PyResult<()>;
 
     /// Retrieves the type object for this Python object type.
+    ///
+    /// Panics if the type object can not be initialized,
+    /// the fallible callers use `init_type()` first.
     fn type_object() -> Py<PyType>;
 
     /// Create PyRawObject which can be initialized with rust value
@@ -320,7 +726,7 @@ pub trait PyTypeObject {
     where
         Self: Sized + PyObjectAlloc<Self> + PyTypeInfo,
     {
-        <Self as PyTypeObject>::init_type();
+        <Self as PyTypeObject>::init_type()?;
 
         unsafe {
             let ptr = <Self as PyObjectAlloc<Self>>::alloc(py)?;
@@ -339,47 +745,126 @@ where
     T: PyObjectAlloc<T> + PyTypeInfo,
 {
     #[inline]
-    default fn init_type() {
-        unsafe {
-            if ((*<T>::type_object()).tp_flags & ffi::Py_TPFLAGS_READY) == 0 {
-                // automatically initialize the class on-demand
-                let gil = Python::acquire_gil();
-                let py = gil.python();
-
-                initialize_type::<T>(py, None).expect(
-                    format!("An error occurred while initializing class {}", T::NAME).as_ref(),
-                );
+    default fn init_type() -> PyResult<()> {
+        // per-interpreter types are created by `PyModule::add_class()`
+        #[cfg(Py_3)]
+        {
+            if is_per_interpreter::<T>() {
+                return ::modulestate::interpreter_type_object::<T>().map(|_| ());
+            }
+        }
+        if let Some(storage) = unsafe { T::type_storage() } {
+            if storage.init.is_initialized() {
+                return Ok(());
             }
         }
+
+        // automatically initialize the class on-demand
+        let gil = Python::acquire_gil();
+        initialize_type::<T>(gil.python(), None)
     }
 
     #[inline]
     default fn type_object() -> Py<PyType> {
-        <T as PyTypeObject>::init_type();
+        if let Err(e) = <T as PyTypeObject>::init_type() {
+            let gil = Python::acquire_gil();
+            e.print(gil.python());
+            panic!("An error occurred while initializing class {}", T::NAME);
+        }
         PyType::new::<T>()
     }
 }
 
 /// Register new type in python object system.
+///
+/// The type is initialized once, guarded by the `TypeInitCell` of the type object storage.
+/// Returns immediately if the type is initialized already.
 pub fn initialize_type<'p, T>(py: Python<'p>, module_name: Option<&str>) -> PyResult<()>
 where
     T: PyObjectAlloc<T> + PyTypeInfo,
 {
-    // type name
-    let name = match module_name {
+    if is_per_interpreter::<T>() {
+        return Err(PyErr::new::<exc::TypeError, _>(format!(
+            "{}: per-interpreter class must be added to a module with PyModule::add_class()",
+            T::NAME
+        )));
+    }
+
+    match unsafe { T::type_storage() } {
+        Some(storage) => storage.init.get_or_init(py, || {
+            storage
+                .ready
+                .get_or_init(py, || prepare_type::<T>(py, module_name))?;
+            // class attributes, created after the type is ready,
+            // so a class attribute can be an instance of the class
+            py_class_attributes::<T>(py, unsafe { T::type_object() })
+        }),
+        // types without storage are not guarded
+        None => {
+            if is_type_ready(unsafe { T::type_object() }) {
+                Ok(())
+            } else {
+                prepare_type::<T>(py, module_name)?;
+                py_class_attributes::<T>(py, unsafe { T::type_object() })
+            }
+        }
+    }
+}
+
+/// Fills the type object layout and registers the type object
+fn prepare_type<T>(py: Python, module_name: Option<&str>) -> PyResult<()>
+where
+    T: PyObjectAlloc<T> + PyTypeInfo,
+{
+    let type_object = stage_type::<T>(module_name)?;
+    unsafe { ready_type::<T>(py, type_object) }
+}
+
+/// Creates the heap type of a per-interpreter class, returns a new reference.
+///
+/// The type object layout is filled once, it is the template of the heap types
+/// of all interpreters. The class attributes are not created yet.
+#[cfg(Py_3)]
+pub(crate) fn create_interpreter_type<T>(py: Python, module_name: &str) -> PyResult<*mut ffi::PyObject>
+where
+    T: PyObjectAlloc<T> + PyTypeInfo,
+{
+    let storage = match unsafe { T::type_storage() } {
+        Some(storage) => storage,
+        None => {
+            return Err(PyErr::new::<exc::TypeError, _>(format!(
+                "{}: the type object storage is required",
+                T::NAME
+            )))
+        }
+    };
+    storage
+        .ready
+        .get_or_init(py, || stage_type::<T>(Some(module_name)).map(|_| ()))?;
+    unsafe { create_heap_type::<T>(py, &mut storage.layout) }
+}
+
+/// Fills the type object layout of the class
+fn stage_type<T>(module_name: Option<&str>) -> PyResult<&'static mut ffi::PyTypeObjectLayout>
+where
+    T: PyObjectAlloc<T> + PyTypeInfo,
+{
+    // type name, explicit module name of the class takes precedence
+    let name = match T::MODULE.or(module_name) {
         Some(module_name) => CString::new(format!("{}.{}", module_name, T::NAME)),
         None => CString::new(T::NAME),
     };
     let name = name.expect("Module name/type name must not contain NUL byte")
         .into_raw();
 
-    let type_object: &mut ffi::PyTypeObject = unsafe { &mut *T::type_object() };
-    let base_type_object: &mut ffi::PyTypeObject =
-        unsafe { &mut *<T::BaseType as PyTypeInfo>::type_object() };
+    let type_object: &'static mut ffi::PyTypeObjectLayout = unsafe { type_layout::<T>()? };
+    let base_type_object = unsafe { <T::BaseType as PyTypeInfo>::type_object() };
+
+    check_native_base::<T>()?;
 
     type_object.tp_name = name;
-    type_object.tp_doc = T::DESCRIPTION.as_ptr() as *const _;
-    type_object.tp_base = base_type_object;
+    type_object.tp_doc = type_doc::<T>();
+    type_object.tp_base = base_type_object as *mut ffi::PyTypeObject as *mut _;
 
     // dealloc
     type_object.tp_dealloc = Some(tp_dealloc_callback::<T>);
@@ -387,6 +872,12 @@ where
     // type size
     type_object.tp_basicsize = <T as PyTypeInfo>::SIZE as ffi::Py_ssize_t;
 
+    // the item size of `type` is inherited by metaclasses
+    #[cfg(not(Py_LIMITED_API))]
+    {
+        type_object.tp_itemsize = unsafe { (*T::native_base_type()).tp_itemsize };
+    }
+
     let mut offset = T::SIZE;
     // weakref support (check py3cls::py_class::impl_class)
     if T::FLAGS & PY_TYPE_FLAG_WEAKREF != 0 {
@@ -412,6 +903,9 @@ where
     // basic methods
     <T as class::basic::PyObjectProtocolImpl>::tp_as_object(type_object);
 
+    // derived methods, i.e. #[class(repr, eq)]
+    class::derived::tp_as_object::<T>(type_object);
+
     // number methods
     if let Some(meth) = <T as class::number::PyNumberProtocolImpl>::tp_as_number() {
         type_object.tp_as_number = Box::into_raw(Box::new(meth));
@@ -422,6 +916,9 @@ where
     // mapping methods
     if let Some(meth) = <T as class::mapping::PyMappingProtocolImpl>::tp_as_mapping() {
         type_object.tp_as_mapping = Box::into_raw(Box::new(meth));
+    } else if let Some(meth) = <T as class::sequence::PySequenceProtocolImpl>::tp_as_mapping() {
+        // sequence subscription with slice keys
+        type_object.tp_as_mapping = Box::into_raw(Box::new(meth));
     } else {
         type_object.tp_as_mapping = ::std::ptr::null_mut()
     }
@@ -475,24 +972,406 @@ where
 
     // set type flags
     py_class_flags::<T>(type_object);
-    if type_object.tp_base != unsafe {
+    if type_object.tp_base as *mut ffi::PyTypeObject != unsafe {
         &ffi::PyBaseObject_Type as *const ffi::PyTypeObject as *mut ffi::PyTypeObject
     } {
         type_object.tp_flags |= ffi::Py_TPFLAGS_HEAPTYPE
     }
 
-    // register type object
+    Ok(type_object)
+}
+
+/// The classes are created with `PyType_FromSpec` with the limited API,
+/// per-interpreter classes are heap types too
+#[cfg(Py_3)]
+fn is_heap_type<T: PyTypeInfo>() -> bool {
+    cfg!(Py_LIMITED_API) || is_per_interpreter::<T>()
+}
+
+/// Checks `PY_TYPE_FLAG_PER_INTERPRETER`, python 2 has one type object for all interpreters
+#[cfg(Py_3)]
+pub fn is_per_interpreter<T: PyTypeInfo>() -> bool {
+    T::FLAGS & PY_TYPE_FLAG_PER_INTERPRETER != 0
+}
+
+#[cfg(not(Py_3))]
+pub fn is_per_interpreter<T: PyTypeInfo>() -> bool {
+    false
+}
+
+/// `type_object()` of the classes, the types of per-interpreter classes are owned
+/// by the module state. `PyTypeObject::init_type()` checks the type is available.
+///
+/// The `'static` lifetime is required by `PyTypeInfo::type_object()`, it does not hold
+/// for per-interpreter classes: the type is released with the state of its module,
+/// so the reference must not be kept after the module can be freed. Panics if the class
+/// is not added to a module of the current interpreter, the fallible callers use
+/// `interpreter_type_object()` through `init_type()` first.
+#[doc(hidden)]
+pub unsafe fn class_type_object<T: PyTypeInfo>() -> &'static mut ffi::PyTypeObject {
+    #[cfg(Py_3)]
+    {
+        if is_per_interpreter::<T>() {
+            match ::modulestate::interpreter_type_object::<T>() {
+                Ok(ty) => return &mut *ty,
+                Err(_) => panic!(
+                    "{}: per-interpreter class is not added to a module of the current \
+                     interpreter, init_type() reports the error",
+                    T::NAME
+                ),
+            }
+        }
+    }
+    match T::type_storage() {
+        Some(storage) => storage.type_object(),
+        None => unreachable!(),
+    }
+}
+
+/// `is_instance()` of the classes, the type of a per-interpreter class
+/// is found in the bases of the type of the instance
+#[doc(hidden)]
+pub fn class_is_instance<T: PyTypeInfo>(ptr: *mut ffi::PyObject) -> bool {
     unsafe {
-        if ffi::PyType_Ready(type_object) == 0 {
-            Ok(())
-        } else {
-            PyErr::fetch(py).into()
+        #[cfg(Py_3)]
+        {
+            if is_per_interpreter::<T>() {
+                return interpreter_base::<T>(ffi::Py_TYPE(ptr)).is_some();
+            }
+        }
+        ffi::PyObject_TypeCheck(ptr, T::type_object()) != 0
+    }
+}
+
+/// `is_exact_instance()` of the classes
+#[doc(hidden)]
+pub fn class_is_exact_instance<T: PyTypeInfo>(ptr: *mut ffi::PyObject) -> bool {
+    unsafe {
+        #[cfg(Py_3)]
+        {
+            if is_per_interpreter::<T>() {
+                return is_interpreter_type::<T>(ffi::Py_TYPE(ptr));
+            }
+        }
+        ffi::Py_TYPE(ptr) == T::type_object() as *mut _
+    }
+}
+
+/// The heap type of the per-interpreter class `T` in the bases of `ty`,
+/// the python subclasses of the heap type are heap types too
+#[cfg(Py_3)]
+unsafe fn interpreter_base<T: PyTypeInfo>(
+    mut ty: *mut ffi::PyTypeObject,
+) -> Option<*mut ffi::PyTypeObject> {
+    while !ty.is_null() && ffi::PyType_HasFeature(ty, ffi::Py_TPFLAGS_HEAPTYPE) != 0 {
+        if is_interpreter_type::<T>(ty) {
+            return Some(ty);
+        }
+        ty = type_base(ty);
+    }
+    None
+}
+
+/// The heap types of the per-interpreter class `T` are identified by `tp_dealloc`
+#[cfg(Py_3)]
+unsafe fn is_interpreter_type<T: PyTypeInfo>(ty: *mut ffi::PyTypeObject) -> bool {
+    ffi::PyType_HasFeature(ty, ffi::Py_TPFLAGS_HEAPTYPE) != 0
+        && ffi::PyType_GetSlot(ty, ffi::Py_tp_dealloc) == tp_dealloc_callback::<T> as *mut c_void
+}
+
+/// The type object layout filled by the protocol implementations
+#[cfg(not(Py_LIMITED_API))]
+unsafe fn type_layout<T: PyTypeInfo>() -> PyResult<&'static mut ffi::PyTypeObjectLayout> {
+    if !is_per_interpreter::<T>() {
+        return Ok(T::type_object());
+    }
+    match T::type_storage() {
+        Some(storage) => Ok(&mut storage.layout),
+        None => Err(PyErr::new::<exc::TypeError, _>(format!(
+            "{}: the type object storage is required",
+            T::NAME
+        ))),
+    }
+}
+
+#[cfg(Py_LIMITED_API)]
+unsafe fn type_layout<T: PyTypeInfo>() -> PyResult<&'static mut ffi::PyTypeObjectLayout> {
+    match T::type_storage() {
+        Some(storage) => Ok(&mut storage.layout),
+        None => Err(PyErr::new::<exc::TypeError, _>(format!(
+            "{}: the type object storage is required",
+            T::NAME
+        ))),
+    }
+}
+
+/// The Rust struct is placed behind the native base object, its offset is checked
+/// against the runtime size of the native base. Only fixed size native types can be
+/// extended, except `type`: the members of the type objects are placed behind
+/// `tp_basicsize` of the metaclass, so the Rust struct does not overlap them.
+#[cfg(not(Py_LIMITED_API))]
+fn check_native_base<T: PyTypeInfo>() -> PyResult<()> {
+    unsafe {
+        let native = &*T::native_base_type();
+        let var_sized = native.tp_itemsize != 0 && !is_type_type(native);
+        if var_sized || native.tp_basicsize as isize > T::OFFSET {
+            return Err(PyErr::new::<exc::TypeError, _>(format!(
+                "{}: extending native type {} is not supported",
+                T::NAME,
+                CStr::from_ptr(native.tp_name).to_string_lossy()
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(Py_LIMITED_API))]
+fn is_type_type(ty: &ffi::PyTypeObject) -> bool {
+    ty as *const ffi::PyTypeObject == unsafe { &ffi::PyType_Type as *const _ }
+}
+
+/// The layout of the native types is not a part of the limited API
+#[cfg(Py_LIMITED_API)]
+fn check_native_base<T: PyTypeInfo>() -> PyResult<()> {
+    if unsafe { T::native_base_type() } != unsafe { &mut ffi::PyBaseObject_Type as *mut _ } {
+        return Err(PyErr::new::<exc::TypeError, _>(format!(
+            "{}: extending native types is not supported by the limited API",
+            T::NAME
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(not(Py_LIMITED_API))]
+unsafe fn ready_type<T: PyTypeInfo>(
+    py: Python,
+    type_object: &mut ffi::PyTypeObjectLayout,
+) -> PyResult<()> {
+    if ffi::PyType_Ready(type_object) != 0 {
+        return PyErr::fetch(py).into();
+    }
+    Ok(())
+}
+
+/// The type object is the heap type created from the type object layout
+#[cfg(Py_LIMITED_API)]
+unsafe fn ready_type<T: PyTypeInfo>(
+    py: Python,
+    type_object: &mut ffi::PyTypeObjectLayout,
+) -> PyResult<()> {
+    let heap_type = create_heap_type::<T>(py, type_object)?;
+    match T::type_storage() {
+        Some(storage) => storage.heap_type = heap_type as *mut ffi::PyTypeObject,
+        None => unreachable!(),
+    }
+    Ok(())
+}
+
+/// Creates the heap type from the slots of the type object layout, returns a new reference
+#[cfg(Py_3)]
+unsafe fn create_heap_type<T: PyTypeInfo>(
+    py: Python,
+    type_object: &mut ffi::PyTypeObjectLayout,
+) -> PyResult<*mut ffi::PyObject> {
+    if !type_object.tp_as_buffer.is_null() {
+        return Err(PyErr::new::<exc::TypeError, _>(format!(
+            "{}: the buffer protocol is not supported by heap types",
+            T::NAME
+        )));
+    }
+    if type_object.tp_weaklistoffset != 0 || type_object.tp_dictoffset != 0 {
+        return Err(PyErr::new::<exc::TypeError, _>(format!(
+            "{}: weakref and dict support are not supported by heap types",
+            T::NAME
+        )));
+    }
+
+    // the slots are copied into the heap type
+    let mut slots = type_slots(type_object);
+    let mut spec = ffi::PyType_Spec {
+        name: type_object.tp_name,
+        basicsize: type_object.tp_basicsize as c_int,
+        itemsize: type_object.tp_itemsize as c_int,
+        flags: (type_object.tp_flags & !ffi::Py_TPFLAGS_HEAPTYPE) as c_uint,
+        slots: slots.as_mut_ptr(),
+    };
+
+    let bases = ffi::PyTuple_Pack(1, type_object.tp_base as *mut ffi::PyObject);
+    if bases.is_null() {
+        return PyErr::fetch(py).into();
+    }
+    let heap_type = ffi::PyType_FromSpecWithBases(&mut spec, bases);
+    ffi::Py_DECREF(bases);
+    if heap_type.is_null() {
+        return PyErr::fetch(py).into();
+    }
+    Ok(heap_type)
+}
+
+/// `PyType_FromSpec` slots of the type object layout
+#[cfg(Py_3)]
+unsafe fn type_slots(type_object: &ffi::PyTypeObjectLayout) -> Vec<ffi::PyType_Slot> {
+    let mut slots = Vec::new();
+
+    macro_rules! slot {
+        ($slot:ident, $value:expr) => {
+            if let Some(value) = $value {
+                slots.push(ffi::PyType_Slot {
+                    slot: ffi::$slot,
+                    pfunc: value as *mut c_void,
+                });
+            }
+        };
+    }
+
+    slot!(Py_tp_dealloc, type_object.tp_dealloc);
+    slot!(Py_tp_repr, type_object.tp_repr);
+    slot!(Py_tp_hash, type_object.tp_hash);
+    slot!(Py_tp_call, type_object.tp_call);
+    slot!(Py_tp_str, type_object.tp_str);
+    slot!(Py_tp_getattro, type_object.tp_getattro);
+    slot!(Py_tp_setattro, type_object.tp_setattro);
+    slot!(Py_tp_traverse, type_object.tp_traverse);
+    slot!(Py_tp_clear, type_object.tp_clear);
+    slot!(Py_tp_richcompare, type_object.tp_richcompare);
+    slot!(Py_tp_iter, type_object.tp_iter);
+    slot!(Py_tp_iternext, type_object.tp_iternext);
+    slot!(Py_tp_descr_get, type_object.tp_descr_get);
+    slot!(Py_tp_descr_set, type_object.tp_descr_set);
+    slot!(Py_tp_init, type_object.tp_init);
+    slot!(Py_tp_new, type_object.tp_new);
+    slot!(Py_tp_free, type_object.tp_free);
+    slot!(Py_tp_finalize, type_object.tp_finalize);
+
+    let pointers = [
+        (ffi::Py_tp_doc, type_object.tp_doc as *mut c_void),
+        (ffi::Py_tp_methods, type_object.tp_methods as *mut c_void),
+        (ffi::Py_tp_getset, type_object.tp_getset as *mut c_void),
+    ];
+    for &(slot, pfunc) in pointers.iter() {
+        if !pfunc.is_null() {
+            slots.push(ffi::PyType_Slot { slot, pfunc });
+        }
+    }
+
+    if let Some(nb) = type_object.tp_as_number.as_ref() {
+        slot!(Py_nb_add, nb.nb_add);
+        slot!(Py_nb_subtract, nb.nb_subtract);
+        slot!(Py_nb_multiply, nb.nb_multiply);
+        slot!(Py_nb_remainder, nb.nb_remainder);
+        slot!(Py_nb_divmod, nb.nb_divmod);
+        slot!(Py_nb_power, nb.nb_power);
+        slot!(Py_nb_negative, nb.nb_negative);
+        slot!(Py_nb_positive, nb.nb_positive);
+        slot!(Py_nb_absolute, nb.nb_absolute);
+        slot!(Py_nb_bool, nb.nb_bool);
+        slot!(Py_nb_invert, nb.nb_invert);
+        slot!(Py_nb_lshift, nb.nb_lshift);
+        slot!(Py_nb_rshift, nb.nb_rshift);
+        slot!(Py_nb_and, nb.nb_and);
+        slot!(Py_nb_xor, nb.nb_xor);
+        slot!(Py_nb_or, nb.nb_or);
+        slot!(Py_nb_int, nb.nb_int);
+        slot!(Py_nb_float, nb.nb_float);
+        slot!(Py_nb_inplace_add, nb.nb_inplace_add);
+        slot!(Py_nb_inplace_subtract, nb.nb_inplace_subtract);
+        slot!(Py_nb_inplace_multiply, nb.nb_inplace_multiply);
+        slot!(Py_nb_inplace_remainder, nb.nb_inplace_remainder);
+        slot!(Py_nb_inplace_power, nb.nb_inplace_power);
+        slot!(Py_nb_inplace_lshift, nb.nb_inplace_lshift);
+        slot!(Py_nb_inplace_rshift, nb.nb_inplace_rshift);
+        slot!(Py_nb_inplace_and, nb.nb_inplace_and);
+        slot!(Py_nb_inplace_xor, nb.nb_inplace_xor);
+        slot!(Py_nb_inplace_or, nb.nb_inplace_or);
+        slot!(Py_nb_floor_divide, nb.nb_floor_divide);
+        slot!(Py_nb_true_divide, nb.nb_true_divide);
+        slot!(Py_nb_inplace_floor_divide, nb.nb_inplace_floor_divide);
+        slot!(Py_nb_inplace_true_divide, nb.nb_inplace_true_divide);
+        slot!(Py_nb_index, nb.nb_index);
+        slot!(Py_nb_matrix_multiply, nb.nb_matrix_multiply);
+        slot!(Py_nb_inplace_matrix_multiply, nb.nb_inplace_matrix_multiply);
+    }
+
+    if let Some(sq) = type_object.tp_as_sequence.as_ref() {
+        slot!(Py_sq_length, sq.sq_length);
+        slot!(Py_sq_concat, sq.sq_concat);
+        slot!(Py_sq_repeat, sq.sq_repeat);
+        slot!(Py_sq_item, sq.sq_item);
+        slot!(Py_sq_ass_item, sq.sq_ass_item);
+        slot!(Py_sq_contains, sq.sq_contains);
+        slot!(Py_sq_inplace_concat, sq.sq_inplace_concat);
+        slot!(Py_sq_inplace_repeat, sq.sq_inplace_repeat);
+    }
+
+    if let Some(mp) = type_object.tp_as_mapping.as_ref() {
+        slot!(Py_mp_length, mp.mp_length);
+        slot!(Py_mp_subscript, mp.mp_subscript);
+        slot!(Py_mp_ass_subscript, mp.mp_ass_subscript);
+    }
+
+    if let Some(am) = type_object.tp_as_async.as_ref() {
+        slot!(Py_am_await, am.am_await);
+        slot!(Py_am_aiter, am.am_aiter);
+        slot!(Py_am_anext, am.am_anext);
+    }
+
+    slots.push(ffi::PyType_Slot {
+        slot: 0,
+        pfunc: ptr::null_mut(),
+    });
+    slots
+}
+
+#[cfg(not(Py_LIMITED_API))]
+pub(crate) fn py_class_attributes<T: PyTypeInfo>(
+    py: Python,
+    type_object: *mut ffi::PyTypeObject,
+) -> PyResult<()> {
+    let mut modified = false;
+
+    for def in <T as class::methods::PyMethodsProtocolImpl>::py_methods() {
+        if let PyMethodDefType::ClassAttribute(ref attr) = *def {
+            let name = CString::new(attr.name).expect("Class attribute name must not contain NUL byte");
+            let value = (attr.meth)(py)?;
+            unsafe {
+                if ffi::PyDict_SetItemString((*type_object).tp_dict, name.as_ptr(), value.as_ptr()) != 0 {
+                    return PyErr::fetch(py).into();
+                }
+            }
+            modified = true;
         }
     }
+
+    if modified {
+        unsafe { ffi::PyType_Modified(type_object) };
+    }
+    Ok(())
+}
+
+/// Heap types accept new attributes, `tp_dict` is not a part of the limited API
+#[cfg(Py_LIMITED_API)]
+pub(crate) fn py_class_attributes<T: PyTypeInfo>(
+    py: Python,
+    type_object: *mut ffi::PyTypeObject,
+) -> PyResult<()> {
+    let type_object = type_object as *mut ffi::PyObject;
+
+    for def in <T as class::methods::PyMethodsProtocolImpl>::py_methods() {
+        if let PyMethodDefType::ClassAttribute(ref attr) = *def {
+            let name = CString::new(attr.name).expect("Class attribute name must not contain NUL byte");
+            let value = (attr.meth)(py)?;
+            unsafe {
+                if ffi::PyObject_SetAttrString(type_object, name.as_ptr(), value.as_ptr()) != 0 {
+                    return PyErr::fetch(py).into();
+                }
+            }
+        }
+    }
+    Ok(())
 }
 
 #[cfg(Py_3)]
-fn async_methods<T>(type_info: &mut ffi::PyTypeObject) {
+fn async_methods<T>(type_info: &mut ffi::PyTypeObjectLayout) {
     if let Some(meth) = <T as class::async::PyAsyncProtocolImpl>::tp_as_async() {
         type_info.tp_as_async = Box::into_raw(Box::new(meth));
     } else {
@@ -501,12 +1380,13 @@ fn async_methods<T>(type_info: &mut ffi::PyTypeObject) {
 }
 
 #[cfg(not(Py_3))]
-fn async_methods<T>(_type_info: &mut ffi::PyTypeObject) {}
+fn async_methods<T>(_type_info: &mut ffi::PyTypeObjectLayout) {}
 
 unsafe extern "C" fn tp_dealloc_callback<T>(obj: *mut ffi::PyObject)
 where
     T: PyObjectAlloc<T>,
 {
+    #[cfg(not(Py_LIMITED_API))]
     debug!(
         "DEALLOC: {:?} - {:?}",
         obj,
@@ -518,7 +1398,7 @@ where
 }
 
 #[cfg(Py_3)]
-fn py_class_flags<T: PyTypeInfo>(type_object: &mut ffi::PyTypeObject) {
+fn py_class_flags<T: PyTypeInfo>(type_object: &mut ffi::PyTypeObjectLayout) {
     if type_object.tp_traverse != None || type_object.tp_clear != None
         || T::FLAGS & PY_TYPE_FLAG_GC != 0
     {
@@ -532,7 +1412,7 @@ fn py_class_flags<T: PyTypeInfo>(type_object: &mut ffi::PyTypeObject) {
 }
 
 #[cfg(not(Py_3))]
-fn py_class_flags<T: PyTypeInfo>(type_object: &mut ffi::PyTypeObject) {
+fn py_class_flags<T: PyTypeInfo>(type_object: &mut ffi::PyTypeObjectLayout) {
     if type_object.tp_traverse != None || type_object.tp_clear != None
         || T::FLAGS & PY_TYPE_FLAG_GC != 0
     {
@@ -549,6 +1429,35 @@ fn py_class_flags<T: PyTypeInfo>(type_object: &mut ffi::PyTypeObject) {
     }
 }
 
+/// Class doc string, prefixed with the signature of `__new__` for `__text_signature__`.
+#[cfg(Py_3)]
+fn type_doc<T: PyTypeInfo>() -> *const c_char {
+    for def in <T as class::methods::PyMethodsProtocolImpl>::py_methods() {
+        if let PyMethodDefType::New(ref def) = *def {
+            match class::methods::split_text_signature(def.ml_doc) {
+                (Some(sig), _) if sig.starts_with("__new__(") => {
+                    let doc = format!(
+                        "{}{}\n--\n\n{}",
+                        T::NAME,
+                        &sig["__new__".len()..],
+                        T::DESCRIPTION
+                    );
+                    return CString::new(doc.trim_right_matches('\0'))
+                        .expect("Class doc string must not contain NUL byte")
+                        .into_raw();
+                }
+                _ => (),
+            }
+        }
+    }
+    T::DESCRIPTION.as_ptr() as *const _
+}
+
+#[cfg(not(Py_3))]
+fn type_doc<T: PyTypeInfo>() -> *const c_char {
+    T::DESCRIPTION.as_ptr() as *const _
+}
+
 #[cfg_attr(feature = "cargo-clippy", allow(type_complexity))]
 fn py_class_method_defs<T>() -> PyResult<(
     Option<ffi::newfunc>,
@@ -608,6 +1517,12 @@ fn py_class_method_defs<T>() -> PyResult<(
     for def in <T as class::descr::PyDescrProtocolImpl>::methods() {
         defs.push(def.as_method_def());
     }
+    for def in <T as class::pickle::PyPickleProtocolImpl>::methods() {
+        defs.push(def.as_method_def());
+    }
+    for def in <T as class::copy::PyCloneProtocolImpl>::methods() {
+        defs.push(def.as_method_def());
+    }
 
     py_class_async_methods::<T>(&mut defs);
 
@@ -626,12 +1541,14 @@ fn py_class_async_methods<T>(_defs: &mut Vec<ffi::PyMethodDef>) {}
 
 fn py_class_properties<T>() -> Vec<ffi::PyGetSetDef> {
     let mut defs = HashMap::new();
+    let mut deleters = Vec::new();
 
     for def in <T as class::methods::PyMethodsProtocolImpl>::py_methods()
         .iter()
         .chain(<T as class::methods::PyPropMethodsProtocolImpl>::py_methods().iter())
     {
         match *def {
+            PyMethodDefType::Deleter(ref deleter) => deleters.push(deleter),
             PyMethodDefType::Getter(ref getter) => {
                 let name = getter.name.to_string();
                 if !defs.contains_key(&name) {
@@ -652,5 +1569,13 @@ fn py_class_properties<T>() -> Vec<ffi::PyGetSetDef> {
         }
     }
 
+    // deleters share the `set` slot with setters
+    for deleter in deleters {
+        let name = deleter.name.to_string();
+        let def = defs.entry(name).or_insert(ffi::PyGetSetDef_INIT);
+        let setter = def.set;
+        deleter.copy_to(def, setter);
+    }
+
     defs.values().cloned().collect()
 }
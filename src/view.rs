@@ -0,0 +1,659 @@
+// Copyright (c) 2017-present PyO3 Project and Contributors
+
+//! Python views of Rust collections.
+//!
+//! `IntoPyObject` for `Vec` and `HashMap` copies the whole collection into
+//! a Python `list` or `dict`. `PyVecView<T>` and `PyMapView<K, V>` instead keep a shared
+//! reference to the Rust collection and convert elements on access.
+//! The Python types `VecView` and `MapView` are shared by all element types.
+//!
+//! Example:
+//! ```rust
+//! # extern crate pyo3;
+//! # use std::sync::{Arc, RwLock};
+//! # use pyo3::prelude::*;
+//! # use pyo3::view::PyVecView;
+//! # fn main() {
+//! # let gil = Python::acquire_gil();
+//! # let py = gil.python();
+//! let data = Arc::new(RwLock::new(vec![1, 2, 3]));
+//! let view = PyVecView::new(py, data.clone()).unwrap();
+//!
+//! // changes to the Rust vector are visible through the view
+//! data.write().unwrap().push(4);
+//! # }
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::os::raw::c_long;
+use std::sync::{Arc, RwLock, RwLockReadGuard};
+
+use callback::{cb_convert, PyObjectCallbackConverter};
+use class::iter::{PyIterIterProtocol, PyIterProtocol};
+use class::mapping::{PyMappingGetItemProtocol, PyMappingLenProtocol, PyMappingProtocol};
+use class::methods::{PyMethodDef, PyMethodDefType, PyMethodType, PyMethodsProtocolImpl};
+use class::sequence::{PySequenceContainsProtocol, PySequenceGetItemProtocol,
+                      PySequenceLenProtocol, PySequenceProtocol};
+use conversion::{FromPyObject, IntoPyObject, ToPyObject};
+use err::{PyErr, PyResult};
+use instance::{AsPyRef, Py, PyObjectWithToken, PyToken};
+use object::PyObject;
+use objectprotocol::ObjectProtocol;
+use objects::{exc, PyList, PyObjectRef, PyTuple, SliceOrIndex};
+use python::{IntoPyPointer, Python, ToPyPointer};
+use pythonrun::GILPool;
+use typeob::PyTypeInfo;
+use ffi;
+
+/// Rust sequence of `T` elements that can be exposed to Python with `PyVecView<T>`.
+pub trait SequenceSource<T>: Send + 'static {
+    /// Number of elements in the sequence.
+    fn len(&self, py: Python) -> PyResult<usize>;
+
+    /// Converts the element at `idx` to a Python object.
+    ///
+    /// Raises `IndexError` if `idx` is out of range, the source may
+    /// shrink between `len` and `get_item` calls.
+    fn get_item(&self, py: Python, idx: usize) -> PyResult<PyObject>;
+}
+
+/// Rust mapping of `K` keys to `V` values that can be exposed to Python with `PyMapView<K, V>`.
+pub trait MappingSource<K, V>: Send + 'static {
+    /// Number of items in the mapping.
+    fn len(&self, py: Python) -> PyResult<usize>;
+
+    /// Looks up `key`, returns `None` if the key is not present.
+    ///
+    /// Raises `TypeError` if `key` can not be converted to `K`.
+    fn get_item(&self, py: Python, key: &PyObjectRef) -> PyResult<Option<PyObject>>;
+
+    /// Converts all keys of the mapping to Python objects.
+    fn keys(&self, py: Python) -> PyResult<Vec<PyObject>>;
+
+    /// Converts all items of the mapping to Python objects.
+    fn items(&self, py: Python) -> PyResult<Vec<(PyObject, PyObject)>> {
+        let mut items = Vec::new();
+        for key in self.keys(py)? {
+            if let Some(value) = self.get_item(py, key.as_ref(py))? {
+                items.push((key, value));
+            }
+        }
+        Ok(items)
+    }
+}
+
+fn read_lock<T>(lock: &RwLock<T>) -> PyResult<RwLockReadGuard<T>> {
+    lock.read().map_err(|_| {
+        PyErr::new::<exc::RuntimeError, _>("collection lock is poisoned")
+    })
+}
+
+impl<T> SequenceSource<T> for Arc<Vec<T>>
+where
+    T: ToPyObject + Send + Sync + 'static,
+{
+    fn len(&self, _py: Python) -> PyResult<usize> {
+        Ok(Vec::len(self))
+    }
+
+    fn get_item(&self, py: Python, idx: usize) -> PyResult<PyObject> {
+        match self.get(idx) {
+            Some(item) => Ok(item.to_object(py)),
+            None => Err(PyErr::new::<exc::IndexError, _>("index out of range")),
+        }
+    }
+}
+
+impl<T> SequenceSource<T> for Arc<RwLock<Vec<T>>>
+where
+    T: ToPyObject + Send + Sync + 'static,
+{
+    fn len(&self, _py: Python) -> PyResult<usize> {
+        Ok(read_lock(self)?.len())
+    }
+
+    fn get_item(&self, py: Python, idx: usize) -> PyResult<PyObject> {
+        match read_lock(self)?.get(idx) {
+            Some(item) => Ok(item.to_object(py)),
+            None => Err(PyErr::new::<exc::IndexError, _>("index out of range")),
+        }
+    }
+}
+
+/// Slice of a Python class instance, created by `PyVecView::from_owner()`.
+struct OwnedSlice<O, T: 'static> {
+    owner: Py<O>,
+    project: fn(&O) -> &[T],
+}
+
+impl<O, T> SequenceSource<T> for OwnedSlice<O, T>
+where
+    O: PyTypeInfo + 'static,
+    T: ToPyObject + 'static,
+{
+    fn len(&self, py: Python) -> PyResult<usize> {
+        Ok((self.project)(self.owner.as_ref(py)).len())
+    }
+
+    fn get_item(&self, py: Python, idx: usize) -> PyResult<PyObject> {
+        match (self.project)(self.owner.as_ref(py)).get(idx) {
+            Some(item) => Ok(item.to_object(py)),
+            None => Err(PyErr::new::<exc::IndexError, _>("index out of range")),
+        }
+    }
+}
+
+macro_rules! map_source_impl(
+    ($map:ident, $($bounds:tt)*) => {
+        impl<K, V> MappingSource<K, V> for Arc<RwLock<$map<K, V>>>
+        where
+            K: for<'a> FromPyObject<'a> + ToPyObject + $($bounds)* + Send + Sync + 'static,
+            V: ToPyObject + Send + Sync + 'static,
+        {
+            fn len(&self, _py: Python) -> PyResult<usize> {
+                Ok(read_lock(self)?.len())
+            }
+
+            fn get_item(&self, py: Python, key: &PyObjectRef) -> PyResult<Option<PyObject>> {
+                let key: K = key.extract()?;
+                Ok(read_lock(self)?.get(&key).map(|v| v.to_object(py)))
+            }
+
+            fn keys(&self, py: Python) -> PyResult<Vec<PyObject>> {
+                Ok(read_lock(self)?.keys().map(|k| k.to_object(py)).collect())
+            }
+
+            fn items(&self, py: Python) -> PyResult<Vec<(PyObject, PyObject)>> {
+                Ok(read_lock(self)?
+                    .iter()
+                    .map(|(k, v)| (k.to_object(py), v.to_object(py)))
+                    .collect())
+            }
+        }
+    }
+);
+
+map_source_impl!(HashMap, Eq + Hash);
+map_source_impl!(BTreeMap, Ord);
+
+/// `SequenceSource<T>` without the element type, the source of the `VecView` class
+trait RawSequence: Send {
+    fn len(&self, py: Python) -> PyResult<usize>;
+    fn get_item(&self, py: Python, idx: usize) -> PyResult<PyObject>;
+}
+
+struct TypedSequence<T>(Box<SequenceSource<T>>);
+
+impl<T: 'static> RawSequence for TypedSequence<T> {
+    fn len(&self, py: Python) -> PyResult<usize> {
+        self.0.len(py)
+    }
+
+    fn get_item(&self, py: Python, idx: usize) -> PyResult<PyObject> {
+        self.0.get_item(py, idx)
+    }
+}
+
+/// Read-only Python sequence of `T` elements backed by a Rust collection.
+///
+/// Elements are converted to Python objects on access, slicing returns a new `list`.
+pub struct PyVecView<T> {
+    view: Py<VecView>,
+    _item: PhantomData<fn() -> T>,
+}
+
+impl<T: 'static> PyVecView<T> {
+    /// Creates a view of a shared Rust vector,
+    /// i.e. `Arc<Vec<T>>` or `Arc<RwLock<Vec<T>>>`.
+    pub fn new<S>(py: Python, source: S) -> PyResult<PyVecView<T>>
+    where
+        S: SequenceSource<T>,
+    {
+        PyVecView::from_source(py, Box::new(source))
+    }
+
+    /// Creates a view of a slice owned by a Python class instance.
+    /// The view keeps `owner` alive.
+    pub fn from_owner<O>(py: Python, owner: Py<O>, project: fn(&O) -> &[T]) -> PyResult<PyVecView<T>>
+    where
+        O: PyTypeInfo + 'static,
+        T: ToPyObject,
+    {
+        PyVecView::from_source(
+            py,
+            Box::new(OwnedSlice {
+                owner: owner,
+                project: project,
+            }),
+        )
+    }
+
+    /// Creates a view of a custom sequence source.
+    pub fn from_source(py: Python, source: Box<SequenceSource<T>>) -> PyResult<PyVecView<T>> {
+        let view = Py::new(py, |token| VecView {
+            source: Box::new(TypedSequence(source)),
+            token: token,
+        })?;
+        Ok(PyVecView {
+            view: view,
+            _item: PhantomData,
+        })
+    }
+}
+
+impl<T> ToPyObject for PyVecView<T> {
+    fn to_object(&self, py: Python) -> PyObject {
+        self.view.to_object(py)
+    }
+}
+
+impl<T> IntoPyObject for PyVecView<T> {
+    fn into_object(self, py: Python) -> PyObject {
+        self.view.into_object(py)
+    }
+}
+
+impl<T> ToPyPointer for PyVecView<T> {
+    fn as_ptr(&self) -> *mut ffi::PyObject {
+        self.view.as_ptr()
+    }
+}
+
+impl<T> IntoPyPointer for PyVecView<T> {
+    fn into_ptr(self) -> *mut ffi::PyObject {
+        self.view.into_ptr()
+    }
+}
+
+/// Python class of `PyVecView<T>`
+struct VecView {
+    source: Box<RawSequence>,
+    token: PyToken,
+}
+
+impl PyObjectWithToken for VecView {
+    #[inline]
+    fn py(&self) -> Python {
+        self.token.py()
+    }
+}
+
+pyobject_rusttype!(VecView, "VecView", "Read-only sequence backed by a Rust collection");
+
+impl<'p> PySequenceProtocol<'p> for VecView {
+    fn __len__(&'p self) -> <VecView as PySequenceLenProtocol<'p>>::Result {
+        self.source.len(self.py())
+    }
+
+    fn __getitem__(
+        &'p self,
+        key: <VecView as PySequenceGetItemProtocol<'p>>::Index,
+    ) -> <VecView as PySequenceGetItemProtocol<'p>>::Result {
+        let py = self.py();
+        let len = self.source.len(py)?;
+        let indices = key.indices(len as c_long)?;
+
+        match key {
+            SliceOrIndex::Index(_) => self.source.get_item(py, indices.start as usize),
+            SliceOrIndex::Slice(_) => {
+                let items = (0..indices.slicelength)
+                    .map(|i| {
+                        self.source
+                            .get_item(py, (indices.start + i * indices.step) as usize)
+                    })
+                    .collect::<PyResult<Vec<_>>>()?;
+                Ok(PyList::new(py, &items).to_object(py))
+            }
+        }
+    }
+}
+
+impl<'p> PySequenceLenProtocol<'p> for VecView {
+    type Result = PyResult<usize>;
+}
+
+impl<'p> PySequenceGetItemProtocol<'p> for VecView {
+    type Index = SliceOrIndex<'p>;
+    type Success = PyObject;
+    type Result = PyResult<PyObject>;
+}
+
+/// `MappingSource<K, V>` without the key and value types, the source of the `MapView` class
+trait RawMapping: Send {
+    fn len(&self, py: Python) -> PyResult<usize>;
+    fn get_item(&self, py: Python, key: &PyObjectRef) -> PyResult<Option<PyObject>>;
+    fn keys(&self, py: Python) -> PyResult<Vec<PyObject>>;
+    fn items(&self, py: Python) -> PyResult<Vec<(PyObject, PyObject)>>;
+}
+
+struct TypedMapping<K, V>(Box<MappingSource<K, V>>);
+
+impl<K: 'static, V: 'static> RawMapping for TypedMapping<K, V> {
+    fn len(&self, py: Python) -> PyResult<usize> {
+        self.0.len(py)
+    }
+
+    fn get_item(&self, py: Python, key: &PyObjectRef) -> PyResult<Option<PyObject>> {
+        self.0.get_item(py, key)
+    }
+
+    fn keys(&self, py: Python) -> PyResult<Vec<PyObject>> {
+        self.0.keys(py)
+    }
+
+    fn items(&self, py: Python) -> PyResult<Vec<(PyObject, PyObject)>> {
+        self.0.items(py)
+    }
+}
+
+/// Read-only Python mapping of `K` keys to `V` values backed by a Rust map.
+///
+/// Values are converted to Python objects on access, keys of another type raise `TypeError`.
+/// Iteration converts all keys of the map when the iterator is created,
+/// `keys()`, `values()` and `items()` return lists.
+pub struct PyMapView<K, V> {
+    view: Py<MapView>,
+    _items: PhantomData<fn() -> (K, V)>,
+}
+
+impl<K: 'static, V: 'static> PyMapView<K, V> {
+    /// Creates a view of a shared Rust map,
+    /// i.e. `Arc<RwLock<HashMap<K, V>>>` or `Arc<RwLock<BTreeMap<K, V>>>`.
+    pub fn new<S>(py: Python, source: S) -> PyResult<PyMapView<K, V>>
+    where
+        S: MappingSource<K, V>,
+    {
+        PyMapView::from_source(py, Box::new(source))
+    }
+
+    /// Creates a view of a custom mapping source.
+    pub fn from_source(py: Python, source: Box<MappingSource<K, V>>) -> PyResult<PyMapView<K, V>> {
+        let view = Py::new(py, |token| MapView {
+            source: Box::new(TypedMapping(source)),
+            token: token,
+        })?;
+        Ok(PyMapView {
+            view: view,
+            _items: PhantomData,
+        })
+    }
+}
+
+impl<K, V> ToPyObject for PyMapView<K, V> {
+    fn to_object(&self, py: Python) -> PyObject {
+        self.view.to_object(py)
+    }
+}
+
+impl<K, V> IntoPyObject for PyMapView<K, V> {
+    fn into_object(self, py: Python) -> PyObject {
+        self.view.into_object(py)
+    }
+}
+
+impl<K, V> ToPyPointer for PyMapView<K, V> {
+    fn as_ptr(&self) -> *mut ffi::PyObject {
+        self.view.as_ptr()
+    }
+}
+
+impl<K, V> IntoPyPointer for PyMapView<K, V> {
+    fn into_ptr(self) -> *mut ffi::PyObject {
+        self.view.into_ptr()
+    }
+}
+
+/// Python class of `PyMapView<K, V>`
+struct MapView {
+    source: Box<RawMapping>,
+    token: PyToken,
+}
+
+impl PyObjectWithToken for MapView {
+    #[inline]
+    fn py(&self) -> Python {
+        self.token.py()
+    }
+}
+
+pyobject_rusttype!(MapView, "MapView", "Read-only mapping backed by a Rust map");
+
+impl<'p> PyMappingProtocol<'p> for MapView {
+    fn __len__(&'p self) -> <MapView as PyMappingLenProtocol<'p>>::Result {
+        self.source.len(self.py())
+    }
+
+    fn __getitem__(
+        &'p self,
+        key: <MapView as PyMappingGetItemProtocol<'p>>::Key,
+    ) -> <MapView as PyMappingGetItemProtocol<'p>>::Result {
+        match self.source.get_item(self.py(), key)? {
+            Some(value) => Ok(value),
+            None => Err(PyErr::new::<exc::KeyError, _>(key.to_object(self.py()))),
+        }
+    }
+}
+
+impl<'p> PyMappingLenProtocol<'p> for MapView {
+    type Result = PyResult<usize>;
+}
+
+impl<'p> PyMappingGetItemProtocol<'p> for MapView {
+    type Key = &'p PyObjectRef;
+    type Success = PyObject;
+    type Result = PyResult<PyObject>;
+}
+
+// `in` is dispatched through `sq_contains`
+impl<'p> PySequenceProtocol<'p> for MapView {
+    fn __contains__(
+        &'p self,
+        item: <MapView as PySequenceContainsProtocol<'p>>::Item,
+    ) -> <MapView as PySequenceContainsProtocol<'p>>::Result {
+        let py = self.py();
+        // same as `dict`, a key of another type is not present
+        match self.source.get_item(py, item) {
+            Ok(value) => Ok(value.is_some()),
+            Err(ref err) if err.is_instance::<exc::TypeError>(py) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+impl<'p> PySequenceContainsProtocol<'p> for MapView {
+    type Item = &'p PyObjectRef;
+    type Result = PyResult<bool>;
+}
+
+impl<'p> PyIterProtocol<'p> for MapView {
+    fn __iter__(&'p mut self) -> <MapView as PyIterIterProtocol<'p>>::Result {
+        let py = self.py();
+        let keys = self.source.keys(py)?;
+        Ok(PyList::new(py, &keys).call_method0("__iter__")?.to_object(py))
+    }
+}
+
+impl<'p> PyIterIterProtocol<'p> for MapView {
+    type Success = PyObject;
+    type Result = PyResult<PyObject>;
+}
+
+impl PyMethodsProtocolImpl for MapView {
+    fn py_methods() -> &'static [PyMethodDefType] {
+        static METHODS: &'static [PyMethodDefType] = &[
+            PyMethodDefType::Method(PyMethodDef {
+                ml_name: "keys",
+                ml_meth: PyMethodType::PyCFunction(map_view_keys),
+                ml_flags: ffi::METH_NOARGS,
+                ml_doc: "Returns a list of the keys.\0",
+            }),
+            PyMethodDefType::Method(PyMethodDef {
+                ml_name: "values",
+                ml_meth: PyMethodType::PyCFunction(map_view_values),
+                ml_flags: ffi::METH_NOARGS,
+                ml_doc: "Returns a list of the values.\0",
+            }),
+            PyMethodDefType::Method(PyMethodDef {
+                ml_name: "items",
+                ml_meth: PyMethodType::PyCFunction(map_view_items),
+                ml_flags: ffi::METH_NOARGS,
+                ml_doc: "Returns a list of the (key, value) pairs.\0",
+            }),
+            PyMethodDefType::Method(PyMethodDef {
+                ml_name: "get",
+                ml_meth: PyMethodType::PyCFunction(map_view_get),
+                ml_flags: ffi::METH_VARARGS,
+                ml_doc: "Returns the value for key if key is in the mapping, else default.\0",
+            }),
+        ];
+        METHODS
+    }
+}
+
+/// Calls `f` with the `MapView` instance and converts the result.
+unsafe fn map_view_call<F, R>(slf: *mut ffi::PyObject, f: F) -> *mut ffi::PyObject
+where
+    F: FnOnce(Python, &MapView) -> PyResult<R>,
+    R: IntoPyObject,
+{
+    let _pool = GILPool::new();
+    let py = Python::assume_gil_acquired();
+    let slf = py.from_borrowed_ptr::<MapView>(slf);
+    cb_convert(PyObjectCallbackConverter, py, f(py, slf))
+}
+
+unsafe extern "C" fn map_view_keys(
+    slf: *mut ffi::PyObject,
+    _args: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    map_view_call(slf, |py, slf| {
+        Ok(PyList::new(py, &slf.source.keys(py)?).to_object(py))
+    })
+}
+
+unsafe extern "C" fn map_view_values(
+    slf: *mut ffi::PyObject,
+    _args: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    map_view_call(slf, |py, slf| {
+        let values: Vec<PyObject> = slf.source
+            .items(py)?
+            .into_iter()
+            .map(|(_, v)| v)
+            .collect();
+        Ok(PyList::new(py, &values).to_object(py))
+    })
+}
+
+unsafe extern "C" fn map_view_items(
+    slf: *mut ffi::PyObject,
+    _args: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    map_view_call(slf, |py, slf| {
+        Ok(PyList::new(py, &slf.source.items(py)?).to_object(py))
+    })
+}
+
+unsafe extern "C" fn map_view_get(
+    slf: *mut ffi::PyObject,
+    args: *mut ffi::PyObject,
+) -> *mut ffi::PyObject {
+    map_view_call(slf, |py, slf| {
+        let args = py.from_borrowed_ptr::<PyTuple>(args);
+        if args.len() < 1 || args.len() > 2 {
+            return Err(PyErr::new::<exc::TypeError, _>(format!(
+                "get expected 1 or 2 arguments, got {}",
+                args.len()
+            )));
+        }
+        match slf.source.get_item(py, args.get_item(0))? {
+            Some(value) => Ok(value),
+            None if args.len() == 2 => Ok(args.get_item(1).to_object(py)),
+            None => Ok(py.None()),
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use std::sync::{Arc, RwLock};
+
+    use super::{PyMapView, PyVecView, SequenceSource};
+    use objects::{exc, PyDict};
+    use python::Python;
+
+    #[test]
+    fn test_vec_view() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let data = Arc::new(RwLock::new(vec![1, 2, 3, 4]));
+        let view = PyVecView::new(py, data.clone()).unwrap();
+        let d = PyDict::new(py);
+        d.set_item("view", view).unwrap();
+
+        py.run("assert len(view) == 4 and view[0] == 1 and view[-1] == 4", None, Some(d))
+            .unwrap();
+        py.run("assert view[1:3] == [2, 3] and view[::-2] == [4, 2]", None, Some(d))
+            .unwrap();
+        py.run("assert list(view) == [1, 2, 3, 4] and 3 in view", None, Some(d))
+            .unwrap();
+        assert!(py.run("view[4]", None, Some(d)).is_err());
+
+        data.write().unwrap().push(5);
+        py.run("assert len(view) == 5 and view[4] == 5", None, Some(d))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_vec_source_index() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let data = Arc::new(vec![1, 2]);
+        assert!(data.get_item(py, 1).is_ok());
+        let err = data.get_item(py, 2).unwrap_err();
+        assert!(err.is_instance::<exc::IndexError>(py));
+    }
+
+    #[test]
+    fn test_map_view() {
+        let gil = Python::acquire_gil();
+        let py = gil.python();
+
+        let mut map = HashMap::new();
+        map.insert("a".to_owned(), 1);
+        map.insert("b".to_owned(), 2);
+        let data = Arc::new(RwLock::new(map));
+        let view = PyMapView::new(py, data.clone()).unwrap();
+        let d = PyDict::new(py);
+        d.set_item("view", view).unwrap();
+
+        py.run("assert len(view) == 2 and view['a'] == 1 and view['b'] == 2", None, Some(d))
+            .unwrap();
+        py.run("assert 'a' in view and 'c' not in view and 1 not in view", None, Some(d))
+            .unwrap();
+        py.run("assert sorted(view) == ['a', 'b']", None, Some(d))
+            .unwrap();
+        assert!(py.run("view['c']", None, Some(d)).is_err());
+        let err = py.run("view[1]", None, Some(d)).unwrap_err();
+        assert!(err.is_instance::<exc::TypeError>(py));
+
+        py.run("assert sorted(view.keys()) == ['a', 'b']", None, Some(d))
+            .unwrap();
+        py.run("assert sorted(view.values()) == [1, 2]", None, Some(d))
+            .unwrap();
+        py.run("assert sorted(view.items()) == [('a', 1), ('b', 2)]", None, Some(d))
+            .unwrap();
+        py.run("assert view.get('a') == 1 and view.get('c') is None", None, Some(d))
+            .unwrap();
+        py.run("assert view.get('c', 3) == 3 and dict(view) == {'a': 1, 'b': 2}", None, Some(d))
+            .unwrap();
+
+        data.write().unwrap().insert("c".to_owned(), 3);
+        py.run("assert view['c'] == 3", None, Some(d)).unwrap();
+    }
+}
@@ -2,9 +2,14 @@
 
 extern crate pyo3;
 
+use std::sync::atomic::{AtomicUsize, Ordering, ATOMIC_USIZE_INIT};
+use std::thread;
+use std::time::Duration;
+
 use pyo3::prelude::*;
 
 use pyo3::py::class as pyclass;
+use pyo3::py::methods as pymethods;
 
 #[macro_use]
 mod common;
@@ -78,3 +83,133 @@ fn empty_class_in_module() {
         "test_module.nested"
     );
 }
+
+#[pyclass(repr, str, eq, ord, hash)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct Version {
+    major: u32,
+    minor: u32,
+    token: PyToken,
+}
+
+impl std::fmt::Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+#[test]
+fn derived_methods() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let v1 = py.init(|t| Version { major: 1, minor: 2, token: t }).unwrap();
+    let v2 = py.init(|t| Version { major: 1, minor: 10, token: t }).unwrap();
+    let v3 = py.init(|t| Version { major: 1, minor: 2, token: t }).unwrap();
+
+    py_assert!(py, v1, "repr(v1) == 'Version { major: 1, minor: 2, token: PyToken }'");
+    py_assert!(py, v1, "str(v1) == '1.2'");
+
+    let d = PyDict::new(py);
+    d.set_item("v1", &v1).unwrap();
+    d.set_item("v2", &v2).unwrap();
+    d.set_item("v3", &v3).unwrap();
+    let run = |code: &str| py.run(code, None, Some(d)).unwrap();
+    run("assert v1 == v3 and not v1 != v3");
+    run("assert v1 != v2 and not v1 == v2");
+    run("assert v1 < v2 and v1 <= v2 and v2 > v1 and v2 >= v1 and v1 <= v3");
+    run("assert hash(v1) == hash(v3)");
+    run("assert len({v1, v2, v3}) == 2");
+    run("assert sorted([v2, v1]) == [v1, v2]");
+    // foreign types
+    run("assert v1 != 1 and not v1 == 1");
+    run("assert v1.__lt__(1) is NotImplemented");
+}
+
+#[pyclass(eq)]
+#[derive(PartialEq)]
+struct EqOnly {
+    value: i32,
+    token: PyToken,
+}
+
+#[test]
+fn derived_eq_only() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let a = py.init(|t| EqOnly { value: 1, token: t }).unwrap();
+    let b = py.init(|t| EqOnly { value: 1, token: t }).unwrap();
+    let d = PyDict::new(py);
+    d.set_item("a", &a).unwrap();
+    d.set_item("b", &b).unwrap();
+    py.run("assert a == b", None, Some(d)).unwrap();
+    py.run("assert a.__lt__(b) is NotImplemented", None, Some(d)).unwrap();
+    // without `hash`, objects that compare by value are unhashable
+    py.run("try:\n    hash(a)\nexcept TypeError:\n    pass\nelse:\n    assert False", None, Some(d))
+        .unwrap();
+}
+
+#[pyclass]
+struct SlowInit {}
+
+static SLOW_INIT_CALLS: AtomicUsize = ATOMIC_USIZE_INIT;
+
+#[pymethods]
+impl SlowInit {
+    #[classattr]
+    #[allow(non_snake_case)]
+    fn CALLS(py: Python) -> PyResult<usize> {
+        // other threads get the GIL while the type gets initialized
+        py.allow_threads(|| thread::sleep(Duration::from_millis(50)));
+        Ok(SLOW_INIT_CALLS.fetch_add(1, Ordering::SeqCst) + 1)
+    }
+}
+
+#[test]
+fn concurrent_type_initialization() {
+    let threads: Vec<_> = (0..4)
+        .map(|_| {
+            thread::spawn(|| {
+                let gil = Python::acquire_gil();
+                let py = gil.python();
+                let typeobj = py.get_type::<SlowInit>();
+                py_assert!(py, typeobj, "typeobj.CALLS == 1");
+            })
+        })
+        .collect();
+    for t in threads {
+        t.join().unwrap();
+    }
+    assert_eq!(SLOW_INIT_CALLS.load(Ordering::SeqCst), 1);
+}
+
+#[pyclass]
+struct RetryInit {}
+
+static RETRY_INIT_CALLS: AtomicUsize = ATOMIC_USIZE_INIT;
+
+#[pymethods]
+impl RetryInit {
+    #[classattr]
+    #[allow(non_snake_case)]
+    fn ATTEMPT(_py: Python) -> PyResult<usize> {
+        match RETRY_INIT_CALLS.fetch_add(1, Ordering::SeqCst) {
+            0 => Err(exc::ValueError::new("the first attempt fails")),
+            calls => Ok(calls + 1),
+        }
+    }
+}
+
+#[test]
+fn retry_type_initialization() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    // the error of the class attributes is raised, the type is ready already
+    let err = py.is_instance::<RetryInit, _>(&py.None()).unwrap_err();
+    assert!(err.is_instance::<exc::ValueError>(py));
+
+    let typeobj = py.get_type::<RetryInit>();
+    py_assert!(py, typeobj, "typeobj.ATTEMPT == 2");
+}
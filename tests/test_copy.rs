@@ -0,0 +1,182 @@
+#![feature(proc_macro, specialization)]
+
+extern crate pyo3;
+
+use pyo3::prelude::*;
+use std::collections::HashMap;
+
+use pyo3::py::class as pyclass;
+use pyo3::py::methods as pymethods;
+
+#[macro_use]
+mod common;
+
+#[pyclass(clone)]
+struct Copyable {
+    num: i32,
+    items: PyObject,
+    token: PyToken,
+}
+
+#[pymethods]
+impl Copyable {
+    #[getter]
+    fn num(&self) -> PyResult<i32> {
+        Ok(self.num)
+    }
+
+    #[setter]
+    fn set_num(&mut self, value: i32) -> PyResult<()> {
+        self.num = value;
+        Ok(())
+    }
+
+    #[getter]
+    fn items(&self) -> PyResult<PyObject> {
+        Ok(self.items.clone_ref(self.py()))
+    }
+}
+
+#[test]
+fn copy_and_deepcopy() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let items = PyList::new(py, &[1, 2]).to_object(py);
+    let inst = py.init(|t| Copyable {
+        num: 1,
+        items,
+        token: t,
+    }).unwrap();
+
+    py_run!(
+        py,
+        inst,
+        r#"
+import copy
+c = copy.copy(inst)
+assert type(c) is type(inst) and c is not inst
+assert c.num == 1
+c.num = 2
+assert inst.num == 1
+assert c.items is inst.items
+
+d = copy.deepcopy(inst)
+assert d.num == 1
+assert d.items == inst.items and d.items is not inst.items
+inst.items.append(3)
+assert d.items == [1, 2]
+"#
+    );
+}
+
+#[pyclass(clone)]
+#[derive(Clone)]
+struct Node {
+    _depth: u32,
+    token: PyToken,
+}
+
+#[test]
+fn deepcopy_memo() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let inst = py.init(|t| Node { _depth: 0, token: t }).unwrap();
+    py_run!(
+        py,
+        inst,
+        r#"
+import copy
+pair = copy.deepcopy([inst, inst])
+assert pair[0] is pair[1] and pair[0] is not inst
+"#
+    );
+}
+
+#[pyclass(clone)]
+struct Cyclic {
+    items: PyObject,
+    token: PyToken,
+}
+
+#[pymethods]
+impl Cyclic {
+    #[getter]
+    fn items(&self) -> PyResult<PyObject> {
+        Ok(self.items.clone_ref(self.py()))
+    }
+}
+
+#[test]
+fn deepcopy_self_reference() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let items = PyList::empty(py).to_object(py);
+    let inst = py.init(|t| Cyclic {
+        items: items.clone_ref(py),
+        token: t,
+    }).unwrap();
+    items.call_method1(py, "append", (&inst,)).unwrap();
+
+    py_run!(
+        py,
+        inst,
+        r#"
+import copy
+d = copy.deepcopy(inst)
+assert d is not inst and d.items is not inst.items
+assert d.items[0] is d
+"#
+    );
+}
+
+#[pyclass(clone)]
+struct Containers {
+    maybe: Option<PyObject>,
+    lists: Vec<Py<PyList>>,
+    named: HashMap<String, PyObject>,
+    token: PyToken,
+}
+
+#[pymethods]
+impl Containers {
+    #[getter]
+    fn items(&self) -> PyResult<(PyObject, PyObject, PyObject)> {
+        let py = self.py();
+        let maybe = self.maybe.as_ref().unwrap().clone_ref(py);
+        let lists = self.lists[0].clone_ref(py).into();
+        let named = self.named["a"].clone_ref(py);
+        Ok((maybe, lists, named))
+    }
+}
+
+#[test]
+fn copy_container_fields() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let mut named = HashMap::new();
+    named.insert("a".to_owned(), PyList::new(py, &[3]).to_object(py));
+    let inst = py.init(|t| Containers {
+        maybe: Some(PyList::new(py, &[1]).to_object(py)),
+        lists: vec![PyList::new(py, &[2]).into()],
+        named,
+        token: t,
+    }).unwrap();
+
+    py_run!(
+        py,
+        inst,
+        r#"
+import copy
+c = copy.copy(inst)
+assert all(a is b for a, b in zip(c.items, inst.items))
+
+d = copy.deepcopy(inst)
+assert d.items == ([1], [2], [3])
+assert all(a is not b for a, b in zip(d.items, inst.items))
+"#
+    );
+}
@@ -73,3 +73,76 @@ fn getter_setter_autogen() {
     py_run!(py, inst, "assert inst.num == 10");
     py_run!(py, inst, "inst.num = 20; assert inst.num == 20");
 }
+
+#[pyclass]
+struct PropOptions {
+    /// Value doc
+    #[prop(get, set, del, name = "value")]
+    inner: Option<i32>,
+    #[prop(get, doc = "Object doc")]
+    obj: PyObject,
+    #[prop(get, del)]
+    count: i32,
+    token: PyToken,
+}
+
+#[test]
+fn prop_options() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let obj = PyList::empty(py).to_object(py);
+    let inst = py.init(|t| PropOptions {
+        inner: Some(10),
+        obj: obj.clone_ref(py),
+        count: 1,
+        token: t,
+    }).unwrap();
+
+    py_run!(py, inst, "assert inst.value == 10");
+    py_run!(py, inst, "inst.value = 20; assert inst.value == 20");
+    py_run!(py, inst, "del inst.value; assert inst.value is None");
+    py_run!(py, inst, "inst.value = None; assert inst.value is None");
+    py_run!(py, inst, "assert type(inst).value.__doc__ == 'Value doc'");
+    py_run!(py, inst, "assert type(inst).obj.__doc__ == 'Object doc'");
+    py_run!(py, inst, "assert inst.obj is inst.obj");
+    py_expect_exception!(py, inst, "inst.obj = 1", AttributeError);
+    py_expect_exception!(py, inst, "del inst.obj", AttributeError);
+    py_expect_exception!(py, inst, "del inst.count", AttributeError);
+    py_run!(py, inst, "assert inst.count == 1");
+}
+
+#[pyclass]
+struct ClassWithDeleter {
+    data: Option<i32>,
+    token: PyToken,
+}
+
+#[pymethods]
+impl ClassWithDeleter {
+    #[getter]
+    fn get_data(&self) -> PyResult<Option<i32>> {
+        Ok(self.data)
+    }
+    #[setter]
+    fn set_data(&mut self, value: i32) -> PyResult<()> {
+        self.data = Some(value);
+        Ok(())
+    }
+    #[deleter]
+    fn del_data(&mut self) -> PyResult<()> {
+        self.data = None;
+        Ok(())
+    }
+}
+
+#[test]
+fn class_with_deleter() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let inst = py.init(|t| ClassWithDeleter { data: Some(1), token: t }).unwrap();
+
+    py_run!(py, inst, "inst.data = 5; assert inst.data == 5");
+    py_run!(py, inst, "del inst.data; assert inst.data is None");
+}
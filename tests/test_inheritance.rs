@@ -68,3 +68,156 @@ fn inheritance_with_new_methods() {
     let inst = typeobj.call(NoArgs, NoArgs).unwrap();
     py_run!(py, inst, "assert inst.val1 == 10; assert inst.val2 == 5");
 }
+
+#[pyclass(base=PyDict)]
+struct DictWithCounter {
+    counter: usize,
+}
+
+#[pymethods]
+impl DictWithCounter {
+    #[new]
+    fn __new__(obj: &PyRawObject) -> PyResult<()> {
+        obj.init(|_| DictWithCounter { counter: 0 })
+    }
+
+    fn set(&mut self, key: String, value: &PyObjectRef) -> PyResult<()> {
+        self.counter += 1;
+        self.get_base().set_item(key, value)
+    }
+
+    #[getter]
+    fn counter(&self) -> PyResult<usize> {
+        Ok(self.counter)
+    }
+}
+
+#[test]
+fn inherit_dict() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let typeobj = py.get_type::<DictWithCounter>();
+    py_run!(
+        py,
+        typeobj,
+        "d = typeobj(); d.set('a', 1); d['b'] = 2; assert d == {'a': 1, 'b': 2}; assert d.counter == 1; assert isinstance(d, dict)"
+    );
+}
+
+#[pyclass(base=pyo3::exc::Exception)]
+struct CustomError {
+    #[prop(get)]
+    code: i32,
+}
+
+#[pymethods]
+impl CustomError {
+    #[new]
+    fn __new__(obj: &PyRawObject, code: i32) -> PyResult<()> {
+        obj.init(|_| CustomError { code: code })
+    }
+}
+
+#[test]
+fn inherit_exception() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let typeobj = py.get_type::<CustomError>();
+    py_run!(
+        py,
+        typeobj,
+        "try:\n    raise typeobj(42)\nexcept Exception as e:\n    assert e.code == 42 and e.args == (42,)\nelse:\n    assert False"
+    );
+}
+
+#[cfg(Py_3_11)]
+#[test]
+fn inherit_exception_notes() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let size = std::mem::size_of::<pyo3::ffi::PyBaseExceptionObject>();
+    let basicsize = unsafe { (*(pyo3::ffi::PyExc_BaseException as *mut pyo3::ffi::PyTypeObject)).tp_basicsize };
+    assert_eq!(size as isize, basicsize);
+
+    let typeobj = py.get_type::<CustomError>();
+    py_run!(
+        py,
+        typeobj,
+        "e = typeobj(42)\ne.add_note('retried')\nassert e.__notes__ == ['retried'] and e.code == 42"
+    );
+}
+
+#[pyclass(base=pyo3::exc::OSError)]
+struct CustomOSError {
+    #[prop(get)]
+    retries: i32,
+}
+
+#[pymethods]
+impl CustomOSError {
+    #[new]
+    fn __new__(obj: &PyRawObject, errno: i32, _strerror: &PyString) -> PyResult<()> {
+        obj.init(|_| CustomOSError { retries: errno * 10 })
+    }
+}
+
+#[test]
+fn inherit_os_error() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let typeobj = py.get_type::<CustomOSError>();
+    py_run!(
+        py,
+        typeobj,
+        "try:
+    raise typeobj(2, 'missing')
+except OSError as e:
+    assert e.retries == 20 and e.errno == 2 and e.strerror == 'missing' and e.filename is None
+else:
+    assert False"
+    );
+}
+
+#[pyclass(base=PyType)]
+struct CountingMeta {
+    #[prop(get)]
+    name_len: usize,
+}
+
+#[pymethods]
+impl CountingMeta {
+    #[new]
+    fn __new__(obj: &PyRawObject, name: &PyString, _bases: &PyTuple, _dict: &PyDict) -> PyResult<()> {
+        let name_len = name.to_string()?.len();
+        obj.init(|_| CountingMeta { name_len: name_len })
+    }
+}
+
+#[test]
+fn inherit_type() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let meta = py.get_type::<CountingMeta>();
+    py_run!(
+        py,
+        meta,
+        "Point = meta('Point', (object,), {'__slots__': ('x', 'y')}); p = Point(); p.x = 1; assert p.x == 1; assert Point.name_len == 5; assert isinstance(Point, meta) and isinstance(Point, type)"
+    );
+}
+
+#[pyclass(base=PyTuple)]
+struct InvalidBase {}
+
+#[test]
+fn inherit_unsupported_native_type() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let err = pyo3::typeob::initialize_type::<InvalidBase>(py, None).unwrap_err();
+    assert!(err.is_instance::<exc::TypeError>(py));
+
+    // the initialization error is raised again, instead of a panic
+    let err = py.init(|_| InvalidBase {}).unwrap_err();
+    assert!(err.is_instance::<exc::TypeError>(py));
+}
@@ -2,6 +2,8 @@
 
 extern crate pyo3;
 
+use std::collections::BTreeMap;
+
 use pyo3::prelude::*;
 
 use pyo3::py::class as pyclass;
@@ -247,3 +249,389 @@ fn meth_args() {
     );
     // py_expect_exception!(py, inst, "inst.get_kwarg(100)", TypeError);
 }
+
+#[pyclass]
+struct FastCall {
+    token: PyToken,
+}
+
+#[pymethods]
+impl FastCall {
+    #[args(b = "2", c = "3")]
+    fn method(&self, a: i32, b: i32, c: i32) -> PyResult<(i32, i32, i32)> {
+        Ok((a, b, c))
+    }
+
+    #[classmethod]
+    fn class_method(cls: &PyType, a: i32, b: Option<i32>) -> PyResult<String> {
+        Ok(format!("{}.{}.{:?}", cls.name(), a, b))
+    }
+
+    #[staticmethod]
+    fn static_method(a: &str, b: &str) -> PyResult<String> {
+        Ok(format!("{}{}", a, b))
+    }
+}
+
+#[test]
+fn fastcall_args() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let inst = py.init(|t| FastCall { token: t }).unwrap();
+
+    py_assert!(py, inst, "inst.method(1) == (1, 2, 3)");
+    py_assert!(py, inst, "inst.method(1, 5) == (1, 5, 3)");
+    py_assert!(py, inst, "inst.method(1, c=7) == (1, 2, 7)");
+    py_assert!(py, inst, "inst.method(c=7, b=6, a=5) == (5, 6, 7)");
+    py_assert!(py, inst, "inst.method(1, None, c=None) == (1, 2, 3)");
+    py_assert!(py, inst, "inst.class_method(1) == 'FastCall.1.None'");
+    py_assert!(py, inst, "inst.class_method(b=2, a=1) == 'FastCall.1.Some(2)'");
+    py_assert!(py, inst, "type(inst).static_method('a', b='b') == 'ab'");
+
+    py_expect_exception!(py, inst, "inst.method()", TypeError);
+    py_expect_exception!(py, inst, "inst.method(1, 2, 3, 4)", TypeError);
+    py_expect_exception!(py, inst, "inst.method(1, a=2)", TypeError);
+    py_expect_exception!(py, inst, "inst.method(1, d=2)", TypeError);
+    py_expect_exception!(py, inst, "inst.static_method('a', 1)", TypeError);
+}
+
+#[cfg(all(Py_3_7, not(Py_LIMITED_API)))]
+#[test]
+fn fastcall_flags() {
+    use pyo3::class::methods::{PyMethodDefType, PyMethodsProtocolImpl};
+
+    let methods = <FastCall as PyMethodsProtocolImpl>::py_methods();
+    for name in ["method", "class_method", "static_method"].iter() {
+        let def = methods
+            .iter()
+            .filter_map(|def| match *def {
+                PyMethodDefType::Method(ref def)
+                | PyMethodDefType::Class(ref def)
+                | PyMethodDefType::Static(ref def) => Some(def),
+                _ => None,
+            })
+            .find(|def| def.ml_name == *name)
+            .unwrap();
+        let flags = def.as_method_def().ml_flags;
+        assert!(flags & pyo3::ffi::METH_FASTCALL != 0, "{} is not fastcall", name);
+        assert!(flags & pyo3::ffi::METH_VARARGS == 0, "{} is not fastcall", name);
+    }
+}
+
+#[pyclass]
+struct ArgSpec {
+    token: PyToken,
+}
+
+#[pymethods]
+impl ArgSpec {
+    #[args(a, "/", type_(name = "type", default = "u8::max_value()"))]
+    fn pos_only(&self, a: i32, b: i32, type_: u8) -> PyResult<(i32, i32, u8)> {
+        Ok((a, b, type_))
+    }
+
+    #[args(items(default), "*", sep(name = "separator", default = "\"-\".to_owned()"))]
+    fn join(&self, items: Vec<String>, sep: String) -> PyResult<String> {
+        Ok(items.join(&sep))
+    }
+}
+
+#[test]
+fn meth_arg_spec() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let inst = py.init(|t| ArgSpec { token: t }).unwrap();
+
+    py_assert!(py, inst, "inst.pos_only(1, 2) == (1, 2, 255)");
+    py_assert!(py, inst, "inst.pos_only(1, b=2, type=3) == (1, 2, 3)");
+    py_assert!(py, inst, "inst.pos_only.__text_signature__ == '($self, a, /, b, type=...)'");
+    py_assert!(py, inst, "inst.join() == ''");
+    py_assert!(py, inst, "inst.join(['a', 'b']) == 'a-b'");
+    py_assert!(py, inst, "inst.join(['a', 'b'], separator='+') == 'a+b'");
+    py_assert!(
+        py,
+        inst,
+        "inst.join.__text_signature__ == '($self, items=..., *, separator=...)'"
+    );
+
+    let errors = [
+        (
+            "inst.pos_only(a=1, b=2)",
+            "ArgSpec.pos_only() got some positional-only arguments passed as keyword arguments: 'a'",
+        ),
+        (
+            "inst.pos_only(1)",
+            "ArgSpec.pos_only() missing required argument 'b' (pos 2)",
+        ),
+        (
+            "inst.pos_only(1, 2, 3, 4)",
+            "ArgSpec.pos_only() takes at most 3 positional arguments (4 given)",
+        ),
+        (
+            "inst.pos_only(1, 2, type_=3)",
+            "'type_' is an invalid keyword argument for ArgSpec.pos_only()",
+        ),
+        (
+            "inst.join([], items=[])",
+            "argument for ArgSpec.join() given by name ('items') and position (1)",
+        ),
+    ];
+    for &(code, msg) in errors.iter() {
+        let d = PyDict::new(py);
+        d.set_item("inst", &inst).unwrap();
+        d.set_item("code", code).unwrap();
+        d.set_item("msg", msg).unwrap();
+        py.run(
+            "try:\n    exec(code)\nexcept TypeError as e:\n    assert str(e) == msg, str(e)\n\
+             else:\n    assert False, code",
+            None,
+            Some(d),
+        ).map_err(|e| e.print(py))
+            .expect(code);
+    }
+}
+
+#[pyclass]
+struct TypedVariadic {
+    token: PyToken,
+}
+
+#[pymethods]
+impl TypedVariadic {
+    #[args(values = "*", options = "**")]
+    fn sum(&self, start: f64, values: Vec<f64>, options: BTreeMap<String, i32>)
+           -> PyResult<(f64, Vec<(String, i32)>)> {
+        Ok((values.iter().fold(start, |acc, v| acc + v), options.into_iter().collect()))
+    }
+
+    #[args(args = "*", kwargs = "**")]
+    fn raw(&self, a: i32, args: &PyTuple, kwargs: Option<&PyDict>) -> PyResult<PyObject> {
+        Ok((a, args, kwargs).to_object(self.py()))
+    }
+
+    #[args(pair = "*")]
+    fn pair(&self, pair: (i32, String)) -> PyResult<String> {
+        Ok(format!("{}{}", pair.0, pair.1))
+    }
+}
+
+#[test]
+fn typed_variadic_args() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let inst = py.init(|t| TypedVariadic { token: t }).unwrap();
+
+    py_assert!(py, inst, "inst.sum(1) == (1.0, [])");
+    py_assert!(py, inst, "inst.sum(1, 2, 3.5, y=2, x=1) == (6.5, [('x', 1), ('y', 2)])");
+    py_assert!(py, inst, "inst.sum(start=1, x=1) == (1.0, [('x', 1)])");
+    py_assert!(py, inst, "inst.raw(1, 2, 3, b=4) == (1, (2, 3), {'b': 4})");
+    py_assert!(py, inst, "inst.raw(a=1, b=2) == (1, (), {'b': 2})");
+    py_assert!(py, inst, "inst.raw(a=1) == (1, (), None)");
+    py_assert!(py, inst, "inst.pair(1, 'x') == '1x'");
+    py_assert!(
+        py,
+        inst,
+        "inst.sum.__text_signature__ == '($self, start, *values, **options)'"
+    );
+
+    let errors = [
+        ("inst.sum(1, 2, 'a')", "TypedVariadic.sum() argument 'values' item 1: "),
+        ("inst.sum(1, x='a')", "TypedVariadic.sum() argument 'options' key 'x': "),
+        ("inst.pair('a', 'b')", "TypedVariadic.pair() argument 'pair': "),
+    ];
+    for &(code, msg) in errors.iter() {
+        let d = PyDict::new(py);
+        d.set_item("inst", &inst).unwrap();
+        d.set_item("code", code).unwrap();
+        d.set_item("msg", msg).unwrap();
+        py.run(
+            "try:\n    exec(code)\nexcept TypeError as e:\n    assert str(e).startswith(msg), str(e)\n\
+             else:\n    assert False, code",
+            None,
+            Some(d),
+        ).map_err(|e| e.print(py))
+            .expect(code);
+    }
+}
+
+#[pyclass]
+struct Overloaded {
+    token: PyToken,
+}
+
+#[pymethods]
+impl Overloaded {
+    /// Describes the value.
+    #[overload(name = "describe")]
+    fn describe_int(&self, value: i64) -> PyResult<String> {
+        if value < 0 {
+            return Err(exc::ValueError::new("negative value"));
+        }
+        Ok(format!("int {}", value))
+    }
+
+    #[overload(name = "describe")]
+    fn describe_str(&self, value: &str) -> PyResult<String> {
+        Ok(format!("str {}", value))
+    }
+
+    #[overload(name = "describe")]
+    fn describe_list(&self, value: Vec<i64>, sep: &str) -> PyResult<String> {
+        let items: Vec<String> = value.iter().map(|v| v.to_string()).collect();
+        Ok(items.join(sep))
+    }
+
+    #[overload(name = "describe")]
+    fn describe_nothing(&self) -> PyResult<&'static str> {
+        Ok("nothing")
+    }
+}
+
+#[test]
+fn overloaded_method() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let inst = py.init(|t| Overloaded { token: t }).unwrap();
+
+    py_assert!(py, inst, "inst.describe(1) == 'int 1'");
+    py_assert!(py, inst, "inst.describe('a') == 'str a'");
+    py_assert!(py, inst, "inst.describe(value='a') == 'str a'");
+    py_assert!(py, inst, "inst.describe([1, 2], '-') == '1-2'");
+    py_assert!(py, inst, "inst.describe() == 'nothing'");
+    py_assert!(py, inst, "inst.describe.__doc__ == 'Describes the value.'");
+    py_expect_exception!(py, inst, "inst.describe(-1)", ValueError);
+    // only a `TypeError` tries the next signature
+    py_expect_exception!(py, inst, "inst.describe(2 ** 70)", OverflowError);
+    py_run!(
+        py,
+        inst,
+        r#"
+try:
+    inst.describe(1.5, 2)
+except TypeError as e:
+    assert str(e) == """Overloaded.describe() arguments do not match any of the signatures:
+    describe(value: int)
+    describe(value: str)
+    describe(value: List[int], sep: str)
+    describe()""", str(e)
+else:
+    assert False
+"#
+    );
+}
+
+/// Class doc
+#[pyclass]
+struct MethSignature {
+    token: PyToken,
+}
+
+#[pymethods]
+impl MethSignature {
+    #[new]
+    fn __new__(obj: &PyRawObject, _a: i32, _b: Option<i32>) -> PyResult<()> {
+        obj.init(|t| MethSignature { token: t })
+    }
+
+    /// Method doc
+    #[args(num = "10", flag = "true", name = "\"x\"", items = "Vec::new()")]
+    fn defaults(&self, num: i32, flag: bool, name: &str, items: Vec<i32>) -> PyResult<()> {
+        let _ = (num, flag, name, items);
+        Ok(())
+    }
+
+    #[args("*", test = 10)]
+    fn kw_only(&self, a: i32, test: i32) -> PyResult<i32> {
+        Ok(a + test)
+    }
+
+    #[args(args = "*", kwargs = "**")]
+    fn var_args(&self, py: Python, args: &PyTuple, kwargs: Option<&PyDict>) -> PyResult<()> {
+        let _ = (py, args, kwargs);
+        Ok(())
+    }
+
+    #[classmethod]
+    fn class_method(_cls: &PyType, x: i32) -> PyResult<i32> {
+        Ok(x)
+    }
+
+    #[staticmethod]
+    fn static_method(x: i32) -> PyResult<i32> {
+        Ok(x)
+    }
+}
+
+#[cfg(Py_3)]
+#[test]
+fn meth_signature() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let inst = py.init(|t| MethSignature { token: t }).unwrap();
+    let typeobj = py.get_type::<MethSignature>();
+
+    py_assert!(py, typeobj, "typeobj.__text_signature__ == '(_a, _b=None)'");
+    py_assert!(py, typeobj, "typeobj.__doc__ == 'Class doc'");
+    py_assert!(
+        py,
+        inst,
+        "inst.defaults.__text_signature__ == '($self, num=10, flag=True, name=\"x\", items=...)'"
+    );
+    py_assert!(py, inst, "inst.defaults.__doc__ == 'Method doc'");
+    py_assert!(py, inst, "inst.kw_only.__text_signature__ == '($self, a, *, test=10)'");
+    py_assert!(
+        py,
+        inst,
+        "inst.var_args.__text_signature__ == '($self, *args, **kwargs)'"
+    );
+    py_assert!(py, inst, "inst.class_method.__text_signature__ == '($cls, x)'");
+    py_assert!(py, inst, "inst.static_method.__text_signature__ == '(x)'");
+
+    py_run!(
+        py,
+        inst,
+        r#"
+import inspect
+assert str(inspect.signature(inst.kw_only)) == '(a, *, test=10)'
+assert str(inspect.signature(type(inst).class_method)) == '(x)'
+assert str(inspect.signature(type(inst))) == '(_a, _b=None)'
+"#
+    );
+}
+
+#[pyclass]
+struct ClassAttributes {
+    value: i32,
+    token: PyToken,
+}
+
+#[pymethods]
+impl ClassAttributes {
+    #[classattr]
+    #[allow(non_snake_case)]
+    fn VERSION() -> PyResult<&'static str> {
+        Ok("1.0")
+    }
+
+    #[classattr]
+    #[allow(non_snake_case)]
+    fn DEFAULT(py: Python) -> PyResult<Py<ClassAttributes>> {
+        Py::new(py, |t| ClassAttributes { value: 42, token: t })
+    }
+
+    #[getter]
+    fn value(&self) -> PyResult<i32> {
+        Ok(self.value)
+    }
+}
+
+#[test]
+fn class_attributes() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let typeobj = py.get_type::<ClassAttributes>();
+    py_assert!(py, typeobj, "typeobj.VERSION == '1.0'");
+    py_assert!(py, typeobj, "typeobj.DEFAULT.value == 42");
+    py_assert!(py, typeobj, "isinstance(typeobj.DEFAULT, typeobj)");
+}
@@ -1,9 +1,12 @@
 #![feature(proc_macro, specialization)]
 
+#[macro_use]
 extern crate pyo3;
 
+use pyo3::ffi;
 use pyo3::prelude::*;
-use pyo3::py::{class, modinit};
+use pyo3::py::{class, modinit, submodule};
+use pyo3::typeob::PyTypeInfo;
 
 #[class]
 struct EmptyClass {}
@@ -26,6 +29,11 @@ fn init_mod(py: Python, m: &PyModule) -> PyResult<()> {
         return Ok(42);
     }
 
+    #[pyfn(m, "internal", hidden)]
+    fn internal() -> PyResult<usize> {
+        return Ok(0);
+    }
+
     m.add_class::<EmptyClass>();
 
     m.add("foo", "bar");
@@ -58,6 +66,11 @@ fn test_module_with_functions() {
         None,
         Some(d),
     ).unwrap();
+    py.run(
+        "assert module_with_functions.sum_as_string.__text_signature__ == '(a, b)'",
+        None,
+        Some(d),
+    ).unwrap();
     py.run("assert module_with_functions.foo == 'bar'", None, Some(d))
         .unwrap();
     py.run(
@@ -65,4 +78,411 @@ fn test_module_with_functions() {
         None,
         Some(d),
     ).unwrap();
+    py.run(
+        "assert module_with_functions.__all__ == \
+            ['sum_as_string', 'no_parameters', 'EmptyClass', 'foo']",
+        None,
+        Some(d),
+    ).unwrap();
+    py.run("assert module_with_functions.internal() == 0", None, Some(d))
+        .unwrap();
+}
+
+#[class]
+struct SubmoduleClass {}
+
+#[submodule]
+fn init_submodule(py: Python, m: &PyModule) -> PyResult<()> {
+    #[pyfn(m, "double")]
+    fn double(x: i64) -> PyResult<i64> {
+        Ok(x * 2)
+    }
+
+    m.add_class::<SubmoduleClass>()?;
+    Ok(())
+}
+
+#[modinit(supermodule)]
+fn init_supermodule(py: Python, m: &PyModule) -> PyResult<()> {
+    let sub = PyModule::new(py, "submodule")?;
+    m.add_submodule(sub)?;
+    init_submodule(py, sub)?;
+    Ok(())
+}
+
+#[test]
+#[cfg(Py_3)]
+fn test_submodule() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let supermodule = unsafe { PyObject::from_owned_ptr(py, PyInit_supermodule()) };
+    let sys = py.import("sys").unwrap();
+    sys.get("modules").unwrap().set_item("supermodule", supermodule).unwrap();
+
+    py.run(
+        r#"
+import supermodule
+import supermodule.submodule
+from supermodule.submodule import double
+assert supermodule.submodule.__name__ == 'supermodule.submodule'
+assert supermodule.submodule.__package__ == 'supermodule'
+assert double(2) == 4
+assert supermodule.submodule.SubmoduleClass.__module__ == 'supermodule.submodule'
+"#,
+        None,
+        None,
+    ).unwrap();
+}
+
+#[cfg(Py_3)]
+#[derive(Default)]
+struct ModuleCounter {
+    count: usize,
+    last: Option<PyObject>,
+}
+
+#[cfg(Py_3)]
+impl PyModuleState for ModuleCounter {
+    fn traverse(&self, visit: PyVisit) -> Result<(), PyTraverseError> {
+        if let Some(ref last) = self.last {
+            visit.call(last)?;
+        }
+        Ok(())
+    }
+
+    fn clear(&mut self) {
+        self.last = None;
+    }
+}
+
+#[cfg(Py_3)]
+#[modinit(module_with_state, state = "ModuleCounter")]
+fn init_module_with_state(py: Python, m: &PyModule) -> PyResult<()> {
+    let mut state = m.state_mut::<ModuleCounter>()?;
+    state.count += 1;
+    state.last = Some(PyList::empty(py).to_object(py));
+    m.add("count", state.count)?;
+    Ok(())
+}
+
+#[cfg(Py_3)]
+fn create_module_with_state(py: Python) -> &PyModule {
+    let machinery = py.import("importlib.machinery").unwrap();
+    let spec = machinery
+        .call1("ModuleSpec", ("module_with_state", py.None()))
+        .unwrap();
+    unsafe {
+        let def = PyInit_module_with_state() as *mut ffi::PyModuleDef;
+        let module: &PyModule =
+            py.from_owned_ptr_or_err(ffi::PyModule_FromDefAndSpec(def, spec.as_ptr())).unwrap();
+        assert_eq!(ffi::PyModule_ExecDef(module.as_ptr(), def), 0);
+        module
+    }
+}
+
+#[test]
+#[cfg(Py_3)]
+fn test_module_state() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let first = create_module_with_state(py);
+    let second = create_module_with_state(py);
+    first.state_mut::<ModuleCounter>().unwrap().count += 10;
+
+    assert_eq!(first.state::<ModuleCounter>().unwrap().count, 11);
+    assert_eq!(second.state::<ModuleCounter>().unwrap().count, 1);
+
+    // the state can not be borrowed mutably while it is borrowed
+    {
+        let state = first.state::<ModuleCounter>().unwrap();
+        let err = first.state_mut::<ModuleCounter>().err().unwrap();
+        assert!(err.is_instance::<exc::RuntimeError>(py));
+        assert_eq!(first.state::<ModuleCounter>().unwrap().count, state.count);
+
+        let mut other = second.state_mut::<ModuleCounter>().unwrap();
+        assert!(second.state::<ModuleCounter>().is_err());
+        assert!(second.state_mut::<ModuleCounter>().is_err());
+        other.count += 1;
+    }
+    assert_eq!(second.state_mut::<ModuleCounter>().unwrap().count, 2);
+    assert_eq!(second.get("count").unwrap().extract::<usize>().unwrap(), 1);
+
+    #[derive(Default)]
+    struct Other;
+    impl PyModuleState for Other {}
+
+    assert!(first.state::<Other>().is_err());
+    assert!(PyModule::new(py, "plain").unwrap().state::<ModuleCounter>().is_err());
+}
+
+#[cfg(Py_3)]
+#[class(per_interpreter)]
+struct InterpreterClass {
+    token: PyToken,
+}
+
+#[cfg(Py_3)]
+#[modinit(module_with_types, multi_phase)]
+fn init_module_with_types(_py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_class::<InterpreterClass>()
+}
+
+#[cfg(Py_3)]
+fn create_module_with_types(py: Python) -> &PyModule {
+    let machinery = py.import("importlib.machinery").unwrap();
+    let spec = machinery
+        .call1("ModuleSpec", ("module_with_types", py.None()))
+        .unwrap();
+    unsafe {
+        let def = PyInit_module_with_types() as *mut ffi::PyModuleDef;
+        let module: &PyModule =
+            py.from_owned_ptr_or_err(ffi::PyModule_FromDefAndSpec(def, spec.as_ptr())).unwrap();
+        let modules = py.import("sys").unwrap().get("modules").unwrap();
+        modules.set_item("module_with_types", module).unwrap();
+        assert_eq!(ffi::PyModule_ExecDef(module.as_ptr(), def), 0);
+        module
+    }
+}
+
+#[test]
+#[cfg(Py_3)]
+fn test_per_interpreter_class() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    let d = PyDict::new(py);
+
+    {
+        let _pool = pyo3::GILPool::new();
+        let first = create_module_with_types(py);
+        let ty = first.get("InterpreterClass").unwrap();
+        let obj = py.init(|t| InterpreterClass { token: t }).unwrap();
+        assert_eq!(obj.as_ref(py).get_type().as_ptr(), ty.as_ptr());
+
+        // a new module object gets a new type, i.e. in another interpreter
+        let second = create_module_with_types(py);
+        let other = second.get("InterpreterClass").unwrap();
+        assert_ne!(ty.as_ptr(), other.as_ptr());
+        let obj = py.init(|t| InterpreterClass { token: t }).unwrap();
+        assert_eq!(obj.as_ref(py).get_type().as_ptr(), other.as_ptr());
+
+        let weakref = py.import("weakref").unwrap();
+        d.set_item("first", weakref.call1("ref", (ty,)).unwrap()).unwrap();
+        d.set_item("second", weakref.call1("ref", (other,)).unwrap()).unwrap();
+    }
+
+    // the first module is released with its type, the second one is in sys.modules
+    py.run(
+        r#"
+import gc
+gc.collect()
+assert first() is None
+assert second() is not None and second().__module__ == 'module_with_types'
+"#,
+        None,
+        Some(d),
+    ).unwrap();
+}
+
+#[test]
+#[cfg(Py_3)]
+fn test_per_interpreter_class_subinterpreter() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let main = create_module_with_types(py);
+    let main_type = main.get("InterpreterClass").unwrap();
+
+    unsafe {
+        let main_state = ffi::PyThreadState_Get();
+        let sub_state = ffi::Py_NewInterpreter();
+        assert!(!sub_state.is_null());
+        {
+            let _pool = pyo3::GILPool::new();
+            let sub = create_module_with_types(py);
+            let sub_type = sub.get("InterpreterClass").unwrap();
+            assert_ne!(sub_type.as_ptr(), main_type.as_ptr());
+
+            let obj = py.init(|t| InterpreterClass { token: t }).unwrap();
+            assert_eq!(obj.as_ref(py).get_type().as_ptr(), sub_type.as_ptr());
+            assert!(py.is_instance::<InterpreterClass, _>(&obj).unwrap());
+        }
+        // the module of the sub-interpreter is released with its type
+        ffi::Py_EndInterpreter(sub_state);
+        ffi::PyThreadState_Swap(main_state);
+    }
+
+    let obj = py.init(|t| InterpreterClass { token: t }).unwrap();
+    assert_eq!(obj.as_ref(py).get_type().as_ptr(), main_type.as_ptr());
+    assert!(InterpreterClass::is_instance(obj.as_ptr()));
+
+    // the python subclasses are resolved from the type of the instance
+    let d = PyDict::new(py);
+    d.set_item("cls", main_type).unwrap();
+    py.run("class Sub(cls): pass\nobj = Sub()", None, Some(d)).unwrap();
+    let sub_obj = d.get_item("obj").unwrap();
+    assert!(InterpreterClass::is_instance(sub_obj.as_ptr()));
+    assert!(!InterpreterClass::is_exact_instance(sub_obj.as_ptr()));
+}
+
+#[test]
+#[cfg(Py_3)]
+fn test_lazy_attributes() {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = calls.clone();
+    let m = PyModule::new(py, "lazy").unwrap();
+    m.add_lazy("table", move |py| {
+        counter.fetch_add(1, Ordering::SeqCst);
+        Ok(PyList::new(py, &[1, 2, 3]).to_object(py))
+    }).unwrap();
+    m.add_lazy("answer", |_| Ok(42)).unwrap();
+    m.add("eager", "value").unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+    let d = PyDict::new(py);
+    d.set_item("lazy", m).unwrap();
+    py.run(
+        r#"
+assert 'table' in dir(lazy) and 'answer' in dir(lazy) and 'eager' in dir(lazy)
+assert 'table' not in lazy.__dict__
+assert lazy.table == [1, 2, 3]
+assert lazy.table is lazy.table
+assert lazy.__dict__['table'] == [1, 2, 3]
+assert getattr(lazy, 'answer') == 42
+assert not hasattr(lazy, 'missing')
+"#,
+        None,
+        Some(d),
+    ).unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    let err = py.run("lazy.missing", None, Some(d)).unwrap_err();
+    assert!(err.is_instance::<exc::AttributeError>(py));
+}
+
+#[test]
+#[cfg(Py_3)]
+fn test_lazy_attributes_chain() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let m = PyModule::new(py, "lazy_chain").unwrap();
+    let d = PyDict::new(py);
+    d.set_item("lazy", m).unwrap();
+    py.run(
+        r#"
+lazy.__getattr__ = lambda name: 'fallback ' + name
+lazy.__dir__ = lambda: ['custom']
+"#,
+        None,
+        Some(d),
+    ).unwrap();
+    m.add_lazy("answer", |_| Ok(42)).unwrap();
+    m.add_lazy("other", |_| Ok(1)).unwrap();
+
+    py.run(
+        r#"
+assert lazy.answer == 42 and lazy.other == 1
+assert lazy.missing == 'fallback missing'
+assert dir(lazy) == ['custom']
+assert not [name for name in lazy.__dict__ if 'lazy' in name]
+"#,
+        None,
+        Some(d),
+    ).unwrap();
+}
+
+#[test]
+fn test_exports() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let m = PyModule::new(py, "exports").unwrap();
+    m.add_function(wrap_function!(functions::double_value)).unwrap();
+    m.add_class::<EmptyClass>().unwrap();
+    m.add("VERSION", "1.0").unwrap();
+    m.add("_private", 1).unwrap();
+    m.setattr("hidden", 2).unwrap();
+    m.add_submodule(PyModule::new(py, "sub").unwrap()).unwrap();
+    m.add("VERSION", "1.1").unwrap();
+
+    let exports = m.exports().unwrap();
+    let names: Vec<&str> = exports.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, ["double", "EmptyClass", "VERSION", "sub"]);
+    let kinds: Vec<ExportKind> = exports.iter().map(|e| e.kind).collect();
+    assert_eq!(
+        kinds,
+        [
+            ExportKind::Function,
+            ExportKind::Class,
+            ExportKind::Constant,
+            ExportKind::Module
+        ]
+    );
+    assert_eq!(exports[0].doc.as_ref().unwrap().trim(), "Doubles the value.");
+    assert_eq!(exports[2].doc, None);
+
+    let all: Vec<String> = m.get("__all__").unwrap().extract().unwrap();
+    assert_eq!(all, ["double", "EmptyClass", "VERSION", "sub"]);
+}
+
+#[test]
+fn test_exports_tuple_all() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let m = PyModule::new(py, "exports_tuple").unwrap();
+    m.add("first", 1).unwrap();
+    m.setattr("__all__", ("first",)).unwrap();
+    let names: Vec<String> = m.exports().unwrap().into_iter().map(|e| e.name).collect();
+    assert_eq!(names, ["first"]);
+
+    m.add("second", 2).unwrap();
+    let all: Vec<String> = m.get("__all__").unwrap().extract().unwrap();
+    assert_eq!(all, ["first", "second"]);
+}
+
+mod functions {
+    use pyo3::prelude::*;
+    use pyo3::py::function;
+
+    /// Doubles the value.
+    #[function(name = "double", x = "1")]
+    pub fn double_value(x: i64) -> PyResult<i64> {
+        Ok(x * 2)
+    }
+}
+
+#[test]
+fn test_add_function() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let first = PyModule::new(py, "first").unwrap();
+    let second = PyModule::new(py, "second").unwrap();
+    first.add_function(wrap_function!(functions::double_value)).unwrap();
+    second.add_function(wrap_function!(functions::double_value)).unwrap();
+
+    let d = PyDict::new(py);
+    d.set_item("first", first).unwrap();
+    d.set_item("second", second).unwrap();
+    let double = wrap_function!(functions::double_value).create(py, None).unwrap();
+    d.set_item("double", double).unwrap();
+    py.run(
+        r#"
+assert first.double(2) == 4 and second.double(x=3) == 6 and double() == 2
+assert first.double.__module__ == 'first' and second.double.__module__ == 'second'
+assert first.double.__name__ == 'double'
+assert first.double.__doc__.strip() == 'Doubles the value.'
+"#,
+        None,
+        Some(d),
+    ).unwrap();
 }
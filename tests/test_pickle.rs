@@ -0,0 +1,151 @@
+#![feature(proc_macro, specialization)]
+
+extern crate pyo3;
+#[cfg(feature = "serde-pickle")]
+#[macro_use]
+extern crate serde_derive;
+
+use pyo3::prelude::*;
+
+use pyo3::py::class as pyclass;
+use pyo3::py::methods as pymethods;
+
+#[macro_use]
+mod common;
+
+#[pyclass(pickle, module = "test_pickle")]
+struct Point {
+    x: i32,
+    y: i32,
+    token: PyToken,
+}
+
+#[pymethods]
+impl Point {
+    #[new]
+    fn __new__(obj: &PyRawObject, x: i32) -> PyResult<()> {
+        obj.init(|t| Point { x, y: 0, token: t })
+    }
+
+    fn __getnewargs__(&self) -> PyResult<(i32,)> {
+        Ok((self.x,))
+    }
+
+    fn __getstate__(&self) -> PyResult<i32> {
+        Ok(self.y)
+    }
+
+    fn __setstate__(&mut self, state: i32) -> PyResult<()> {
+        self.y = state;
+        Ok(())
+    }
+}
+
+#[pyclass(pickle, module = "test_pickle")]
+struct Empty {
+    token: PyToken,
+}
+
+#[pymethods]
+impl Empty {
+    #[new]
+    fn __new__(obj: &PyRawObject) -> PyResult<()> {
+        obj.init(|t| Empty { token: t })
+    }
+}
+
+fn register_module(py: Python) {
+    let m = PyModule::new(py, "test_pickle").unwrap();
+    m.add_class::<Point>().unwrap();
+    m.add_class::<Empty>().unwrap();
+    py_run!(py, m, "import sys; sys.modules['test_pickle'] = m");
+}
+
+#[test]
+fn module_qualified_name() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let typeobj = py.get_type::<Point>();
+    py_assert!(py, typeobj, "typeobj.__module__ == 'test_pickle'");
+    py_assert!(py, typeobj, "typeobj.__name__ == 'Point'");
+}
+
+#[test]
+fn pickle_with_user_methods() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    register_module(py);
+
+    let inst = py.init(|t| Point { x: 1, y: 2, token: t }).unwrap();
+    py_run!(
+        py,
+        inst,
+        r#"
+import pickle
+for proto in range(pickle.HIGHEST_PROTOCOL + 1):
+    obj = pickle.loads(pickle.dumps(inst, proto))
+    assert type(obj) is type(inst)
+    assert obj.__getnewargs__() == (1,)
+    assert obj.__getstate__() == 2
+"#
+    );
+    py_run!(py, inst, "import copy; assert copy.copy(inst).__getstate__() == 2");
+}
+
+#[test]
+fn pickle_without_state() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+    register_module(py);
+
+    let inst = py.init(|t| Empty { token: t }).unwrap();
+    py_assert!(py, inst, "inst.__reduce__() == (type(inst), ())");
+    py_run!(py, inst, "import pickle; assert type(pickle.loads(pickle.dumps(inst))) is type(inst)");
+}
+
+#[cfg(feature = "serde-pickle")]
+#[pyclass(pickle, module = "test_pickle_serde")]
+#[derive(Serialize, Deserialize)]
+struct Settings {
+    name: String,
+    values: Vec<i32>,
+    token: PyToken,
+}
+
+#[cfg(feature = "serde-pickle")]
+#[pymethods]
+impl Settings {
+    #[new]
+    fn __new__(obj: &PyRawObject) -> PyResult<()> {
+        obj.init(|t| Settings {
+            name: String::new(),
+            values: Vec::new(),
+            token: t,
+        })
+    }
+}
+
+#[cfg(feature = "serde-pickle")]
+#[test]
+fn pickle_with_serde() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let m = PyModule::new(py, "test_pickle_serde").unwrap();
+    m.add_class::<Settings>().unwrap();
+    py_run!(py, m, "import sys; sys.modules['test_pickle_serde'] = m");
+
+    let inst = py.init(|t| Settings {
+        name: "test".to_string(),
+        values: vec![1, 2, 3],
+        token: t,
+    }).unwrap();
+    let d = PyDict::new(py);
+    d.set_item("inst", &inst).unwrap();
+    let obj = py.eval("__import__('pickle').loads(__import__('pickle').dumps(inst))", None, Some(d))
+        .unwrap();
+    let obj = obj.cast_as::<Settings>().unwrap();
+    assert_eq!(obj.name, "test");
+    assert_eq!(obj.values, vec![1, 2, 3]);
+}
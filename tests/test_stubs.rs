@@ -0,0 +1,145 @@
+#![feature(proc_macro, specialization)]
+
+extern crate pyo3;
+
+use std::collections::HashMap;
+
+use pyo3::prelude::*;
+use pyo3::py::{class, methods, modinit, proto};
+use pyo3::stubs;
+
+/// A point on the plane.
+#[class]
+struct Point {
+    #[prop(get, set)]
+    x: i32,
+    #[prop(get)]
+    #[stub(ty = "List[float]")]
+    coords: PyObject,
+    token: PyToken,
+}
+
+#[methods]
+impl Point {
+    #[new]
+    fn __new__(obj: &PyRawObject, x: i32) -> PyResult<()> {
+        obj.init(|t| Point {
+            x,
+            coords: t.py().None(),
+            token: t,
+        })
+    }
+
+    /// Distance to the other point.
+    fn distance(&self, other: &Point) -> PyResult<f64> {
+        Ok(f64::from((self.x - other.x).abs()))
+    }
+
+    #[args(scale = "1.5", names = "*")]
+    fn scaled(&self, scale: f64, names: &PyTuple) -> PyResult<Vec<f64>> {
+        Ok(vec![f64::from(self.x) * scale; names.len()])
+    }
+
+    #[stub(values = "Dict[str, int]", returns = "Point")]
+    fn update(&self, values: &PyDict) -> PyResult<PyObject> {
+        Ok(values.to_object(self.py()))
+    }
+
+    #[getter]
+    fn get_label(&self) -> PyResult<Option<String>> {
+        Ok(None)
+    }
+
+    #[staticmethod]
+    fn origin() -> PyResult<HashMap<String, i32>> {
+        Ok(HashMap::new())
+    }
+}
+
+#[proto]
+impl PyObjectProtocol for Point {
+    fn __repr__(&self) -> PyResult<String> {
+        Ok(format!("Point({})", self.x))
+    }
+}
+
+#[modinit(stub_module)]
+fn init_mod(py: Python, m: &PyModule) -> PyResult<()> {
+    /// Adds the numbers.
+    #[pyfn(m, "add", b = "10")]
+    fn add(a: i64, b: i64) -> PyResult<i64> {
+        Ok(a + b)
+    }
+
+    #[pyfn(m, "first")]
+    fn first(items: Vec<String>, default: Option<&str>) -> PyResult<(bool, String)> {
+        Ok((true, items.get(0).map(|s| s.clone()).or(default.map(|s| s.to_owned())).unwrap()))
+    }
+
+    m.add_class::<Point>()?;
+    m.add("VERSION", "1.0")?;
+    m.add("LIMIT", 42)?;
+
+    Ok(())
+}
+
+#[test]
+#[cfg(Py_3)]
+fn module_stub_file() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let m: &PyModule = unsafe { py.from_owned_ptr(PyInit_stub_module()) };
+    let stub = stubs::module_stub(py, m).unwrap();
+
+    let expected = [
+        "from typing import Any, Dict, FrozenSet, List, Optional, Set, Tuple",
+        "def add(a: int, b: int = 10) -> int:\n    \"\"\"Adds the numbers.\"\"\"\n    ...",
+        "def first(items: List[str], default: Optional[str] = None) -> Tuple[bool, str]: ...",
+        "class Point:\n    \"\"\"A point on the plane.\"\"\"",
+        "    @property\n    def x(self) -> int: ...\n    @x.setter\n    def x(self, value: int) -> None: ...",
+        "    @property\n    def coords(self) -> List[float]: ...",
+        "    def __new__(cls, x: int) -> Point: ...",
+        "    def distance(self, other: Point) -> float:\n        \"\"\"Distance to the other point.\"\"\"",
+        "    def scaled(self, scale: float = 1.5, *names: Any) -> List[float]: ...",
+        "    def update(self, values: Dict[str, int]) -> Point: ...",
+        "    @property\n    def label(self) -> Optional[str]: ...",
+        "    @staticmethod\n    def origin() -> Dict[str, int]: ...",
+        "    def __repr__(self) -> str: ...",
+        "VERSION: str",
+        "LIMIT: int",
+    ];
+    for line in expected.iter() {
+        assert!(stub.contains(line), "{:?} not found in:\n{}", line, stub);
+    }
+    assert!(!stub.contains("__doc__"));
+
+    // the stub file is valid python
+    let d = PyDict::new(py);
+    d.set_item("stub", &stub).unwrap();
+    py.run("compile(stub, 'stub_module.pyi', 'exec')", None, Some(d))
+        .unwrap();
+}
+
+#[test]
+#[cfg(Py_3)]
+fn module_stub_same_name() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let m: &PyModule = unsafe { py.from_owned_ptr(PyInit_stub_module()) };
+    assert!(stubs::module_stub(py, m).unwrap().contains("def add(a: int"));
+
+    // the registered items belong to the module object, not to its name
+    let other = PyModule::new(py, "stub_module").unwrap();
+    other.add("add", 1).unwrap();
+    other.setattr("__doc__", "Says \"hi\"").unwrap();
+    let stub = stubs::module_stub(py, other).unwrap();
+    assert!(stub.contains("add: int"), "{}", stub);
+    assert!(stub.contains("\"\"\"Says \"hi\\\"\"\"\""), "{}", stub);
+
+    let d = PyDict::new(py);
+    d.set_item("stub", &stub).unwrap();
+    py.run("compile(stub, 'stub_module.pyi', 'exec')", None, Some(d))
+        .unwrap();
+}
@@ -453,3 +453,121 @@ fn test_cls_impl() {
     py.run("assert ob[100:200:1] == 'slice'", None, Some(d))
         .unwrap();
 }
+
+#[pyclass]
+struct SliceSequence {
+    items: Vec<i32>,
+    token: PyToken,
+}
+
+#[pyproto]
+impl<'p> PySequenceProtocol<'p> for SliceSequence {
+    fn __len__(&self) -> PyResult<usize> {
+        Ok(self.items.len())
+    }
+
+    fn __getitem__(&self, idx: SliceOrIndex<'p>) -> PyResult<PyObject> {
+        match idx {
+            SliceOrIndex::Index(idx) => match self.items.get(idx as usize) {
+                Some(item) => Ok(item.into_object(self.py())),
+                None => Err(PyErr::new::<exc::IndexError, NoArgs>(NoArgs)),
+            },
+            SliceOrIndex::Slice(slice) => {
+                let indices = slice.indices(self.items.len() as _)?;
+                let items: Vec<i32> = (0..indices.slicelength)
+                    .map(|i| self.items[(indices.start + i * indices.step) as usize])
+                    .collect();
+                Ok(items.into_object(self.py()))
+            }
+        }
+    }
+
+    fn __setitem__(&mut self, idx: SliceOrIndex<'p>, value: &'p PyObjectRef) -> PyResult<()> {
+        let indices = idx.indices(self.items.len() as _)?;
+        let values: Vec<i32> = match idx {
+            SliceOrIndex::Index(_) => vec![value.extract()?],
+            SliceOrIndex::Slice(_) => value.extract()?,
+        };
+        if values.len() as isize != indices.slicelength {
+            return Err(PyErr::new::<exc::ValueError, _>("size mismatch"));
+        }
+        for (i, v) in values.into_iter().enumerate() {
+            self.items[(indices.start + i as isize * indices.step) as usize] = v;
+        }
+        Ok(())
+    }
+
+    fn __delitem__(&mut self, idx: SliceOrIndex<'p>) -> PyResult<()> {
+        let indices = idx.indices(self.items.len() as _)?;
+        let mut removed = 0;
+        for i in 0..indices.slicelength {
+            let pos = indices.start + i * indices.step;
+            let pos = if indices.step > 0 { pos - removed } else { pos };
+            self.items.remove(pos as usize);
+            removed += 1;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn sequence_slices() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let c = py.init(|t| SliceSequence {
+        items: (0..10).collect(),
+        token: t,
+    }).unwrap();
+    py_assert!(py, c, "c[3] == 3");
+    py_assert!(py, c, "c[-1] == 9");
+    py_assert!(py, c, "c[1:10:2] == [1, 3, 5, 7, 9]");
+    py_assert!(py, c, "c[::-3] == [9, 6, 3, 0]");
+    py_expect_exception!(py, c, "c[10]", IndexError);
+    py_expect_exception!(py, c, "c['abc']", TypeError);
+
+    py_run!(py, c, "c[0] = 10");
+    py_run!(py, c, "c[-1] = 90");
+    py_run!(py, c, "c[1:3] = [11, 12]");
+    py_assert!(py, c, "c[:] == [10, 11, 12, 3, 4, 5, 6, 7, 8, 90]");
+    py_expect_exception!(py, c, "c[1:3] = [1]", ValueError);
+
+    py_run!(py, c, "del c[::2]");
+    py_assert!(py, c, "c[:] == [11, 3, 5, 7, 90]");
+    py_run!(py, c, "del c[-1]");
+    py_assert!(py, c, "c[:] == [11, 3, 5, 7]");
+}
+
+#[pyclass]
+struct SetOnlySequence {
+    items: Vec<i32>,
+    token: PyToken,
+}
+
+#[pyproto]
+impl<'p> PySequenceProtocol<'p> for SetOnlySequence {
+    fn __len__(&self) -> PyResult<usize> {
+        Ok(self.items.len())
+    }
+
+    fn __setitem__(&mut self, idx: SliceOrIndex<'p>, value: i32) -> PyResult<()> {
+        let indices = idx.indices(self.items.len() as _)?;
+        for i in 0..indices.slicelength {
+            self.items[(indices.start + i * indices.step) as usize] = value;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn sequence_unsupported_slice_deletion() {
+    let gil = Python::acquire_gil();
+    let py = gil.python();
+
+    let c = py.init(|t| SetOnlySequence {
+        items: (0..4).collect(),
+        token: t,
+    }).unwrap();
+    py_run!(py, c, "c[1:3] = 7");
+    py_expect_exception!(py, c, "del c[1:3]", TypeError);
+}